@@ -1,27 +1,29 @@
-#![feature(test)]
-
-extern crate test;
-extern crate serde;
-#[macro_use]
-extern crate serde_derive;
-extern crate serde_bencode;
-
-use test::Bencher;
-use serde::Serialize;
+use divan::Bencher;
+use serde::{Deserialize, Serialize};
+use serde_bencode::de::{from_bytes, Deserializer};
 use serde_bencode::ser::Serializer;
-use serde_bencode::de::from_bytes;
+use serde_bencode::value::Value;
+use serde_bytes::ByteBuf;
+use serde_derive::{Deserialize, Serialize};
 
+// Report allocations next to timings; most optimizations here are about avoiding them.
+#[global_allocator]
+static ALLOC: divan::AllocProfiler = divan::AllocProfiler::system();
+
+fn main() {
+    divan::main();
+}
 
-#[bench]
-fn ser_de_simple(b: &mut Bencher) {
+#[divan::bench]
+fn ser_de_simple(b: Bencher) {
     #[derive(Serialize, Deserialize)]
     struct Fake {
         a: i64,
         b: i64,
     }
 
-    b.iter(|| {
-        let a = Fake {a: 2, b: 7};
+    b.bench(|| {
+        let a = Fake { a: 2, b: 7 };
         let mut ser = Serializer::new();
         a.serialize(&mut ser).unwrap();
         let b: Fake = from_bytes(ser.as_ref()).unwrap();
@@ -29,8 +31,8 @@ fn ser_de_simple(b: &mut Bencher) {
     });
 }
 
-#[bench]
-fn ser_de_nested(b: &mut Bencher) {
+#[divan::bench]
+fn ser_de_nested(b: Bencher) {
     #[derive(Serialize, Deserialize)]
     struct FakeA {
         a: i64,
@@ -43,11 +45,125 @@ fn ser_de_nested(b: &mut Bencher) {
         b: FakeA,
     }
 
-    b.iter(|| {
-        let a = FakeB {a: 2, b: FakeA {a: 7, b: 9}};
+    b.bench(|| {
+        let a = FakeB {
+            a: 2,
+            b: FakeA { a: 7, b: 9 },
+        };
         let mut ser = Serializer::new();
         a.serialize(&mut ser).unwrap();
         let b: FakeB = from_bytes(ser.as_ref()).unwrap();
         b
     });
 }
+
+#[divan::bench]
+fn de_skip_large_field(b: Bencher) {
+    #[derive(Deserialize)]
+    struct Torrent {
+        #[allow(dead_code)]
+        announce: String,
+    }
+
+    let pieces = vec![0u8; 1 << 20];
+    let mut input = b"d8:announce3:url4:infod6:pieces".to_vec();
+    input.extend_from_slice(format!("{}:", pieces.len()).as_bytes());
+    input.extend_from_slice(&pieces);
+    input.extend_from_slice(b"ee");
+
+    b.bench(|| from_bytes::<Torrent>(&input).unwrap());
+}
+
+#[divan::bench(args = [false, true])]
+fn de_large_byte_string(b: Bencher, from_reader: bool) {
+    #[derive(Deserialize)]
+    struct Info {
+        #[allow(dead_code)]
+        pieces: ByteBuf,
+    }
+
+    let pieces = vec![0u8; 8 << 20];
+    let mut input = b"d6:pieces".to_vec();
+    input.extend_from_slice(format!("{}:", pieces.len()).as_bytes());
+    input.extend_from_slice(&pieces);
+    input.push(b'e');
+
+    b.bench(|| {
+        if from_reader {
+            Info::deserialize(&mut Deserializer::new(&input[..])).unwrap()
+        } else {
+            from_bytes::<Info>(&input).unwrap()
+        }
+    });
+}
+
+#[divan::bench(args = [false, true])]
+fn de_large_torrent(b: Bencher, from_reader: bool) {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct File {
+        length: u64,
+        path: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Info {
+        files: Vec<File>,
+        name: String,
+        #[serde(rename = "piece length")]
+        piece_length: u64,
+        pieces: ByteBuf,
+    }
+
+    let input = large_torrent();
+    b.bench(|| {
+        if from_reader {
+            Info::deserialize(&mut Deserializer::new(&input[..])).unwrap()
+        } else {
+            from_bytes::<Info>(&input).unwrap()
+        }
+    });
+}
+
+// Most byte strings of a multi-file torrent are short keys and path components, which the
+// `smallvec` feature stores inline.
+#[divan::bench]
+fn de_large_torrent_value(b: Bencher) {
+    let input = large_torrent();
+    b.bench(|| from_bytes::<Value>(&input).unwrap());
+}
+
+/// The info dictionary of a torrent with 50 000 files.
+fn large_torrent() -> Vec<u8> {
+    let mut input = b"d5:filesl".to_vec();
+    for i in 0..50_000u64 {
+        let name = format!("file-{}.dat", i);
+        input.extend_from_slice(
+            format!(
+                "d6:lengthi{}e4:pathl3:dir{}:{}ee",
+                i * 4099,
+                name.len(),
+                name
+            )
+            .as_bytes(),
+        );
+    }
+    input.extend_from_slice(b"e4:name5:large12:piece lengthi262144e6:pieces");
+    let pieces = vec![0u8; 20 * 1000];
+    input.extend_from_slice(format!("{}:", pieces.len()).as_bytes());
+    input.extend_from_slice(&pieces);
+    input.push(b'e');
+    input
+}
+
+#[divan::bench]
+fn de_integer_list(b: Bencher) {
+    let mut input = b"l".to_vec();
+    for i in 0..100_000i64 {
+        input.extend_from_slice(format!("i{}e", i * 7919 - 300_000_000).as_bytes());
+    }
+    input.push(b'e');
+
+    b.bench(|| from_bytes::<Vec<i64>>(&input).unwrap());
+}
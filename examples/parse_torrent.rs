@@ -9,6 +9,7 @@ use serde_bytes::ByteBuf;
 use std::io::{self, Read};
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct Node(String, i64);
 
 #[derive(Debug, Deserialize)]
@@ -79,6 +80,8 @@ fn render_torrent(torrent: &Torrent) {
     println!("created by:\t{:?}", torrent.created_by);
     println!("encoding:\t{:?}", torrent.encoding);
     println!("piece length:\t{:?}", torrent.info.piece_length);
+    println!("pieces:\t\t{} bytes", torrent.info.pieces.len());
+    println!("length:\t\t{:?}", torrent.info.length);
     println!("private:\t{:?}", torrent.info.private);
     println!("root hash:\t{:?}", torrent.info.root_hash);
     println!("md5sum:\t\t{:?}", torrent.info.md5sum);
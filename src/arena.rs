@@ -0,0 +1,214 @@
+//! Decoding into a [`Value`] tree allocated from a [`bumpalo::Bump`] arena. Available with the
+//! `bumpalo` feature.
+//!
+//! Every byte string, list and dictionary of the tree lives in the arena, so decoding a document
+//! costs a handful of arena allocations instead of one heap allocation per node, and dropping
+//! the tree is free. Pipelines that decode many documents can reuse one arena and
+//! [`reset`](Bump::reset) it between them.
+//!
+//! ```
+//! use bumpalo::Bump;
+//! use serde_bencode::arena;
+//!
+//! let mut bump = Bump::new();
+//! for input in [&b"d4:name4:spam6:lengthi1ee"[..], b"d4:name4:eggs6:lengthi2ee"] {
+//!     let value = arena::from_bytes_in(input, &bump).unwrap();
+//!     assert_eq!(value.get(b"name").and_then(|name| name.as_str()).unwrap().len(), 4);
+//!     bump.reset();
+//! }
+//! ```
+
+use crate::de::{self, DuplicateKeyPolicy, Options};
+use crate::error::{Error, Result};
+use crate::token::{Token, Tokenizer};
+use crate::value;
+use alloc::vec::Vec;
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use core::mem;
+use core::str;
+use serde::ser::{self, SerializeMap, SerializeSeq};
+use serde_bytes::Bytes;
+
+/// A bencode value whose byte strings and containers are allocated from an arena.
+///
+/// Dictionary entries are sorted by key, as in [`value::Value`]. Integers must fit in an `i64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value<'a> {
+    /// A byte string.
+    Bytes(&'a [u8]),
+
+    /// An integer.
+    Int(i64),
+
+    /// A list of other values.
+    List(&'a [Value<'a>]),
+
+    /// A dictionary, with its entries sorted by key.
+    Dict(&'a [(&'a [u8], Value<'a>)]),
+}
+
+impl<'a> Value<'a> {
+    /// The byte string, if this value is one.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match *self {
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// The byte string as UTF-8 text, if this value is a byte string holding valid UTF-8.
+    pub fn as_str(&self) -> Option<&'a str> {
+        self.as_bytes().and_then(|b| str::from_utf8(b).ok())
+    }
+
+    /// The integer, if this value is one.
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            Value::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// The items, if this value is a list.
+    pub fn as_list(&self) -> Option<&'a [Value<'a>]> {
+        match *self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// The entries, if this value is a dictionary.
+    pub fn as_dict(&self) -> Option<&'a [(&'a [u8], Value<'a>)]> {
+        match *self {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// The value of `key`, if this value is a dictionary containing it.
+    pub fn get(&self, key: &[u8]) -> Option<&'a Value<'a>> {
+        let dict = self.as_dict()?;
+        let i = dict.binary_search_by(|(k, _)| (*k).cmp(key)).ok()?;
+        Some(&dict[i].1)
+    }
+
+    /// Copy the tree onto the heap as a [`value::Value`].
+    pub fn to_value(&self) -> value::Value {
+        match *self {
+            Value::Bytes(b) => value::Value::Bytes(b.into()),
+            Value::Int(i) => value::Value::Int(i),
+            Value::List(list) => value::Value::List(list.iter().map(Value::to_value).collect()),
+            Value::Dict(dict) => {
+                value::Value::Dict(dict.iter().map(|(k, v)| (*k, v.to_value())).collect())
+            }
+        }
+    }
+}
+
+impl ser::Serialize for Value<'_> {
+    fn serialize<S: ser::Serializer>(&self, s: S) -> core::result::Result<S::Ok, S::Error> {
+        match *self {
+            Value::Bytes(b) => s.serialize_bytes(b),
+            Value::Int(i) => s.serialize_i64(i),
+            Value::List(list) => {
+                let mut seq = s.serialize_seq(Some(list.len()))?;
+                for item in list {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Value::Dict(dict) => {
+                let mut map = s.serialize_map(Some(dict.len()))?;
+                for (k, v) in dict {
+                    map.serialize_entry(Bytes::new(k), v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Decode a single bencode value into `arena`.
+///
+/// # Errors
+///
+/// Fails if the input is not a single well-formed bencode value, or if an integer does not fit
+/// in an `i64`.
+pub fn from_bytes_in<'a>(input: &[u8], arena: &'a Bump) -> Result<Value<'a>> {
+    from_bytes_in_with_options(input, Options::default(), arena)
+}
+
+/// Decode a single bencode value into `arena` like [`from_bytes_in`], using the given options.
+///
+/// A repeated dictionary key keeps the last value, or the first one with
+/// [`DuplicateKeyPolicy::FirstWins`].
+///
+/// # Errors
+///
+/// In addition to the errors returned by [`from_bytes_in`], this fails if the input violates any
+/// of the restrictions enabled in `options`.
+pub fn from_bytes_in_with_options<'a>(
+    input: &[u8],
+    options: Options,
+    arena: &'a Bump,
+) -> Result<Value<'a>> {
+    let first_wins = options.duplicate_keys == DuplicateKeyPolicy::FirstWins;
+    // Checking first means the tree is only built from valid input, and keys are always byte
+    // strings below.
+    de::validate_with_options(input, options)?;
+
+    enum Frame<'a> {
+        List(BumpVec<'a, Value<'a>>),
+        Dict(BumpVec<'a, (&'a [u8], Value<'a>)>, Option<&'a [u8]>),
+    }
+
+    let mut stack: Vec<Frame<'a>> = Vec::new();
+    for token in Tokenizer::new(input) {
+        let value = match token?.1 {
+            Token::Int(i) => Value::Int(i),
+            Token::Bytes(b) => {
+                let b = &*arena.alloc_slice_copy(b);
+                if let Some(Frame::Dict(_, key @ None)) = stack.last_mut() {
+                    *key = Some(b);
+                    continue;
+                }
+                Value::Bytes(b)
+            }
+            Token::ListStart => {
+                stack.push(Frame::List(BumpVec::new_in(arena)));
+                continue;
+            }
+            Token::DictStart => {
+                stack.push(Frame::Dict(BumpVec::new_in(arena), None));
+                continue;
+            }
+            Token::End => match stack.pop() {
+                Some(Frame::List(items)) => Value::List(items.into_bump_slice()),
+                Some(Frame::Dict(mut entries, _)) => {
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                    entries.dedup_by(|later, earlier| {
+                        if later.0 != earlier.0 {
+                            return false;
+                        }
+                        if !first_wins {
+                            mem::swap(later, earlier);
+                        }
+                        true
+                    });
+                    Value::Dict(entries.into_bump_slice())
+                }
+                None => return Err(Error::EndOfStream),
+            },
+        };
+        match stack.last_mut() {
+            Some(Frame::List(items)) => items.push(value),
+            Some(Frame::Dict(entries, key)) => {
+                let key = key.take().ok_or(Error::EndOfStream)?;
+                entries.push((key, value));
+            }
+            None => return Ok(value),
+        }
+    }
+    Err(Error::EndOfStream)
+}
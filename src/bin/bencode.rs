@@ -0,0 +1,146 @@
+//! Command line tool for converting between bencode and JSON.
+//!
+//! Run `bencode help` for usage.
+
+use serde_bencode::de::{self, Options};
+use serde_bencode::pretty;
+use serde_bencode::value::{JsonBytes, Value};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: bencode <command> [options] [file]
+
+Reads from standard input if no file or `-` is given, and writes to standard output.
+
+Commands:
+    to-json     Convert bencode to JSON
+    from-json   Convert JSON to bencode
+    validate    Check that the input is a single well-formed bencode value
+    pretty      Print bencode as an indented tree, showing binary strings in hex
+    help        Print this message
+
+Options:
+    --bytes <utf8|hex|base64>   How byte strings and dictionary keys appear in JSON (default:
+                                utf8, which replaces invalid UTF-8 and so loses binary data)
+    --pretty                    Indent JSON output
+    --strict                    Reject bencode that is not in canonical form
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    ToJson,
+    FromJson,
+    Validate,
+    Pretty,
+}
+
+#[derive(Debug)]
+struct Args {
+    command: Command,
+    bytes: JsonBytes,
+    pretty: bool,
+    strict: bool,
+    file: Option<String>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let command = match args.next().as_deref() {
+            Some("to-json") => Command::ToJson,
+            Some("from-json") => Command::FromJson,
+            Some("validate") => Command::Validate,
+            Some("pretty") => Command::Pretty,
+            Some("help" | "-h" | "--help") => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            Some(command) => return Err(format!("unknown command `{}`", command)),
+            None => return Err("missing command".to_string()),
+        };
+        let mut parsed = Args {
+            command,
+            bytes: JsonBytes::Utf8Lossy,
+            pretty: false,
+            strict: false,
+            file: None,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bytes" => {
+                    parsed.bytes = match args.next().as_deref() {
+                        Some("utf8") => JsonBytes::Utf8Lossy,
+                        Some("hex") => JsonBytes::Hex,
+                        Some("base64") => JsonBytes::Base64,
+                        Some(other) => return Err(format!("unknown byte encoding `{}`", other)),
+                        None => return Err("missing value for `--bytes`".to_string()),
+                    }
+                }
+                "--pretty" => parsed.pretty = true,
+                "--strict" => parsed.strict = true,
+                "-" if parsed.file.is_none() => parsed.file = Some(arg),
+                _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+                _ if parsed.file.is_none() => parsed.file = Some(arg),
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn read_input(&self) -> io::Result<Vec<u8>> {
+        match self.file.as_deref() {
+            None | Some("-") => {
+                let mut input = Vec::new();
+                io::stdin().lock().read_to_end(&mut input)?;
+                Ok(input)
+            }
+            Some(path) => fs::read(path),
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let input = args.read_input()?;
+    let options = Options::new().strict(args.strict);
+    let mut stdout = io::stdout().lock();
+    match args.command {
+        Command::ToJson => {
+            let value: Value = de::from_bytes_with_options(&input, options)?;
+            let json = value.to_json(args.bytes);
+            if args.pretty {
+                serde_json::to_writer_pretty(&mut stdout, &json)?;
+            } else {
+                serde_json::to_writer(&mut stdout, &json)?;
+            }
+            writeln!(stdout)?;
+        }
+        Command::FromJson => {
+            let json: serde_json::Value = serde_json::from_slice(&input)?;
+            let value = Value::from_json(&json, args.bytes)?;
+            stdout.write_all(&serde_bencode::to_bytes(&value)?)?;
+        }
+        Command::Validate => de::validate_with_options(&input, options)?,
+        Command::Pretty => {
+            de::validate_with_options(&input, options)?;
+            writeln!(stdout, "{}", pretty::to_string(&input)?)?;
+        }
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+fn main() {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("bencode: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(error) = run(&args) {
+        eprintln!("bencode: {}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,199 @@
+//! Zero-copy byte strings with [`bytes::Bytes`]. Available with the `bytes`
+//! feature.
+//!
+//! Use [`serialize`] and [`deserialize`] on `Bytes` fields with `#[serde(with = "...")]`. When
+//! the input is decoded with [`from_bytes`] or a [`BytesRead`], the field is a slice of the input
+//! buffer: it shares the memory of the input and only costs a reference count, however long the
+//! byte string is. From any other source, or from a [`Value`](crate::value::Value), the bytes
+//! are copied into a new buffer as with `serde_bytes::ByteBuf`.
+//!
+//! ```
+//! use bytes::Bytes;
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Announce {
+//!     #[serde(with = "serde_bencode::bytes")]
+//!     info_hash: Bytes,
+//!     port: u16,
+//! }
+//!
+//! let input = Bytes::from_static(b"d9:info_hash4:\x01\x02\x03\x044:porti6881ee");
+//! let announce: Announce = serde_bencode::bytes::from_bytes(&input).unwrap();
+//! assert_eq!(announce.info_hash, &b"\x01\x02\x03\x04"[..]);
+//! assert_eq!(announce.info_hash.as_ptr(), input[14..].as_ptr());
+//! ```
+//!
+//! Keeping any slice alive keeps the whole input buffer alive.
+//!
+//! [`to_buf_mut`] serializes into any [`BufMut`], such as a pooled `BytesMut`.
+
+use crate::de::{BytesRead, Deserializer, Options};
+use crate::error::Result;
+use crate::ser;
+use ::bytes::{BufMut, Bytes};
+use core::cell::RefCell;
+use core::fmt;
+use serde::de::{self, DeserializeOwned};
+use std::thread_local;
+use std::vec::Vec;
+
+/// The newtype struct name [`deserialize`] asks for, so that the deserializer knows to slice the
+/// input.
+pub(crate) const SHARED_BYTES_TOKEN: &str = "$serde_bencode::private::SharedBytes";
+
+thread_local! {
+    /// The slice the deserializer passes to the visitor of [`deserialize`], which serde has no
+    /// way to carry.
+    static HAND_OFF: RefCell<Option<Bytes>> = const { RefCell::new(None) };
+
+    /// The buffer [`to_buf_mut`] serializes into before copying to the destination, kept to
+    /// avoid allocating it for every call.
+    static OUTPUT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(crate) fn hand_off(bytes: Bytes) {
+    HAND_OFF.with(|slot| *slot.borrow_mut() = Some(bytes));
+}
+
+/// Deserialize an instance of type `T` from a shared buffer, slicing the byte strings of `Bytes`
+/// fields out of it. See the [module documentation](self).
+///
+/// # Errors
+///
+/// Fails if the input is not a single well-formed bencode value of type `T`.
+pub fn from_bytes<T: DeserializeOwned>(input: &Bytes) -> Result<T> {
+    from_bytes_with_options(input, Options::default())
+}
+
+/// Deserialize an instance of type `T` from a shared buffer like [`from_bytes`], using the given
+/// options.
+///
+/// # Errors
+///
+/// Fails if the input is not a single well-formed bencode value of type `T`, or if it violates
+/// any of the limits configured in `options`.
+pub fn from_bytes_with_options<T: DeserializeOwned>(input: &Bytes, options: Options) -> Result<T> {
+    let mut de = Deserializer::from_read(BytesRead::new(input.clone()), options);
+    let value = T::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+/// Serialize the given data, writing its bencode encoding to `buf`.
+///
+/// The encoding is built in a buffer that is reused by later calls on the same thread, so this
+/// does not allocate for the output once that buffer has grown large enough.
+///
+/// # Examples
+/// ```
+/// use bytes::BytesMut;
+///
+/// let mut buf = BytesMut::with_capacity(64);
+/// serde_bencode::bytes::to_buf_mut(&("ping", 1), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"l4:pingi1ee");
+/// ```
+///
+/// # Errors
+///
+/// This fails for the same reasons as [`to_bytes`](crate::to_bytes), in which case nothing is
+/// written to `buf`.
+///
+/// # Panics
+///
+/// Panics if `buf` cannot grow to hold the output, like [`BufMut::put_slice`].
+pub fn to_buf_mut<T: serde::Serialize, B: BufMut>(value: &T, buf: &mut B) -> Result<()> {
+    to_buf_mut_with_options(value, buf, ser::Options::default())
+}
+
+/// Serialize the given data, writing its bencode encoding to `buf` like [`to_buf_mut`], using
+/// the given options.
+///
+/// # Errors
+///
+/// This fails for the same reasons as
+/// [`to_bytes_with_options`](crate::ser::to_bytes_with_options), in which case nothing is
+/// written to `buf`.
+pub fn to_buf_mut_with_options<T: serde::Serialize, B: BufMut>(
+    value: &T,
+    buf: &mut B,
+    options: ser::Options,
+) -> Result<()> {
+    // A serializer that calls back into this function gets a buffer of its own.
+    let mut out = OUTPUT.with(|out| out.take());
+    out.clear();
+    let result = ser::to_bytes_in_with_options(value, &mut out, options);
+    if result.is_ok() {
+        buf.put_slice(&out);
+    }
+    OUTPUT.with(|slot| {
+        let mut slot = slot.borrow_mut();
+        if out.capacity() > slot.capacity() {
+            *slot = out;
+        }
+    });
+    result
+}
+
+/// Serialize `Bytes` as a bencode byte string.
+///
+/// # Errors
+///
+/// Only fails if the serializer does.
+pub fn serialize<S: serde::Serializer>(
+    bytes: &Bytes,
+    serializer: S,
+) -> core::result::Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(bytes)
+}
+
+/// Deserialize a byte string into `Bytes`, without copying it if the input is a shared buffer.
+///
+/// # Errors
+///
+/// Fails if the next value is not a byte string.
+pub fn deserialize<'de, D: de::Deserializer<'de>>(
+    deserializer: D,
+) -> core::result::Result<Bytes, D::Error> {
+    deserializer.deserialize_newtype_struct(SHARED_BYTES_TOKEN, BytesVisitor)
+}
+
+struct BytesVisitor;
+
+impl<'de> de::Visitor<'de> for BytesVisitor {
+    type Value = Bytes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    // The deserializer has handed off a slice of its input.
+    fn visit_unit<E: de::Error>(self) -> core::result::Result<Bytes, E> {
+        HAND_OFF
+            .with(|slot| slot.borrow_mut().take())
+            .ok_or_else(|| E::invalid_type(de::Unexpected::Unit, &self))
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> core::result::Result<Bytes, D::Error> {
+        deserializer.deserialize_byte_buf(self)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> core::result::Result<Bytes, E> {
+        Ok(Bytes::copy_from_slice(v))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> core::result::Result<Bytes, E> {
+        Ok(Bytes::from(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> core::result::Result<Bytes, E> {
+        self.visit_bytes(v.as_bytes())
+    }
+
+    fn visit_string<E: de::Error>(self, v: std::string::String) -> core::result::Result<Bytes, E> {
+        self.visit_byte_buf(v.into_bytes())
+    }
+}
@@ -1,23 +1,80 @@
 //! Deserialize bencode data to a Rust data structure
 
+#[cfg(feature = "tokio")]
+mod async_read;
+mod canonical;
+mod decoder;
+mod events;
+mod key;
+mod options;
+mod push;
+mod read;
+mod stream;
+mod tree;
+
+#[cfg(feature = "tokio")]
+pub use self::async_read::{from_async_reader, AsyncBencodeDecoder};
+pub use self::canonical::{canonicalize, canonicalize_with_options};
+pub use self::decoder::Decoder;
+pub use self::events::{parse_events, parse_events_with_options, EventHandler, Position};
+pub use self::options::{DuplicateKeyPolicy, Options, DEFAULT_MAX_DEPTH};
+pub use self::push::{PushDecoder, Status};
+#[cfg(feature = "bytes")]
+pub use self::read::BytesRead;
+#[cfg(feature = "std")]
+pub use self::read::IoRead;
+pub use self::read::{DigestRead, Read, SliceRead};
+pub use self::stream::StreamDeserializer;
+pub use crate::ser::{BoolPolicy, FloatPolicy};
+
+use self::key::KeyDeserializer;
 use crate::error::{Error, Result};
+use crate::value::ByteString;
+#[cfg(feature = "arbitrary_precision")]
+use crate::value::{BigInt, BigIntAccess};
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::mem;
+use core::str;
 use serde::{
-    de::{self, Error as _, Unexpected},
+    de::{self, Error as _, Expected, Unexpected},
     forward_to_deserialize_any,
 };
-use std::io::Read;
-use std::str;
+#[cfg(feature = "std")]
+use std::io;
+
+/// How much memory is allocated for a byte string at first when the length of the input is not
+/// known. The buffer then grows with the bytes that have been read.
+const BYTES_CHUNK_LEN: usize = 64 * 1024;
 
 #[doc(hidden)]
 // TODO: This should be pub(crate).
 pub struct BencodeAccess<'a, R: 'a + Read> {
     de: &'a mut Deserializer<R>,
     len: Option<usize>,
+    /// The key of the current dictionary entry.
+    key: Vec<u8>,
+    /// The position of the next list element.
+    index: usize,
+    /// The field names of the struct being deserialized, if any.
+    fields: &'static [&'static str],
+    /// The length of the path to the list or dictionary.
+    path_len: usize,
 }
 
 impl<'a, R: 'a + Read> BencodeAccess<'a, R> {
     fn new(de: &'a mut Deserializer<R>, len: Option<usize>) -> BencodeAccess<'a, R> {
-        BencodeAccess { de, len }
+        BencodeAccess {
+            path_len: de.path.len(),
+            de,
+            len,
+            key: Vec::new(),
+            index: 0,
+            fields: &[],
+        }
     }
 }
 
@@ -29,20 +86,26 @@ impl<'de, 'a, R: 'a + Read> de::SeqAccess<'de> for BencodeAccess<'a, R> {
         seed: T,
     ) -> Result<Option<T::Value>> {
         let res = match self.de.parse()? {
-            ParseResult::End => Ok(None),
+            ParseResult::End => return Ok(None),
             r => {
                 self.de.next = Some(r);
-                Ok(Some(seed.deserialize(&mut *self.de)?))
+                self.index += 1;
+                let index = PathSegment::Index(self.index - 1);
+                self.de
+                    .in_path(self.path_len, index, |de| seed.deserialize(de))?
+                    .0
             }
         };
         if let Some(l) = self.len {
             let l = l - 1;
             self.len = Some(l);
             if l == 0 && ParseResult::End != self.de.parse()? {
-                return Err(Error::InvalidType("expected `e`".to_string()));
+                return Err(Error::InvalidLength(
+                    "List has more elements than expected".to_string(),
+                ));
             }
         }
-        res
+        Ok(Some(res))
     }
 }
 
@@ -52,11 +115,20 @@ impl<'de, 'a, R: 'a + Read> de::MapAccess<'de> for BencodeAccess<'a, R> {
     where
         K: de::DeserializeSeed<'de>,
     {
-        match self.de.parse()? {
-            ParseResult::End => Ok(None),
-            r => {
-                self.de.next = Some(r);
-                Ok(Some(seed.deserialize(&mut *self.de)?))
+        loop {
+            match self.de.parse()? {
+                ParseResult::End => return Ok(None),
+                _ if self.de.duplicate => self.de.skip_value()?,
+                r => {
+                    if let ParseResult::Bytes(ref key) = r {
+                        self.key.clear();
+                        self.key.extend_from_slice(key);
+                    }
+                    self.de.next = Some(r);
+                    return seed
+                        .deserialize(KeyDeserializer::key(self.de, self.fields))
+                        .map(Some);
+                }
             }
         }
     }
@@ -65,71 +137,161 @@ impl<'de, 'a, R: 'a + Read> de::MapAccess<'de> for BencodeAccess<'a, R> {
     where
         V: de::DeserializeSeed<'de>,
     {
-        seed.deserialize(&mut *self.de)
+        let key = PathSegment::Key(mem::take(&mut self.key));
+        let (value, segment) = self
+            .de
+            .in_path(self.path_len, key, |de| seed.deserialize(de))?;
+        if let PathSegment::Key(key) = segment {
+            // Keep the allocation for the next key.
+            self.key = key;
+        }
+        Ok(value)
+    }
+}
+
+/// A step of the path from the top-level value to the one being deserialized.
+#[derive(Debug)]
+enum PathSegment {
+    Key(Vec<u8>),
+    Index(usize),
+}
+
+/// Format a path like `info.files[3].length`.
+fn format_path(path: &[PathSegment]) -> String {
+    let mut formatted = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => {
+                if !formatted.is_empty() {
+                    formatted.push('.');
+                }
+                formatted.push_str(&String::from_utf8_lossy(key));
+            }
+            PathSegment::Index(index) => formatted.push_str(&format!("[{}]", index)),
+        }
+    }
+    formatted
+}
+
+/// Access to an enum variant, which is either a bare byte string (unit variants) or a dictionary
+/// with a single entry mapping the variant name to its content.
+struct VariantAccess<'a, R: 'a + Read> {
+    de: &'a mut Deserializer<R>,
+    dict: bool,
+    variants: &'static [&'static str],
+}
+
+impl<'a, R: 'a + Read> VariantAccess<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, variants: &'static [&'static str]) -> VariantAccess<'a, R> {
+        VariantAccess {
+            de,
+            dict: false,
+            variants,
+        }
+    }
+
+    fn expect_dict(&self, expected: &str) -> Result<()> {
+        if self.dict {
+            Ok(())
+        } else {
+            Err(Error::invalid_type(Unexpected::UnitVariant, &expected))
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        match self.de.parse()? {
+            ParseResult::End => Ok(()),
+            _ => Err(Error::InvalidLength(
+                "Enum variant dictionary must contain exactly one entry".to_string(),
+            )),
+        }
     }
 }
 
-impl<'de, 'a, R: 'a + Read> de::VariantAccess<'de> for BencodeAccess<'a, R> {
+impl<'de, 'a, R: 'a + Read> de::VariantAccess<'de> for VariantAccess<'a, R> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
+        if self.dict {
+            return Err(Error::invalid_type(
+                Unexpected::Map,
+                &"unit variant as a byte string",
+            ));
+        }
         Ok(())
     }
 
     fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.expect_dict("newtype variant")?;
         let res = seed.deserialize(&mut *self.de)?;
-        if ParseResult::End != self.de.parse()? {
-            return Err(Error::InvalidType("expected `e`".to_string()));
-        }
+        self.end()?;
         Ok(res)
     }
 
     fn tuple_variant<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
-        let res = match self.de.parse()? {
-            ParseResult::List => visitor.visit_seq(BencodeAccess::new(&mut *self.de, Some(len)))?,
-            _ => return Err(Error::InvalidType("expected list".to_string())),
-        };
-        if ParseResult::End != self.de.parse()? {
-            return Err(Error::InvalidType("expected `e`".to_string()));
-        }
+        self.expect_dict("tuple variant")?;
+        let res = de::Deserializer::deserialize_tuple(&mut *self.de, len, visitor)?;
+        self.end()?;
         Ok(res)
     }
 
     fn struct_variant<V: de::Visitor<'de>>(
         self,
-        _: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        let res = de::Deserializer::deserialize_any(&mut *self.de, visitor)?;
-        if ParseResult::End != self.de.parse()? {
-            return Err(Error::InvalidType("expected `e`".to_string()));
-        }
+        self.expect_dict("struct variant")?;
+        let res = de::Deserializer::deserialize_struct(&mut *self.de, "", fields, visitor)?;
+        self.end()?;
         Ok(res)
     }
 }
 
-impl<'de, 'a, R: 'a + Read> de::EnumAccess<'de> for BencodeAccess<'a, R> {
+impl<'de, 'a, R: 'a + Read> de::EnumAccess<'de> for VariantAccess<'a, R> {
     type Error = Error;
     type Variant = Self;
-    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+    fn variant_seed<V: de::DeserializeSeed<'de>>(mut self, seed: V) -> Result<(V::Value, Self)> {
         match self.de.parse()? {
-            t @ ParseResult::Bytes(_) => {
-                self.de.next = Some(t);
-                Ok((seed.deserialize(&mut *self.de)?, self))
-            }
-            ParseResult::Map => Ok((seed.deserialize(&mut *self.de)?, self)),
-            t => Err(Error::InvalidValue(format!(
-                "Expected bytes or map; got `{:?}`",
-                t
-            ))),
+            ParseResult::Map => self.dict = true,
+            t @ ParseResult::Bytes(_) => self.de.next = Some(t),
+            t => {
+                return Err(Error::InvalidValue(format!(
+                    "Expected bytes or map; got `{:?}`",
+                    t
+                )))
+            }
         }
+        let variant = seed.deserialize(KeyDeserializer::variant(self.de, self.variants))?;
+        Ok((variant, self))
     }
 }
 
+/// The kind of the next token, as returned by [`Deserializer::peek_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// An integer.
+    Integer,
+    /// A byte string.
+    Bytes,
+    /// The start of a list.
+    ListStart,
+    /// The start of a dictionary.
+    DictStart,
+    /// The end of the innermost list or dictionary.
+    End,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 enum ParseResult {
     Int(i64),
-    Bytes(Vec<u8>),
+    /// an integer outside of the `i64` range
+    Int128(i128),
+    /// an integer above `i128::MAX`
+    Uint128(u128),
+    /// the canonical digits of an integer outside of the 128-bit range
+    #[cfg(feature = "arbitrary_precision")]
+    BigInt(String),
+    Bytes(ByteString),
     /// list start
     List,
     /// map start
@@ -139,139 +301,1212 @@ enum ParseResult {
 }
 
 impl ParseResult {
-    fn to_unexpected_error(&self, expected: &str) -> Error {
+    fn to_unexpected_error(&self, expected: &dyn Expected) -> Error {
         match self {
-            Self::Int(i) => Error::invalid_type(Unexpected::Signed(*i), &expected),
-            Self::Bytes(bytes) => Error::invalid_type(Unexpected::Bytes(bytes), &expected),
-            Self::List => Error::invalid_type(Unexpected::Seq, &expected),
-            Self::Map => Error::invalid_type(Unexpected::Map, &expected),
-            Self::End => Error::custom(format_args!("unexpected end, expected {}", expected)),
+            Self::Int(i) => Error::invalid_type(Unexpected::Signed(*i), expected),
+            Self::Int128(i) => match u64::try_from(*i) {
+                Ok(i) => Error::invalid_type(Unexpected::Unsigned(i), expected),
+                Err(_) => Error::invalid_type(Unexpected::Other("128-bit integer"), expected),
+            },
+            Self::Uint128(_) => Error::invalid_type(Unexpected::Other("128-bit integer"), expected),
+            #[cfg(feature = "arbitrary_precision")]
+            Self::BigInt(_) => Error::invalid_type(Unexpected::Other("big integer"), expected),
+            // Name byte strings the way `deserialize_any` reports them.
+            Self::Bytes(bytes) => match str::from_utf8(bytes) {
+                Ok(s) => Error::invalid_type(Unexpected::Str(s), expected),
+                Err(_) => Error::invalid_type(Unexpected::Bytes(bytes), expected),
+            },
+            Self::List => Error::invalid_type(Unexpected::Seq, expected),
+            Self::Map => Error::invalid_type(Unexpected::Map, expected),
+            Self::End => Error::invalid_type(Unexpected::Other("end of container"), expected),
         }
     }
 }
 
+/// A list or dictionary which has been opened but not yet closed.
+#[derive(Debug)]
+struct Frame {
+    kind: FrameKind,
+    /// Number of values read directly inside this container; in a dictionary keys and values
+    /// are counted separately.
+    items: usize,
+    /// The previous dictionary key, kept for the strict mode ordering check.
+    last_key: Option<Vec<u8>>,
+    /// The keys read so far, kept when duplicate keys are checked.
+    keys: BTreeSet<Vec<u8>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum FrameKind {
+    List,
+    Dict,
+}
+
 /// A structure for deserializing bencode into Rust values.
+///
+/// The format is self-describing: `deserialize_any` reports integers as the smallest of `i64`,
+/// `u64`, `i128` and `u128` that fits, byte strings as strings when they are valid UTF-8 and as
+/// bytes otherwise, and lists and dictionaries as sequences and maps. A deserializer can
+/// therefore drive any serializer without knowing the target type, for example to transcode
+/// bencode to JSON with the `serde-transcode` crate.
+///
+/// # Byte strings
+///
+/// Each byte string is read into a buffer of its own, which is handed to the visitor with
+/// `visit_byte_buf`. Types that take ownership of it, like `serde_bytes::ByteBuf`, `Vec<u8>` with
+/// `#[serde(with = "serde_bytes")]` and `String`, keep that buffer without copying it again.
+/// With the `bytes` feature, [`bytes::deserialize`](crate::bytes) avoids the buffer as well.
+/// With the `smallvec` feature, byte strings of up to 16 bytes are read without allocating and
+/// passed with `visit_str` or `visit_bytes` instead, which costs owning types the same single
+/// allocation and saves it for [`Value`](crate::value::Value) and types that ignore the data.
+///
+/// # Optional values
+///
+/// Bencode has no null value, so an `Option` that is present is always `Some`, and a value that
+/// does not match its type is an error rather than `None`. `None` comes from a dictionary key
+/// that is absent, for struct fields of type `Option` or with `#[serde(default)]`. The
+/// [serializer](crate::Serializer) leaves such keys out by default.
+///
+/// # Enums
+///
+/// A unit variant is a bare byte string holding its name, such as the `event` of a tracker
+/// announce, both as a value and as a dictionary key. Every other variant is a dictionary with
+/// a single entry, mapping the name of the variant to its content.
+///
+/// ```
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// #[serde(rename_all = "lowercase")]
+/// enum Event {
+///     Started,
+///     Stopped,
+///     Completed,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Announce {
+///     event: Event,
+/// }
+///
+/// let announce: Announce = serde_bencode::from_str("d5:event7:startede").unwrap();
+/// assert_eq!(announce.event, Event::Started);
+/// ```
+///
+/// # Integer keys
+///
+/// Dictionary keys are always byte strings. Maps with integer or `char` keys, such as
+/// `HashMap<u32, T>`, are decoded by parsing each key as decimal text, which is how most
+/// encoders write such keys.
+///
+/// # Byte arrays
+///
+/// Fixed-size arrays of `u8`, such as info-hashes and node IDs, are read from byte strings of
+/// exactly their length, and from lists of integers. The serializer writes them as byte strings.
+///
+/// ```
+/// let hash: [u8; 4] = serde_bencode::from_bytes(b"4:\x01\x02\x03\x04").unwrap();
+/// assert_eq!(hash, [1, 2, 3, 4]);
+/// assert_eq!(serde_bencode::to_bytes(&hash).unwrap(), b"4:\x01\x02\x03\x04");
+/// assert!(serde_bencode::from_bytes::<[u8; 4]>(b"3:abc").is_err());
+/// ```
+///
+/// # Binary keys
+///
+/// Struct fields and enum variants are matched by name, and Rust names are always UTF-8. To
+/// match a dictionary key that is not valid UTF-8, rename the field to the key with every byte
+/// that is not part of a valid UTF-8 sequence written as `\xNN`, using two lowercase hex digits.
+/// The backslash is part of the name, so it has to be escaped in a normal string literal. A text
+/// key made of the same characters matches the renamed field as well.
+///
+/// Serde does not tell the deserializer the field names of structs with a `#[serde(flatten)]`
+/// field, so binary keys cannot be matched in them. They are captured by the flattened map
+/// instead.
+///
+/// ```
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Message {
+///     #[serde(rename = r"id\xff")]
+///     id: i64,
+/// }
+///
+/// let message: Message = serde_bencode::from_bytes(b"d3:id\xffi7ee").unwrap();
+/// assert_eq!(message.id, 7);
+/// ```
 #[derive(Debug)]
 pub struct Deserializer<R: Read> {
     reader: R,
     next: Option<ParseResult>,
+    /// The length of a byte string whose prefix has been read by
+    /// [`Deserializer::peek_bytes_len`], while its content has not.
+    pending_len: Option<usize>,
+    options: Options,
+    offset: usize,
+    stack: Vec<Frame>,
+    digest: Option<DigestState>,
+    /// The bytes read so far while decoding a [`RawValue`](crate::value::RawValue).
+    raw: Option<Vec<u8>>,
+    scratch: Vec<u8>,
+    /// Whether the last key read repeated an earlier key of its dictionary, under
+    /// [`DuplicateKeyPolicy::FirstWins`].
+    duplicate: bool,
+    /// The dictionary keys and list positions leading to the value being deserialized.
+    path: Vec<PathSegment>,
 }
 
-impl<'de, R: Read> Deserializer<R> {
-    /// Create a new deserializer.
-    pub fn new(reader: R) -> Deserializer<R> {
-        Deserializer { reader, next: None }
+/// Progress towards the subtree selected by [`Deserializer::with_digest`].
+#[derive(Debug)]
+struct DigestState {
+    path: Vec<Vec<u8>>,
+    /// Number of path components matched by the open dictionaries, not counting the root.
+    matched: usize,
+    /// Whether the next value belongs to the last matched key.
+    pending: bool,
+    /// The nesting depth at which the selected value ends, while it is being read.
+    capture: Option<usize>,
+    done: bool,
+}
+
+impl<'a> Deserializer<SliceRead<'a>> {
+    /// Create a new deserializer reading from a byte slice.
+    pub fn from_slice(slice: &'a [u8]) -> Deserializer<SliceRead<'a>> {
+        Self::from_slice_with_options(slice, Options::default())
     }
 
-    fn parse_int(&mut self) -> Result<i64> {
+    /// Create a new deserializer reading from a byte slice, using the given options.
+    pub fn from_slice_with_options(
+        slice: &'a [u8],
+        options: Options,
+    ) -> Deserializer<SliceRead<'a>> {
+        Deserializer::from_read(SliceRead::new(slice), options)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> Deserializer<IoRead<R>> {
+    /// Create a new deserializer reading from an [`io::Read`]er.
+    pub fn new(reader: R) -> Deserializer<IoRead<R>> {
+        Self::with_options(reader, Options::default())
+    }
+
+    /// Create a new deserializer reading from an [`io::Read`]er, using the given options.
+    pub fn with_options(reader: R, options: Options) -> Deserializer<IoRead<R>> {
+        Deserializer::from_read(IoRead::new(reader), options)
+    }
+
+    /// Unwrap this deserializer, returning the reader together with the byte that was already
+    /// read from it to look ahead but not consumed, if any.
+    ///
+    /// This allows decoding a bencoded header and then reading whatever follows it, such as the
+    /// binary payload of a message, from the same stream: the payload starts with the returned
+    /// byte and continues with the reader. A value that was started but not finished, such as a
+    /// byte string whose length was read by [`peek_bytes_len`](Deserializer::peek_bytes_len), is
+    /// lost.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bencode::de::Deserializer;
+    /// use serde_bencode::value::Value;
+    /// use std::io::Read;
+    ///
+    /// let mut de = Deserializer::new(&b"i3e\x01\x02\x03"[..]);
+    /// let header = Value::deserialize(&mut de)?;
+    /// assert_eq!(de.bytes_consumed(), 3);
+    ///
+    /// let (mut reader, peeked) = de.into_inner();
+    /// let mut payload: Vec<u8> = peeked.into_iter().collect();
+    /// reader.read_to_end(&mut payload)?;
+    /// assert_eq!(header, Value::Int(payload.len() as i64));
+    /// # Ok::<(), serde_bencode::Error>(())
+    /// ```
+    pub fn into_inner(self) -> (R, Option<u8>) {
+        self.reader.into_inner()
+    }
+}
+
+impl<R: Read> Deserializer<R> {
+    /// Create a new deserializer reading from any input source, using the given options.
+    pub fn from_read(reader: R, options: Options) -> Deserializer<R> {
+        Deserializer {
+            reader,
+            next: None,
+            pending_len: None,
+            options,
+            offset: 0,
+            stack: Vec::new(),
+            digest: None,
+            raw: None,
+            scratch: Vec::new(),
+            duplicate: false,
+            path: Vec::new(),
+        }
+    }
+
+    /// Create a deserializer that passes the raw encoding of the value at `path` to `digest` as
+    /// it is read.
+    ///
+    /// `path` is a sequence of dictionary keys starting from the top-level dictionary; an empty
+    /// path selects the whole top-level value. The callback receives the bytes of the first
+    /// matching value exactly as they appear in the input, in one or more pieces. This computes
+    /// the info-hash of a metainfo file in the same pass that decodes it, with any hasher that
+    /// accepts its input incrementally.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::de::{Deserializer, Options, SliceRead};
+    /// use serde_bencode::value::Value;
+    /// use serde::Deserialize;
+    ///
+    /// let input = b"d8:announce3:url4:infod6:lengthi4eee";
+    /// let mut info = Vec::new();
+    /// let mut de = Deserializer::with_digest(
+    ///     SliceRead::new(input),
+    ///     Options::default(),
+    ///     &["info"],
+    ///     |bytes: &[u8]| info.extend_from_slice(bytes),
+    /// );
+    /// let torrent = Value::deserialize(&mut de)?;
+    /// de.end()?;
+    /// drop(de);
+    /// assert_eq!(torrent["info"]["length"], Value::Int(4));
+    /// assert_eq!(info, b"d6:lengthi4ee");
+    /// # Ok::<(), serde_bencode::Error>(())
+    /// ```
+    pub fn with_digest<K, F>(
+        reader: R,
+        options: Options,
+        path: &[K],
+        digest: F,
+    ) -> Deserializer<DigestRead<R, F>>
+    where
+        K: AsRef<[u8]>,
+        F: FnMut(&[u8]),
+    {
+        let mut de = Deserializer::from_read(DigestRead::new(reader, digest), options);
+        de.digest = Some(DigestState {
+            path: path.iter().map(|key| key.as_ref().to_vec()).collect(),
+            matched: 0,
+            pending: false,
+            capture: if path.is_empty() { Some(0) } else { None },
+            done: false,
+        });
+        de
+    }
+
+    /// The number of bytes of input consumed so far. After a value has been deserialized, this is
+    /// the offset in the input at which the next value starts.
+    pub fn bytes_consumed(&self) -> usize {
+        self.offset
+    }
+
+    /// Check that the input has been fully consumed.
+    ///
+    /// Call this after deserializing a value to make sure that nothing follows it. This reads
+    /// from the underlying reader, so it should only be used when the reader is expected to be
+    /// exhausted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TrailingData`] if there is any input left.
+    pub fn end(&mut self) -> Result<()> {
+        if self.next.is_some() || self.pending_len.is_some() || self.peek_byte()?.is_some() {
+            return Err(Error::TrailingData);
+        }
+        Ok(())
+    }
+
+    /// Turn this deserializer into an iterator over consecutive values of type `T`.
+    ///
+    /// See [`StreamDeserializer`] for details.
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<'de, T>(self) -> StreamDeserializer<'de, R, T>
+    where
+        T: de::Deserialize<'de>,
+    {
+        StreamDeserializer::new(self)
+    }
+
+    /// The kind of the next token, without consuming it.
+    ///
+    /// This lets a hand-written [`DeserializeSeed`](de::DeserializeSeed) or a caller sniffing
+    /// the shape of a message decide how to decode the next value before committing to a type.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bencode::de::{Deserializer, Kind};
+    ///
+    /// let mut de = Deserializer::from_slice(b"li1e1:ae");
+    /// assert_eq!(de.peek_kind().unwrap(), Kind::ListStart);
+    /// assert_eq!(<(i64, String)>::deserialize(&mut de).unwrap(), (1, "a".to_string()));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails at the end of the input, on I/O errors, and if the next byte cannot start a token.
+    pub fn peek_kind(&mut self) -> Result<Kind> {
+        if self.pending_len.is_some() {
+            return Ok(Kind::Bytes);
+        }
+        match &self.next {
+            Some(ParseResult::Bytes(_)) => return Ok(Kind::Bytes),
+            Some(ParseResult::List) => return Ok(Kind::ListStart),
+            Some(ParseResult::Map) => return Ok(Kind::DictStart),
+            Some(ParseResult::End) => return Ok(Kind::End),
+            Some(_) => return Ok(Kind::Integer),
+            None => {}
+        }
+        match self.peek_byte()? {
+            Some(b'i') => Ok(Kind::Integer),
+            Some(b'0'..=b'9') => Ok(Kind::Bytes),
+            Some(b'l') => Ok(Kind::ListStart),
+            Some(b'd') => Ok(Kind::DictStart),
+            Some(b'e') => Ok(Kind::End),
+            Some(c) => Err(Error::Syntax(format!("Invalid character `{}`", c as char))),
+            None => Err(Error::EndOfStream),
+        }
+    }
+
+    /// The length of the next byte string, or `None` if the next token is not a byte string.
+    ///
+    /// Only the length prefix is read, and the byte string is still deserialized as usual
+    /// afterwards, so this can be used to decide whether a long byte string is worth decoding.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bencode::de::Deserializer;
+    ///
+    /// let mut de = Deserializer::new(&b"4:spam"[..]);
+    /// assert_eq!(de.peek_bytes_len().unwrap(), Some(4));
+    /// assert_eq!(String::deserialize(&mut de).unwrap(), "spam");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`peek_kind`](Deserializer::peek_kind), if the length
+    /// prefix is malformed, and if the length exceeds the limits of the options.
+    pub fn peek_bytes_len(&mut self) -> Result<Option<usize>> {
+        if let Some(len) = self.pending_len {
+            return Ok(Some(len));
+        }
+        if let Some(next) = &self.next {
+            return Ok(match next {
+                ParseResult::Bytes(bytes) => Some(bytes.len()),
+                _ => None,
+            });
+        }
+        if self.peek_kind()? != Kind::Bytes {
+            return Ok(None);
+        }
+        let first = self.read_byte()?;
+        let len = self.parse_bytes_len(first)?;
+        self.check_bytes_len(len)?;
+        self.pending_len = Some(len);
+        Ok(Some(len))
+    }
+
+    /// Look at the next byte of input without consuming it. Returns `None` at the end of input.
+    fn peek_byte(&mut self) -> Result<Option<u8>> {
+        self.reader.peek_byte()
+    }
+
+    /// Account for `len` more bytes of input, failing if that exceeds the configured maximum.
+    fn consume(&mut self, len: usize) -> Result<()> {
+        self.offset = self.offset.saturating_add(len);
+        match self.options.max_input_len {
+            Some(max) if self.offset > max => Err(Error::LimitExceeded(format!(
+                "Input is longer than the maximum of {} bytes",
+                max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
         let mut buf = [0; 1];
-        let mut result = Vec::new();
-        loop {
-            if 1 != self.reader.read(&mut buf).map_err(Error::IoError)? {
-                return Err(Error::EndOfStream);
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    /// Fill `buf` completely from the reader.
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.reader.read_exact(buf)?;
+        self.record(buf)
+    }
+
+    /// Pass bytes that have been read on to the digest and the raw value being captured, and
+    /// account for them.
+    fn record(&mut self, buf: &[u8]) -> Result<()> {
+        if let Some(DigestState {
+            capture: Some(_), ..
+        }) = self.digest
+        {
+            self.reader.digest(buf);
+        }
+        if let Some(raw) = &mut self.raw {
+            raw.extend_from_slice(buf);
+        }
+        self.consume(buf.len())
+    }
+
+    /// Update the container stack for a freshly read token, enforcing the structural limits and
+    /// the strict mode key checks.
+    ///
+    /// Nesting is tracked here instead of on the call stack, so that skipping a value never
+    /// recurses and the depth limit is enforced no matter how the caller drives the
+    /// deserializer.
+    fn track(&mut self, token: &ParseResult) -> Result<()> {
+        if let ParseResult::End = token {
+            if let Some(frame) = self.stack.pop() {
+                if frame.kind == FrameKind::Dict && frame.items % 2 == 1 {
+                    return Err(Error::Syntax("Dictionary key without a value".to_string()));
+                }
+            }
+            return Ok(());
+        }
+        if let Some(frame) = self.stack.last_mut() {
+            frame.items += 1;
+            let (len, is_key) = match frame.kind {
+                FrameKind::List => (frame.items, false),
+                FrameKind::Dict => (frame.items.div_ceil(2), frame.items % 2 == 1),
+            };
+            if let Some(max) = self.options.max_container_len {
+                if len > max {
+                    return Err(Error::LimitExceeded(format!(
+                        "Container has more than the maximum of {} items",
+                        max
+                    )));
+                }
+            }
+            if is_key && self.options.strict {
+                check_key(&mut frame.last_key, token)?;
             }
-            match buf[0] {
-                b'e' => {
-                    let len_str = String::from_utf8(result).map_err(|_| {
-                        Error::InvalidValue("Non UTF-8 integer encoding".to_string())
-                    })?;
-                    let len_int = len_str.parse().map_err(|_| {
-                        Error::InvalidValue(format!("Can't parse `{}` as integer", len_str))
-                    })?;
-                    return Ok(len_int);
+            if is_key {
+                let key = match token {
+                    ParseResult::Bytes(key) => key,
+                    _ => {
+                        return Err(Error::Syntax(format!(
+                            "Dictionary key must be a byte string; got `{:?}`",
+                            token
+                        )))
+                    }
+                };
+                self.duplicate = false;
+                match self.options.duplicate_keys {
+                    DuplicateKeyPolicy::PassThrough => {}
+                    _ if frame.keys.insert(key.to_vec()) => {}
+                    DuplicateKeyPolicy::Error => {
+                        return Err(Error::InvalidValue(format!(
+                            "Duplicate dictionary key `{}`",
+                            String::from_utf8_lossy(key)
+                        )))
+                    }
+                    DuplicateKeyPolicy::FirstWins => self.duplicate = true,
                 }
-                n => result.push(n),
             }
         }
+        let kind = match token {
+            ParseResult::List => FrameKind::List,
+            ParseResult::Map => FrameKind::Dict,
+            _ => return Ok(()),
+        };
+        if let Some(max) = self.options.max_depth {
+            if self.stack.len() >= max {
+                return Err(Error::LimitExceeded(format!(
+                    "Nesting is deeper than the maximum of {}",
+                    max
+                )));
+            }
+        }
+        self.stack.push(Frame {
+            kind,
+            items: 0,
+            last_key: None,
+            keys: BTreeSet::new(),
+        });
+        Ok(())
     }
 
-    fn parse_bytes_len(&mut self, len_char: u8) -> Result<usize> {
-        let mut buf = [0; 1];
-        let mut len = Vec::new();
-        len.push(len_char);
-        loop {
-            if 1 != self.reader.read(&mut buf).map_err(Error::IoError)? {
-                return Err(Error::EndOfStream);
+    /// Run `f` to deserialize the value at `segment`, a child of the value that `path_len`
+    /// segments lead to. The segment is returned so that its buffer can be reused.
+    ///
+    /// An error leaves the path as it is, leading to the innermost value the error occurred in,
+    /// and the outermost call names all of it in the error.
+    fn in_path<T>(
+        &mut self,
+        path_len: usize,
+        segment: PathSegment,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<(T, PathSegment)> {
+        // Whatever an error that was recovered from left behind.
+        self.path.truncate(path_len);
+        self.path.push(segment);
+        match f(self) {
+            Ok(value) => {
+                self.path.truncate(path_len + 1);
+                // The segment was pushed above.
+                let segment = self.path.pop().unwrap();
+                Ok((value, segment))
             }
-            match buf[0] {
-                b':' => {
-                    let len_str = String::from_utf8(len).map_err(|_| {
-                        Error::InvalidValue("Non UTF-8 integer encoding".to_string())
-                    })?;
-                    let len_int = len_str.parse().map_err(|_| {
-                        Error::InvalidValue(format!("Can't parse `{}` as string length", len_str))
-                    })?;
-                    return Ok(len_int);
+            Err(e) if path_len == 0 => {
+                let e = e.in_field(&format_path(&self.path));
+                self.path.clear();
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deserialize a byte string for [`crate::bytes::deserialize`], as a slice of the input if
+    /// the reader holds it in a shared buffer.
+    #[cfg(feature = "bytes")]
+    fn deserialize_shared<'de, V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        let rest = match self.reader.shared() {
+            Some(rest) if self.next.is_none() && self.pending_len.is_none() => rest,
+            _ => return de::Deserializer::deserialize_byte_buf(self, visitor),
+        };
+        let start = self.offset;
+        match self.parse_token(true)? {
+            ParseResult::Bytes(_) => {
+                let token = rest.slice(..self.offset - start);
+                // The length prefix of a byte string always ends in a colon.
+                let colon = token
+                    .iter()
+                    .position(|&b| b == b':')
+                    .expect("missing length prefix");
+                crate::bytes::hand_off(token.slice(colon + 1..));
+                visitor.visit_unit()
+            }
+            r => {
+                self.next = Some(r);
+                de::Deserializer::deserialize_byte_buf(self, visitor)
+            }
+        }
+    }
+
+    /// Skip over the next complete value without recursing into nested containers.
+    fn skip_value(&mut self) -> Result<()> {
+        // A container whose start has been pushed back is already on the stack.
+        let depth = match self.next {
+            Some(ParseResult::List | ParseResult::Map) => self.stack.len() - 1,
+            _ => self.stack.len(),
+        };
+        let mut first = true;
+        loop {
+            if let ParseResult::End = self.parse_token(true)? {
+                if first {
+                    return Err(Error::Syntax(
+                        "Expected a value, found the end of a container".to_string(),
+                    ));
+                }
+                if self.stack.len() < depth {
+                    return Err(Error::EndOfStream);
                 }
-                n => len.push(n),
+            }
+            first = false;
+            if self.stack.len() == depth {
+                return Ok(());
             }
         }
     }
 
-    fn parse_bytes(&mut self, len_char: u8) -> Result<Vec<u8>> {
-        let len = self.parse_bytes_len(len_char)?;
-        let mut buf = vec![0u8; len];
-        let actual_len = self
-            .reader
-            .read(buf.as_mut_slice())
-            .map_err(Error::IoError)?;
-        if len != actual_len {
-            return Err(Error::EndOfStream);
+    /// Skip over the next value, returning its encoding.
+    fn read_raw_value(&mut self) -> Result<Vec<u8>> {
+        // The first token or length prefix may already have been read. It was the last thing
+        // read, so the scratch buffer still holds its digits as they appeared in the input.
+        let mut raw = Vec::new();
+        let digits = &self.scratch;
+        if self.pending_len.is_some() {
+            raw.extend_from_slice(digits);
+            raw.push(b':');
+        }
+        match &self.next {
+            None | Some(ParseResult::End) => {}
+            Some(ParseResult::Int(_) | ParseResult::Int128(_) | ParseResult::Uint128(_)) => {
+                raw.push(b'i');
+                raw.extend_from_slice(digits);
+                raw.push(b'e');
+            }
+            #[cfg(feature = "arbitrary_precision")]
+            Some(ParseResult::BigInt(_)) => {
+                raw.push(b'i');
+                raw.extend_from_slice(digits);
+                raw.push(b'e');
+            }
+            Some(ParseResult::Bytes(bytes)) => {
+                raw.extend_from_slice(digits);
+                raw.push(b':');
+                raw.extend_from_slice(bytes);
+            }
+            Some(ParseResult::List) => raw.push(b'l'),
+            Some(ParseResult::Map) => raw.push(b'd'),
         }
-        Ok(buf)
+        let outer = self.raw.replace(raw);
+        let result = self.skip_value();
+        let raw = mem::replace(&mut self.raw, outer).unwrap_or_default();
+        result?;
+        Ok(raw)
+    }
+
+    /// Read up to and excluding `end` into the scratch buffer, which the caller puts back when it
+    /// is done with it. Integers and lengths are read often, so this avoids an allocation each.
+    fn read_digits(&mut self, first: Option<u8>, end: u8) -> Result<Vec<u8>> {
+        let mut digits = mem::take(&mut self.scratch);
+        digits.clear();
+        digits.extend(first);
+        let start = digits.len();
+        if self.reader.read_until(end, &mut digits)? {
+            self.record(&digits[start..])?;
+            self.record(&[end])?;
+            return Ok(digits);
+        }
+        loop {
+            match self.read_byte()? {
+                b if b == end => return Ok(digits),
+                b => digits.push(b),
+            }
+        }
+    }
+
+    fn parse_int(&mut self) -> Result<ParseResult> {
+        let digits = self.read_digits(None, b'e')?;
+        let result = self.int_from_digits(&digits);
+        self.scratch = digits;
+        result
+    }
+
+    fn int_from_digits(&self, digits: &[u8]) -> Result<ParseResult> {
+        if self.options.strict && !is_canonical_int(digits) {
+            return Err(Error::NonCanonical(format!(
+                "Integer `{}` is not minimally encoded",
+                String::from_utf8_lossy(digits)
+            )));
+        }
+        let int_str = str::from_utf8(digits)
+            .map_err(|_| Error::Syntax("Non UTF-8 integer encoding".to_string()))?;
+        if let Ok(i) = int_str.parse() {
+            return Ok(ParseResult::Int(i));
+        }
+        if let Ok(i) = int_str.parse() {
+            return Ok(ParseResult::Int128(i));
+        }
+        if let Ok(i) = int_str.parse() {
+            return Ok(ParseResult::Uint128(i));
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        {
+            if let Some(n) = BigInt::from_lenient(int_str) {
+                return Ok(ParseResult::BigInt(n.into_string()));
+            }
+        }
+        Err(Error::Syntax(format!(
+            "Can't parse `{}` as integer",
+            int_str
+        )))
+    }
+
+    fn parse_bytes_len(&mut self, len_char: u8) -> Result<usize> {
+        let digits = self.read_digits(Some(len_char), b':')?;
+        let result = self.len_from_digits(&digits);
+        self.scratch = digits;
+        result
+    }
+
+    fn len_from_digits(&self, digits: &[u8]) -> Result<usize> {
+        if self.options.strict && !is_canonical_len(digits) {
+            return Err(Error::NonCanonical(format!(
+                "String length `{}` is not minimally encoded",
+                String::from_utf8_lossy(digits)
+            )));
+        }
+        let len_str = str::from_utf8(digits)
+            .map_err(|_| Error::Syntax("Non UTF-8 integer encoding".to_string()))?;
+        len_str
+            .parse()
+            .map_err(|_| Error::Syntax(format!("Can't parse `{}` as string length", len_str)))
+    }
+
+    fn parse_bytes(&mut self, len: usize) -> Result<ByteString> {
+        self.check_bytes_len(len)?;
+        if self.reader.remaining().is_some() {
+            let mut buf = ByteString::zeroed(len);
+            self.read_exact(&mut buf)?;
+            return Ok(buf);
+        }
+        // The declared length cannot be checked against the input up front, so memory is only
+        // allocated as the bytes arrive.
+        let mut buf = Vec::new();
+        while buf.len() < len {
+            let filled = buf.len();
+            buf.resize(filled + (len - filled).min(BYTES_CHUNK_LEN.max(filled)), 0);
+            self.read_exact(&mut buf[filled..])?;
+        }
+        Ok(buf.into())
+    }
+
+    /// Read the content of a byte string, or with `skip` consume it and report it as empty.
+    fn bytes_token(&mut self, len: usize, skip: bool) -> Result<ParseResult> {
+        if skip {
+            self.skip_bytes(len)?;
+            return Ok(ParseResult::Bytes(ByteString::new()));
+        }
+        self.parse_bytes(len).map(ParseResult::Bytes)
+    }
+
+    /// Consume a byte string without keeping it.
+    fn skip_bytes(&mut self, len: usize) -> Result<()> {
+        self.check_bytes_len(len)?;
+        let capturing = self.raw.is_some()
+            || matches!(
+                self.digest,
+                Some(DigestState {
+                    capture: Some(_),
+                    ..
+                })
+            );
+        if !capturing {
+            self.reader.discard(len)?;
+            return self.consume(len);
+        }
+        // The bytes are needed after all, but can still be passed on in pieces.
+        let mut chunk = [0; 4096];
+        let mut left = len;
+        while left > 0 {
+            let n = left.min(chunk.len());
+            self.read_exact(&mut chunk[..n])?;
+            left -= n;
+        }
+        Ok(())
+    }
+
+    /// Check the length of a byte string against the configured limits, and against the rest of
+    /// the input if its length is known, before reading it.
+    fn check_bytes_len(&self, len: usize) -> Result<()> {
+        if let Some(max) = self.options.max_bytes_len {
+            if len > max {
+                return Err(Error::LimitExceeded(format!(
+                    "Byte string of length {} is longer than the maximum of {}",
+                    len, max
+                )));
+            }
+        }
+        if let Some(max) = self.options.max_input_len {
+            if self.offset.saturating_add(len) > max {
+                return Err(Error::LimitExceeded(format!(
+                    "Input is longer than the maximum of {} bytes",
+                    max
+                )));
+            }
+        }
+        if let Some(remaining) = self.reader.remaining() {
+            if len > remaining {
+                return Err(Error::EndOfStream);
+            }
+        }
+        Ok(())
     }
 
     fn parse(&mut self) -> Result<ParseResult> {
+        self.parse_token(false)
+    }
+
+    /// Read the next token. With `skip`, byte strings other than dictionary keys are consumed
+    /// without being copied, and reported as empty.
+    fn parse_token(&mut self, skip: bool) -> Result<ParseResult> {
         if let Some(t) = self.next.take() {
             return Ok(t);
         }
-        let mut buf = [0; 1];
-        if 1 != self.reader.read(&mut buf).map_err(Error::IoError)? {
-            return Err(Error::EndOfStream);
-        }
-        match buf[0] {
-            b'i' => Ok(ParseResult::Int(self.parse_int()?)),
-            n @ b'0'..=b'9' => Ok(ParseResult::Bytes(self.parse_bytes(n)?)),
-            b'l' => Ok(ParseResult::List),
-            b'd' => Ok(ParseResult::Map),
-            b'e' => Ok(ParseResult::End),
-            c => Err(Error::InvalidValue(format!(
-                "Invalid character `{}`",
-                c as char
-            ))),
+        let key_position = matches!(
+            self.stack.last(),
+            Some(frame) if frame.kind == FrameKind::Dict && frame.items % 2 == 0
+        );
+        let skip = skip && !key_position;
+        let token = match self.pending_len.take() {
+            Some(len) => self.bytes_token(len, skip)?,
+            None => match self.read_byte()? {
+                b'i' => self.parse_int()?,
+                n @ b'0'..=b'9' => {
+                    let len = self.parse_bytes_len(n)?;
+                    self.bytes_token(len, skip)?
+                }
+                b'l' => ParseResult::List,
+                b'd' => ParseResult::Map,
+                b'e' => ParseResult::End,
+                c => return Err(Error::Syntax(format!("Invalid character `{}`", c as char))),
+            },
+        };
+        let is_key = key_position && token != ParseResult::End;
+        self.track(&token)?;
+        if self.digest.is_some() {
+            self.track_digest(&token, is_key);
+        }
+        Ok(token)
+    }
+
+    /// Follow the path selected by [`Deserializer::with_digest`], starting and stopping the
+    /// digest around the selected value.
+    fn track_digest(&mut self, token: &ParseResult, is_key: bool) {
+        let depth = self.stack.len();
+        let state = match &mut self.digest {
+            Some(state) if !state.done => state,
+            _ => return,
+        };
+        if let Some(end) = state.capture {
+            if depth == end {
+                state.capture = None;
+                state.done = true;
+            }
+            return;
+        }
+        if state.pending {
+            state.pending = false;
+            if *token == ParseResult::Map {
+                state.matched += 1;
+                return;
+            }
+        }
+        if *token == ParseResult::End {
+            state.matched = state.matched.min(depth.saturating_sub(1));
+            return;
+        }
+        match token {
+            ParseResult::Bytes(key)
+                if is_key
+                    && depth == state.matched + 1
+                    && key[..] == state.path[state.matched][..] =>
+            {
+                if state.matched + 1 == state.path.len() {
+                    state.capture = Some(depth);
+                } else {
+                    state.pending = true;
+                }
+            }
+            _ => {}
         }
     }
 }
 
-impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
+/// Parse a dictionary key into an integer or other type with a text representation.
+pub(crate) fn parse_key<T: str::FromStr>(key: &[u8], expected: &dyn Expected) -> Result<T> {
+    let unexpected = match str::from_utf8(key) {
+        Ok(s) => match s.parse() {
+            Ok(parsed) => return Ok(parsed),
+            Err(_) => Unexpected::Str(s),
+        },
+        Err(_) => Unexpected::Bytes(key),
+    };
+    Err(Error::invalid_type(unexpected, expected))
+}
+
+/// Pass a byte string to the visitor as a string if it is valid UTF-8, and as bytes otherwise.
+///
+/// The buffer is handed over, except for a short byte string stored inline with the `smallvec`
+/// feature, which is passed by reference so that visitors that copy it do not pay for an
+/// allocation they throw away.
+fn visit_text_or_bytes<'de, V: de::Visitor<'de>>(
+    bytes: ByteString,
+    visitor: V,
+) -> Result<V::Value> {
+    if bytes.is_inline() {
+        return match str::from_utf8(&bytes) {
+            Ok(s) => visitor.visit_str(s),
+            Err(_) => visitor.visit_bytes(&bytes),
+        };
+    }
+    match String::from_utf8(bytes.into_vec()) {
+        Ok(s) => visitor.visit_string(s),
+        Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+    }
+}
+
+/// Pass a byte string to the visitor as a string, handing over the buffer like
+/// [`visit_text_or_bytes`]. Fails if it is not valid UTF-8.
+fn visit_text<'de, V: de::Visitor<'de>>(bytes: ByteString, visitor: V) -> Result<V::Value> {
+    if bytes.is_inline() {
+        return match str::from_utf8(&bytes) {
+            Ok(s) => visitor.visit_str(s),
+            Err(_) => Err(Error::invalid_value(
+                Unexpected::Bytes(&bytes),
+                &"utf-8 string",
+            )),
+        };
+    }
+    match String::from_utf8(bytes.into_vec()) {
+        Ok(s) => visitor.visit_string(s),
+        Err(e) => Err(Error::invalid_value(
+            Unexpected::Bytes(e.as_bytes()),
+            &"utf-8 string",
+        )),
+    }
+}
+
+/// Pass a byte string to the visitor as bytes, handing over the buffer like
+/// [`visit_text_or_bytes`].
+fn visit_bytes<'de, V: de::Visitor<'de>>(bytes: ByteString, visitor: V) -> Result<V::Value> {
+    if bytes.is_inline() {
+        return visitor.visit_bytes(&bytes);
+    }
+    visitor.visit_byte_buf(bytes.into_vec())
+}
+
+/// Visit a dictionary key or enum variant name that is matched against `names`.
+///
+/// Keys that are not valid UTF-8 are passed to the visitor escaped if that makes them match one
+/// of the names, see [`Deserializer`](Deserializer#binary-keys). Otherwise they are passed on as
+/// bytes, so that unknown fields can be ignored, and so that flattened maps with byte string keys
+/// capture them unchanged.
+pub(crate) fn visit_identifier<'de, V>(bytes: &[u8], names: &[&str], visitor: V) -> Result<V::Value>
+where
+    V: de::Visitor<'de>,
+{
+    if let Ok(s) = str::from_utf8(bytes) {
+        return visitor.visit_str(s);
+    }
+    let escaped = escape_identifier(bytes);
+    if names.contains(&escaped.as_str()) {
+        visitor.visit_string(escaped)
+    } else {
+        visitor.visit_bytes(bytes)
+    }
+}
+
+/// Pass the bytes of a byte string to a tuple or array visitor as `u8` elements, so that
+/// fixed-size byte arrays can be read from byte strings of exactly their length.
+pub(crate) fn visit_byte_tuple<'de, V: de::Visitor<'de>>(
+    bytes: &[u8],
+    size: usize,
+    visitor: V,
+) -> Result<V::Value> {
+    if bytes.len() != size {
+        return Err(Error::invalid_length(bytes.len(), &visitor));
+    }
+    let mut seq = de::value::SeqDeserializer::<_, Error>::new(bytes.iter().copied());
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+/// Turn a key that is not valid UTF-8 into a field or variant name, by writing every byte that is
+/// not part of a valid UTF-8 sequence as `\xNN` with two lowercase hex digits.
+fn escape_identifier(bytes: &[u8]) -> String {
+    let mut name = String::with_capacity(bytes.len() * 4);
+    for chunk in bytes.utf8_chunks() {
+        name.push_str(chunk.valid());
+        for b in chunk.invalid() {
+            name.push_str(&format!("\\x{:02x}", b));
+        }
+    }
+    name
+}
+
+/// In strict mode, dictionary keys must be byte strings in strictly increasing order.
+fn check_key(last_key: &mut Option<Vec<u8>>, key: &ParseResult) -> Result<()> {
+    let key = match key {
+        ParseResult::Bytes(key) => key,
+        _ => {
+            return Err(Error::NonCanonical(format!(
+                "Dictionary key must be a byte string; got `{:?}`",
+                key
+            )))
+        }
+    };
+    if let Some(last_key) = last_key {
+        if last_key[..] >= key[..] {
+            return Err(Error::NonCanonical(format!(
+                "Dictionary key `{}` is not greater than previous key `{}`",
+                String::from_utf8_lossy(key),
+                String::from_utf8_lossy(last_key)
+            )));
+        }
+    }
+    *last_key = Some(key.to_vec());
+    Ok(())
+}
+
+/// Parse the decimal representation of a finite float.
+fn parse_float<F: str::FromStr + Into<f64> + Copy>(bytes: &[u8]) -> Result<F> {
+    str::from_utf8(bytes)
+        .ok()
+        .and_then(|s| s.parse::<F>().ok())
+        .filter(|f| (*f).into().is_finite())
+        .ok_or_else(|| Error::invalid_value(Unexpected::Bytes(bytes), &"a decimal float"))
+}
+
+fn inexact_float(i: i64) -> Error {
+    Error::InvalidValue(format!(
+        "Integer `{}` cannot be represented as a float exactly",
+        i
+    ))
+}
+
+/// Returns whether `digits` is `0`, or a non-zero magnitude without leading zeros and an optional
+/// `-` sign.
+fn is_canonical_int(digits: &[u8]) -> bool {
+    match digits {
+        [b'-', rest @ ..] => rest != b"0" && is_canonical_len(rest),
+        _ => is_canonical_len(digits),
+    }
+}
+
+/// Returns whether `digits` is `0`, or a sequence of digits without leading zeros.
+fn is_canonical_len(digits: &[u8]) -> bool {
+    match digits {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    }
+}
+
+impl<'de, R: Read> de::Deserializer<'de> for &mut Deserializer<R> {
     type Error = Error;
 
     #[inline]
-    fn deserialize_any<V: de::Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         match self.parse()? {
             ParseResult::Int(i) => visitor.visit_i64(i),
-            ParseResult::Bytes(s) => visitor.visit_bytes(s.as_ref()),
-            ParseResult::List => visitor.visit_seq(BencodeAccess::new(&mut self, None)),
-            ParseResult::Map => visitor.visit_map(BencodeAccess::new(&mut self, None)),
-            ParseResult::End => Err(Error::EndOfStream),
+            ParseResult::Int128(i) => match u64::try_from(i) {
+                Ok(i) => visitor.visit_u64(i),
+                Err(_) => visitor.visit_i128(i),
+            },
+            ParseResult::Uint128(i) => visitor.visit_u128(i),
+            #[cfg(feature = "arbitrary_precision")]
+            ParseResult::BigInt(digits) => visitor.visit_map(BigIntAccess::new(digits)),
+            // Bencode does not distinguish text from binary data, but a lot of visitors (notably
+            // the ones serde uses to buffer content for untagged and internally tagged enums)
+            // only accept strings for enum variants and the like. Report byte strings as strings
+            // whenever they are valid UTF-8.
+            ParseResult::Bytes(s) => visit_text_or_bytes(s, visitor),
+            ParseResult::List => visitor.visit_seq(BencodeAccess::new(self, None)),
+            ParseResult::Map => visitor.visit_map(BencodeAccess::new(self, None)),
+            ParseResult::End => Err(Error::Syntax(
+                "Expected a value, found the end of a container".to_string(),
+            )),
         }
     }
 
     forward_to_deserialize_any! {
-        bool char i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 unit bytes byte_buf seq map unit_struct
-        tuple_struct ignored_any struct
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char unit seq map unit_struct tuple_struct
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.parse()? {
+            ParseResult::Map => {
+                let mut access = BencodeAccess::new(self, None);
+                access.fields = fields;
+                visitor.visit_map(access)
+            }
+            r => {
+                self.next = Some(r);
+                self.deserialize_any(visitor)
+            }
+        }
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match (self.options.bool, self.parse()?) {
+            (BoolPolicy::Error, _) => Err(Error::InvalidType(
+                "Cannot deserialize bool; set a `BoolPolicy` to allow it".to_string(),
+            )),
+            (_, ParseResult::Int(0)) => visitor.visit_bool(false),
+            (_, ParseResult::Int(1)) => visitor.visit_bool(true),
+            (BoolPolicy::NonZero, ParseResult::Int(_))
+            | (BoolPolicy::NonZero, ParseResult::Int128(_))
+            | (BoolPolicy::NonZero, ParseResult::Uint128(_)) => visitor.visit_bool(true),
+            #[cfg(feature = "arbitrary_precision")]
+            (BoolPolicy::NonZero, ParseResult::BigInt(_)) => visitor.visit_bool(true),
+            (BoolPolicy::Integer, ParseResult::Int(i)) => {
+                Err(Error::invalid_value(Unexpected::Signed(i), &"0 or 1"))
+            }
+            (_, r) => Err(r.to_unexpected_error(&"integer 0 or 1")),
+        }
+    }
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match (self.options.float, self.parse()?) {
+            (FloatPolicy::Integer, ParseResult::Int(i)) if i as f32 as i128 == i128::from(i) => {
+                visitor.visit_f32(i as f32)
+            }
+            (FloatPolicy::Integer, ParseResult::Int(i)) => Err(inexact_float(i)),
+            (FloatPolicy::String, ParseResult::Bytes(bytes)) => {
+                visitor.visit_f32(parse_float(&bytes)?)
+            }
+            (_, r) => {
+                self.next = Some(r);
+                self.deserialize_f64(visitor)
+            }
+        }
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let r = self.parse()?;
+        match (self.options.float, r) {
+            (FloatPolicy::Integer, ParseResult::Int(i)) if i as f64 as i128 == i128::from(i) => {
+                visitor.visit_f64(i as f64)
+            }
+            (FloatPolicy::Integer, ParseResult::Int(i)) => Err(inexact_float(i)),
+            (FloatPolicy::String, ParseResult::Bytes(bytes)) => {
+                visitor.visit_f64(parse_float(&bytes)?)
+            }
+            (FloatPolicy::Error, _) => Err(Error::InvalidType(
+                "Cannot deserialize float; set a `FloatPolicy` to allow it".to_string(),
+            )),
+            (FloatPolicy::Integer, r) => Err(r.to_unexpected_error(&"integer")),
+            (FloatPolicy::String, r) => Err(r.to_unexpected_error(&"decimal byte string")),
+        }
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.parse()? {
+            ParseResult::Bytes(bytes) => visit_bytes(bytes, visitor),
+            r => {
+                self.next = Some(r);
+                self.deserialize_any(visitor)
+            }
+        }
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.skip_value()?;
+        visitor.visit_unit()
     }
 
     #[inline]
     fn deserialize_newtype_struct<V: de::Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
+        if name == crate::value::RAW_VALUE_TOKEN {
+            return visitor.visit_byte_buf(self.read_raw_value()?);
+        }
+        if name == crate::value::VALUE_TOKEN {
+            return visitor.visit_map(self::tree::TreeAccess::new(self));
+        }
+        #[cfg(feature = "bytes")]
+        {
+            if name == crate::bytes::SHARED_BYTES_TOKEN {
+                return self.deserialize_shared(visitor);
+            }
+        }
         visitor.visit_newtype_struct(self)
     }
 
     #[inline]
+    // Absent keys never reach the deserializer: serde fills in `None` for missing `Option`
+    // fields itself.
     fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         visitor.visit_some(self)
     }
@@ -280,13 +1515,13 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
     fn deserialize_enum<V>(
         self,
         _name: &str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_enum(BencodeAccess::new(self, None))
+        visitor.visit_enum(VariantAccess::new(self, variants))
     }
 
     // Do not delegate this to `deserialize_any` because we want to call `visit_str` instead of
@@ -298,7 +1533,7 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
     {
         let bytes = self.parse().and_then(|r| match r {
             ParseResult::Bytes(bytes) => Ok(bytes),
-            _ => Err(r.to_unexpected_error("bytes")),
+            _ => Err(r.to_unexpected_error(&visitor)),
         })?;
 
         let s = str::from_utf8(&bytes)
@@ -310,26 +1545,39 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        match self.parse()? {
+            ParseResult::Bytes(bytes) => visit_text(bytes, visitor),
+            r => Err(r.to_unexpected_error(&visitor)),
+        }
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_str(visitor)
+        match self.parse()? {
+            ParseResult::Bytes(bytes) => visit_identifier(&bytes, &[], visitor),
+            r => Err(r.to_unexpected_error(&visitor)),
+        }
     }
 
     fn deserialize_tuple<V>(self, size: usize, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.parse().and_then(|r| match r {
-            ParseResult::List => Ok(()),
-            _ => Err(r.to_unexpected_error("list")),
-        })?;
-
-        visitor.visit_seq(BencodeAccess::new(self, Some(size)))
+        match self.parse()? {
+            // The visitor of an empty tuple asks for no elements, so the end of the list would
+            // not be read otherwise.
+            ParseResult::List if size == 0 => match self.parse()? {
+                ParseResult::End => visit_byte_tuple(&[], 0, visitor),
+                _ => Err(Error::InvalidLength(
+                    "List has more elements than expected".to_string(),
+                )),
+            },
+            ParseResult::List => visitor.visit_seq(BencodeAccess::new(self, Some(size))),
+            ParseResult::Bytes(bytes) => visit_byte_tuple(&bytes, size, visitor),
+            r => Err(r.to_unexpected_error(&visitor)),
+        }
     }
 }
 
@@ -362,9 +1610,9 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
 ///
 /// # Errors
 ///
-/// This conversion can fail if the input bencode is improperly formatted or if the structure of
-/// the input does not match the structure expected by `T`. It can also fail if `T`'s
-/// implementation of `Deserialize` decides to fail.
+/// This conversion can fail if the input bencode is improperly formatted, if it is followed by
+/// trailing data or if the structure of the input does not match the structure expected by `T`.
+/// It can also fail if `T`'s implementation of `Deserialize` decides to fail.
 pub fn from_str<'de, T>(s: &'de str) -> Result<T>
 where
     T: de::Deserialize<'de>,
@@ -401,12 +1649,150 @@ where
 ///
 /// # Errors
 ///
-/// This conversion can fail if the input bencode is improperly formatted or if the structure of
-/// the input does not match the structure expected by `T`. It can also fail if `T`'s
-/// implementation of `Deserialize` decides to fail.
+/// This conversion can fail if the input bencode is improperly formatted, if it is followed by
+/// trailing data or if the structure of the input does not match the structure expected by `T`.
+/// It can also fail if `T`'s implementation of `Deserialize` decides to fail. Use
+/// [`from_bytes_partial`] to decode a value that is followed by other data.
 pub fn from_bytes<'de, T>(b: &'de [u8]) -> Result<T>
 where
     T: de::Deserialize<'de>,
 {
-    de::Deserialize::deserialize(&mut Deserializer::new(b))
+    from_bytes_with_options(b, Options::default())
+}
+
+/// Deserialize an instance of type `T` from a bencode byte vector, using the given options.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{from_bytes_with_options, Options};
+///
+/// let strict = Options::new().strict(true);
+/// assert_eq!(from_bytes_with_options::<i64>(b"i7e", strict.clone()).unwrap(), 7);
+/// assert!(from_bytes_with_options::<i64>(b"i07e", strict).is_err());
+/// ```
+///
+/// # Errors
+///
+/// In addition to the errors returned by [`from_bytes`], this fails if the input violates any of
+/// the restrictions enabled in `options`.
+pub fn from_bytes_with_options<'de, T>(b: &'de [u8], options: Options) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    let mut de = Deserializer::from_slice_with_options(b, options);
+    let value = de::Deserialize::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+/// Deserialize an instance of type `T` from the start of a bencode byte slice, returning it
+/// together with the unread remainder of the slice.
+///
+/// This is useful for protocols where a bencoded value is followed by binary data, such as the
+/// BitTorrent extension protocol's metadata messages.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// let (value, rest): (String, _) = serde_bencode::de::from_bytes_partial(b"4:spam\x00\x01")?;
+/// assert_eq!(value, "spam");
+/// assert_eq!(rest, b"\x00\x01");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This conversion can fail if the start of the input is not a complete bencode value or if its
+/// structure does not match the structure expected by `T`. It can also fail if `T`'s
+/// implementation of `Deserialize` decides to fail.
+pub fn from_bytes_partial<'de, T>(b: &'de [u8]) -> Result<(T, &'de [u8])>
+where
+    T: de::Deserialize<'de>,
+{
+    from_bytes_partial_with_options(b, Options::default())
+}
+
+/// Deserialize an instance of type `T` from the start of a bencode byte slice like
+/// [`from_bytes_partial`], using the given options.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{from_bytes_partial_with_options, Options};
+///
+/// let strict = Options::new().strict(true);
+/// let (value, rest) = from_bytes_partial_with_options::<i64>(b"i7e\x00", strict.clone()).unwrap();
+/// assert_eq!((value, rest), (7, &b"\x00"[..]));
+/// assert!(from_bytes_partial_with_options::<i64>(b"i07e\x00", strict).is_err());
+/// ```
+///
+/// # Errors
+///
+/// In addition to the errors returned by [`from_bytes_partial`], this fails if the start of the
+/// input violates any of the restrictions enabled in `options`.
+pub fn from_bytes_partial_with_options<'de, T>(
+    b: &'de [u8],
+    options: Options,
+) -> Result<(T, &'de [u8])>
+where
+    T: de::Deserialize<'de>,
+{
+    let mut de = Deserializer::from_slice_with_options(b, options);
+    let value = de::Deserialize::deserialize(&mut de)?;
+    Ok((value, &b[de.offset..]))
+}
+
+/// Check that a byte slice holds exactly one well-formed bencode value, without decoding it.
+///
+/// This verifies that integers and byte string lengths are valid, that byte strings are
+/// complete, that lists and dictionaries are closed, that dictionary keys are byte strings and
+/// that nothing follows the value. Byte strings are not copied and no values are built, so this
+/// is a cheap way to reject garbage before decoding it into a concrete type.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::validate;
+///
+/// assert!(validate(b"d4:spaml1:a1:bee").is_ok());
+/// assert!(validate(b"d4:spaml1:a1:be").is_err());
+/// assert!(validate(b"5:spam").is_err());
+/// assert!(validate(b"i1ei2e").is_err());
+/// ```
+///
+/// # Errors
+///
+/// Fails if the input is not a single well-formed bencode value.
+pub fn validate(b: &[u8]) -> Result<()> {
+    validate_with_options(b, Options::default())
+}
+
+/// Check that a byte slice holds exactly one well-formed bencode value like [`validate`], using
+/// the given options.
+///
+/// # Errors
+///
+/// In addition to the errors returned by [`validate`], this fails if the input violates any of
+/// the restrictions enabled in `options`, for example if it is not canonical in strict mode.
+pub fn validate_with_options(b: &[u8], options: Options) -> Result<()> {
+    validate_read(SliceRead::new(b), options)
+}
+
+/// Check that `reader` holds exactly one well-formed bencode value like [`validate`], reading it
+/// to its end.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{validate_read, IoRead, Options};
+///
+/// let input: &[u8] = b"li1ei2ee";
+/// assert!(validate_read(IoRead::new(input), Options::default()).is_ok());
+/// ```
+///
+/// # Errors
+///
+/// Fails on I/O errors, and for the same reasons as [`validate_with_options`].
+pub fn validate_read<R: Read>(reader: R, options: Options) -> Result<()> {
+    let mut de = Deserializer::from_read(reader, options);
+    de.skip_value()?;
+    de.end()
 }
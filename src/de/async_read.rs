@@ -0,0 +1,160 @@
+//! Decode bencode from asynchronous readers.
+
+use super::push::Framer;
+use super::{from_bytes_with_options, Options};
+use crate::error::{Error, Result};
+use alloc::format;
+use alloc::vec::Vec;
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// How many bytes are requested from the reader at a time.
+const CHUNK_LEN: usize = 8 * 1024;
+
+/// Decodes consecutive bencode values from a [`tokio::io::AsyncRead`]er.
+///
+/// Input is read in chunks until one complete value has arrived, which is then decoded like
+/// [`from_bytes`](super::from_bytes). Only the value being decoded is held in memory; anything
+/// read past its end is kept for the next call to [`decode`](AsyncBencodeDecoder::decode).
+///
+/// The options are applied to each value separately, including the maximum input length, which
+/// also bounds how much is buffered while waiting for a value to complete.
+///
+/// # Examples
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::de::AsyncBencodeDecoder;
+///
+/// let mut decoder = AsyncBencodeDecoder::new(&b"i1e4:spam"[..]);
+/// assert_eq!(decoder.decode::<i64>().await?, Some(1));
+/// assert_eq!(decoder.decode::<String>().await?, Some("spam".to_string()));
+/// assert_eq!(decoder.decode::<String>().await?, None);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsyncBencodeDecoder<R> {
+    reader: R,
+    options: Options,
+    buf: Vec<u8>,
+    framer: Framer,
+    consumed: usize,
+}
+
+impl<R: AsyncRead + Unpin> AsyncBencodeDecoder<R> {
+    /// Create a new decoder.
+    pub fn new(reader: R) -> AsyncBencodeDecoder<R> {
+        Self::with_options(reader, Options::default())
+    }
+
+    /// Create a new decoder using the given options.
+    pub fn with_options(reader: R, options: Options) -> AsyncBencodeDecoder<R> {
+        AsyncBencodeDecoder {
+            reader,
+            options,
+            buf: Vec::new(),
+            framer: Framer::default(),
+            consumed: 0,
+        }
+    }
+
+    /// Wait for the next value and decode it. Returns `None` if the reader ends cleanly between
+    /// two values.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::EndOfStream`] if the reader ends in the middle of a value, and with
+    /// the errors of [`from_bytes_with_options`] if the value cannot be decoded. A value that is
+    /// well-formed but does not match `T` is consumed, so decoding can continue with the next
+    /// one; malformed input cannot be recovered from.
+    pub async fn decode<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        let len = loop {
+            if let Some(len) = self.framer.advance(&self.buf, &self.options)? {
+                break len;
+            }
+            if let Some(max) = self.options.max_input_len {
+                if self.buf.len() > max {
+                    return Err(Error::LimitExceeded(format!(
+                        "Input is longer than the maximum of {} bytes",
+                        max
+                    )));
+                }
+            }
+            self.buf.reserve(CHUNK_LEN);
+            match self
+                .reader
+                .read_buf(&mut self.buf)
+                .await
+                .map_err(Error::Io)?
+            {
+                0 if self.buf.is_empty() => return Ok(None),
+                0 => return Err(Error::EndOfStream),
+                _ => {}
+            }
+        };
+        let value = from_bytes_with_options(&self.buf[..len], self.options.clone());
+        self.buf.drain(..len);
+        self.consumed += len;
+        value.map(Some)
+    }
+
+    /// The total length of the values decoded so far, including those that did not match the
+    /// requested type. This is the offset in the input at which the next value starts.
+    pub fn bytes_consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// Input that has been read from the reader but not decoded yet.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Unwrap this decoder, returning the underlying reader together with the input that was
+    /// already read from it but not decoded yet.
+    ///
+    /// As with [`Deserializer::into_inner`](crate::de::Deserializer::into_inner), whatever follows
+    /// the decoded values starts with the returned bytes and continues with the reader.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use serde_bencode::de::AsyncBencodeDecoder;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// let mut decoder = AsyncBencodeDecoder::new(&b"d1:ni3ee\x01\x02\x03"[..]);
+    /// let header: std::collections::BTreeMap<String, u64> = decoder.decode().await?.unwrap();
+    /// assert_eq!(decoder.bytes_consumed(), 8);
+    ///
+    /// let (mut reader, mut payload) = decoder.into_inner();
+    /// reader.read_to_end(&mut payload).await?;
+    /// assert_eq!(payload.len() as u64, header["n"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_inner(self) -> (R, Vec<u8>) {
+        (self.reader, self.buf)
+    }
+}
+
+/// Deserialize an instance of type `T` from an asynchronous reader.
+///
+/// The reader is read to its end, which must coincide with the end of the value. Use
+/// [`AsyncBencodeDecoder`] to decode several values from one reader.
+///
+/// # Errors
+///
+/// This fails on I/O errors, and for the same reasons as [`from_bytes`](super::from_bytes).
+pub async fn from_async_reader<R, T>(reader: R) -> Result<T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut decoder = AsyncBencodeDecoder::new(reader);
+    let value = decoder.decode().await?.ok_or(Error::EndOfStream)?;
+    if !decoder.buf.is_empty() || decoder.reader.read(&mut [0]).await.map_err(Error::Io)? > 0 {
+        return Err(Error::TrailingData);
+    }
+    Ok(value)
+}
@@ -0,0 +1,126 @@
+//! Rewriting bencode into canonical form without decoding it into a concrete type.
+
+use super::{Deserializer, Options, ParseResult, Read, SliceRead};
+use crate::error::{Error, Result};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+impl<R: Read> Deserializer<R> {
+    /// Read the next complete value and append its canonical encoding to `out`.
+    fn write_canonical(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        match self.parse()? {
+            ParseResult::Int(i) => write_int(out, i),
+            ParseResult::Int128(i) => write_int(out, i),
+            ParseResult::Uint128(i) => write_int(out, i),
+            #[cfg(feature = "arbitrary_precision")]
+            ParseResult::BigInt(digits) => write_int(out, digits),
+            ParseResult::Bytes(bytes) => write_bytes(out, &bytes),
+            ParseResult::List => {
+                out.push(b'l');
+                while !self.at_end()? {
+                    self.write_canonical(out)?;
+                }
+                out.push(b'e');
+            }
+            ParseResult::Map => {
+                let mut entries = Vec::new();
+                while !self.at_end()? {
+                    let key = match self.parse()? {
+                        ParseResult::Bytes(key) => key,
+                        // Dictionary keys that are not byte strings are rejected by the parser.
+                        _ => unreachable!(),
+                    };
+                    let mut value = Vec::new();
+                    self.write_canonical(&mut value)?;
+                    entries.push((key, value));
+                }
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                    return Err(Error::InvalidValue(format!(
+                        "Duplicate dictionary key `{}`",
+                        String::from_utf8_lossy(&pair[0].0)
+                    )));
+                }
+                out.push(b'd');
+                for (key, value) in entries {
+                    write_bytes(out, &key);
+                    out.extend_from_slice(&value);
+                }
+                out.push(b'e');
+            }
+            ParseResult::End => {
+                return Err(Error::Syntax(
+                    "Expected a value, found the end of a container".to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Consume the end of the innermost container if it comes next, or push the token back.
+    fn at_end(&mut self) -> Result<bool> {
+        match self.parse()? {
+            ParseResult::End => Ok(true),
+            token => {
+                self.next = Some(token);
+                Ok(false)
+            }
+        }
+    }
+}
+
+fn write_int<I: Display>(out: &mut Vec<u8>, i: I) {
+    out.extend_from_slice(format!("i{}e", i).as_bytes());
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// Rewrite one bencode value into its canonical form.
+///
+/// Dictionary keys are sorted, and integers and byte string lengths are written without leading
+/// zeros or signs. This works on any well-formed input without knowing its schema, so it can be
+/// used to get stable hashes of data from producers that do not sort their keys.
+///
+/// Integers outside of the 128-bit range are only supported with the `arbitrary_precision`
+/// feature.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::canonicalize;
+///
+/// let sloppy = b"d1:bi+02e1:ad03:ab1i-0eee";
+/// assert_eq!(canonicalize(sloppy).unwrap(), b"d1:ad3:ab1i0ee1:bi2ee");
+/// assert!(canonicalize(b"d1:ai1e1:ai2ee").is_err());
+/// ```
+///
+/// # Errors
+///
+/// Fails if the input is not a single well-formed bencode value, or if a dictionary contains the
+/// same key more than once. There is no canonical form for such a dictionary, since dropping
+/// either value would change what consumers see.
+pub fn canonicalize(b: &[u8]) -> Result<Vec<u8>> {
+    canonicalize_with_options(b, Options::default())
+}
+
+/// Rewrite one bencode value into its canonical form like [`canonicalize`], using the given
+/// options.
+///
+/// [`Options::strict`] should be left off, since it rejects exactly the input that needs to be
+/// rewritten.
+///
+/// # Errors
+///
+/// In addition to the errors returned by [`canonicalize`], this fails if the input violates any
+/// of the limits configured in `options`.
+pub fn canonicalize_with_options(b: &[u8], options: Options) -> Result<Vec<u8>> {
+    let mut de = Deserializer::from_read(SliceRead::new(b), options);
+    let mut out = Vec::with_capacity(b.len());
+    de.write_canonical(&mut out)?;
+    de.end()?;
+    Ok(out)
+}
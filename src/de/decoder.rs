@@ -0,0 +1,91 @@
+//! Decoding many messages while reusing the deserializer's buffers.
+
+use super::{Deserializer, Frame, Options, PathSegment, SliceRead};
+use crate::error::Result;
+use alloc::vec::Vec;
+use core::mem;
+use serde::de;
+
+/// Decodes a sequence of separate inputs, such as datagrams, keeping the memory the deserializer
+/// allocates for its bookkeeping from one input to the next.
+///
+/// [`from_bytes`](super::from_bytes) builds a new [`Deserializer`] for every call, which has to
+/// grow its container stack and the buffer for integer digits from scratch. A `Decoder` holds on
+/// to them instead, so that decoding small messages in a loop allocates only for the decoded
+/// values themselves. Each call to [`decode`](Decoder::decode) starts from a clean state, whether
+/// or not the previous one succeeded.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::Decoder;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct Ping {
+///     t: String,
+/// }
+///
+/// let mut decoder = Decoder::new();
+/// let mut datagram = b"d1:t2:aae".to_vec();
+/// assert_eq!(decoder.decode::<Ping>(&datagram).unwrap(), Ping { t: "aa".to_string() });
+/// datagram.copy_from_slice(b"d1:t2:bbe");
+/// assert_eq!(decoder.decode::<Ping>(&datagram).unwrap(), Ping { t: "bb".to_string() });
+/// ```
+#[derive(Debug, Default)]
+pub struct Decoder {
+    options: Options,
+    stack: Vec<Frame>,
+    scratch: Vec<u8>,
+    path: Vec<PathSegment>,
+}
+
+impl Decoder {
+    /// Create a decoder with the default options.
+    pub fn new() -> Decoder {
+        Self::with_options(Options::default())
+    }
+
+    /// Create a decoder using the given options for every input.
+    pub fn with_options(options: Options) -> Decoder {
+        Decoder {
+            options,
+            stack: Vec::new(),
+            scratch: Vec::new(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Use `scratch` as the buffer for the digits of integers and length prefixes, for example
+    /// one taken from a pool or from another decoder with [`into_scratch`](Decoder::into_scratch).
+    /// Its contents are ignored.
+    pub fn with_scratch(mut self, scratch: Vec<u8>) -> Decoder {
+        self.scratch = scratch;
+        self
+    }
+
+    /// Give back the scratch buffer, with whatever capacity it has grown to.
+    pub fn into_scratch(self) -> Vec<u8> {
+        self.scratch
+    }
+
+    /// Deserialize one value of type `T` from `input`, which must contain nothing else, like
+    /// [`from_bytes_with_options`](super::from_bytes_with_options).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the input is not a single well-formed bencode value of type `T`, or if it
+    /// violates any of the limits configured in the options.
+    pub fn decode<'de, T: de::Deserialize<'de>>(&mut self, input: &'de [u8]) -> Result<T> {
+        let mut de = Deserializer::from_read(SliceRead::new(input), self.options.clone());
+        de.stack = mem::take(&mut self.stack);
+        de.scratch = mem::take(&mut self.scratch);
+        de.path = mem::take(&mut self.path);
+        let result = T::deserialize(&mut de).and_then(|value| de.end().map(|()| value));
+        self.stack = mem::take(&mut de.stack);
+        self.stack.clear();
+        self.scratch = mem::take(&mut de.scratch);
+        self.path = mem::take(&mut de.path);
+        self.path.clear();
+        result
+    }
+}
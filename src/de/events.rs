@@ -0,0 +1,171 @@
+//! Push-style parsing that reports each token to a handler.
+
+use super::{Deserializer, Options, ParseResult, Read};
+use crate::error::{Error, Result};
+use alloc::string::ToString;
+use core::ops::ControlFlow;
+
+/// Where in the input an event occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Offset of the first byte of the token.
+    pub offset: usize,
+    /// Number of lists and dictionaries enclosing the token. The start and end of a container
+    /// share the same depth.
+    pub depth: usize,
+}
+
+/// Callbacks for [`parse_events`].
+///
+/// Every method has a default implementation that ignores the event, so a handler only needs to
+/// implement the events it cares about. Returning [`ControlFlow::Break`] stops parsing
+/// immediately, without reading the rest of the input.
+///
+/// Inside a dictionary, keys and values are reported in turn. The structure of the input is
+/// validated as it is parsed, but apart from the byte string being reported and a little
+/// bookkeeping per nesting level nothing is kept in memory.
+pub trait EventHandler {
+    /// An integer that fits in an `i64`.
+    fn on_int(&mut self, _value: i64, _pos: Position) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// An integer outside of the `i64` range, as canonical decimal digits.
+    fn on_big_int(&mut self, _digits: &str, _pos: Position) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// A byte string.
+    fn on_bytes(&mut self, _value: &[u8], _pos: Position) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// The start of a list.
+    fn begin_list(&mut self, _pos: Position) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// The start of a dictionary.
+    fn begin_dict(&mut self, _pos: Position) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// The end of the innermost list or dictionary.
+    fn end(&mut self, _pos: Position) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+impl<R: Read> Deserializer<R> {
+    /// Parse the next complete value, reporting each of its tokens to `handler`.
+    ///
+    /// Returns [`ControlFlow::Break`] if the handler stopped parsing early. See [`parse_events`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the input is not well-formed bencode or violates the configured options.
+    pub fn parse_events<H: EventHandler + ?Sized>(
+        &mut self,
+        handler: &mut H,
+    ) -> Result<ControlFlow<()>> {
+        let depth = self.stack.len();
+        loop {
+            let offset = self.offset;
+            let before = self.stack.len();
+            let token = self.parse()?;
+            let pos = Position {
+                offset,
+                depth: before.min(self.stack.len()),
+            };
+            let flow = match token {
+                ParseResult::Int(i) => handler.on_int(i, pos),
+                ParseResult::Int128(i) => handler.on_big_int(&i.to_string(), pos),
+                ParseResult::Uint128(i) => handler.on_big_int(&i.to_string(), pos),
+                #[cfg(feature = "arbitrary_precision")]
+                ParseResult::BigInt(digits) => handler.on_big_int(&digits, pos),
+                ParseResult::Bytes(bytes) => handler.on_bytes(&bytes, pos),
+                ParseResult::List => handler.begin_list(pos),
+                ParseResult::Map => handler.begin_dict(pos),
+                ParseResult::End if before == depth => {
+                    return Err(Error::Syntax(
+                        "Expected a value, found the end of a container".to_string(),
+                    ))
+                }
+                ParseResult::End => handler.end(pos),
+            };
+            if flow.is_break() || self.stack.len() == depth {
+                return Ok(flow);
+            }
+        }
+    }
+}
+
+/// Parse one bencode value from `reader`, reporting each token to `handler` as it is read.
+///
+/// This is useful for validating or extracting parts of very large inputs without decoding them
+/// into memory. If the handler returns [`ControlFlow::Break`], parsing stops immediately and the
+/// rest of the input is left unread. Otherwise the input must end after the value.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{parse_events, EventHandler, Position, SliceRead};
+/// use std::ops::ControlFlow;
+///
+/// /// Finds the value of the top-level `announce` key.
+/// #[derive(Default)]
+/// struct Announce {
+///     found_key: bool,
+///     url: Option<Vec<u8>>,
+/// }
+///
+/// impl EventHandler for Announce {
+///     fn on_bytes(&mut self, value: &[u8], pos: Position) -> ControlFlow<()> {
+///         if self.found_key {
+///             self.url = Some(value.to_vec());
+///             return ControlFlow::Break(());
+///         }
+///         self.found_key = pos.depth == 1 && value == b"announce";
+///         ControlFlow::Continue(())
+///     }
+/// }
+///
+/// let mut handler = Announce::default();
+/// let input = b"d8:announce3:url4:infod6:lengthi1eee";
+/// let flow = parse_events(SliceRead::new(input), &mut handler).unwrap();
+/// assert!(flow.is_break());
+/// assert_eq!(handler.url.as_deref(), Some(&b"url"[..]));
+/// ```
+///
+/// # Errors
+///
+/// Fails if the input is not a single well-formed bencode value.
+pub fn parse_events<R, H>(reader: R, handler: &mut H) -> Result<ControlFlow<()>>
+where
+    R: Read,
+    H: EventHandler + ?Sized,
+{
+    parse_events_with_options(reader, Options::default(), handler)
+}
+
+/// Parse one bencode value from `reader` like [`parse_events`], using the given options.
+///
+/// # Errors
+///
+/// In addition to the errors returned by [`parse_events`], this fails if the input violates any
+/// of the restrictions enabled in `options`.
+pub fn parse_events_with_options<R, H>(
+    reader: R,
+    options: Options,
+    handler: &mut H,
+) -> Result<ControlFlow<()>>
+where
+    R: Read,
+    H: EventHandler + ?Sized,
+{
+    let mut de = Deserializer::from_read(reader, options);
+    let flow = de.parse_events(handler)?;
+    if flow.is_continue() {
+        de.end()?;
+    }
+    Ok(flow)
+}
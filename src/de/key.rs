@@ -0,0 +1,156 @@
+//! Deserializing dictionary keys and enum variant names, which are matched against the names the
+//! visitor expects and, for keys, parsed as integers.
+
+use super::{parse_key, visit_identifier, Deserializer, ParseResult, Read};
+use crate::error::Result;
+use core::str;
+use serde::de;
+
+/// The [`Deserializer`] while it reads a dictionary key or an enum variant name.
+pub(super) struct KeyDeserializer<'a, R: Read> {
+    de: &'a mut Deserializer<R>,
+    /// The names an identifier is matched against.
+    names: &'static [&'static str],
+    /// Whether integer types are parsed from byte strings, which is the case for dictionary
+    /// keys but not for variant names.
+    parse: bool,
+}
+
+impl<'a, R: Read> KeyDeserializer<'a, R> {
+    /// A dictionary key, which is one of `fields` if the dictionary is a struct.
+    pub(super) fn key(
+        de: &'a mut Deserializer<R>,
+        fields: &'static [&'static str],
+    ) -> KeyDeserializer<'a, R> {
+        KeyDeserializer {
+            de,
+            names: fields,
+            parse: true,
+        }
+    }
+
+    /// The name of one of `variants`.
+    pub(super) fn variant(
+        de: &'a mut Deserializer<R>,
+        variants: &'static [&'static str],
+    ) -> KeyDeserializer<'a, R> {
+        KeyDeserializer {
+            de,
+            names: variants,
+            parse: false,
+        }
+    }
+
+    fn deserialize_parsed<'de, V, T>(
+        self,
+        visitor: V,
+        visit: fn(V, T) -> Result<V::Value>,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+        T: str::FromStr,
+    {
+        if !self.parse {
+            return de::Deserializer::deserialize_any(self.de, visitor);
+        }
+        match self.de.parse()? {
+            ParseResult::Bytes(bytes) => {
+                let parsed = parse_key(&bytes, &visitor)?;
+                visit(visitor, parsed)
+            }
+            r => {
+                self.de.next = Some(r);
+                de::Deserializer::deserialize_any(self.de, visitor)
+            }
+        }
+    }
+}
+
+/// Forward methods that only take a visitor to the [`Deserializer`].
+macro_rules! forward_to_deserializer {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                de::Deserializer::$method(self.de, visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, R: Read> de::Deserializer<'de> for KeyDeserializer<'a, R> {
+    type Error = crate::Error;
+
+    forward_to_deserializer! {
+        deserialize_any deserialize_bool deserialize_f32 deserialize_f64 deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_unit deserialize_seq deserialize_map deserialize_ignored_any
+    }
+
+    deserialize_parsed_key!();
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.de.parse()? {
+            ParseResult::Bytes(bytes) => visit_identifier(&bytes, self.names, visitor),
+            r => Err(r.to_unexpected_error(&visitor)),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_unit_struct(self.de, name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        // The private types of this crate are read by the deserializer itself, anything else
+        // is still a key.
+        if name.starts_with("$serde_bencode::private::") {
+            return de::Deserializer::deserialize_newtype_struct(self.de, name, visitor);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_tuple(self.de, len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_tuple_struct(self.de, name, len, visitor)
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_struct(self.de, name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_enum(self.de, name, variants, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        de::Deserializer::is_human_readable(&self.de)
+    }
+}
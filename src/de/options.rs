@@ -0,0 +1,146 @@
+//! Options for configuring how bencode is decoded.
+
+use crate::ser::{BoolPolicy, FloatPolicy};
+
+/// The nesting depth allowed by [`Options::default`].
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Configuration for a [`Deserializer`](super::Deserializer).
+///
+/// The defaults are lenient and accept anything the decoder can make sense of. Apart from a
+/// nesting depth of [`DEFAULT_MAX_DEPTH`], no resource limits are applied. Use the builder methods
+/// to tighten them, especially when decoding input from untrusted peers.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{from_bytes_with_options, Options};
+/// use serde_bencode::value::Value;
+///
+/// let options = Options::new()
+///     .strict(true)
+///     .max_depth(16)
+///     .max_bytes_len(1 << 20);
+/// assert!(from_bytes_with_options::<Value>(b"i42e", options.clone()).is_ok());
+/// assert!(from_bytes_with_options::<Value>(b"i042e", options.clone()).is_err());
+/// assert!(from_bytes_with_options::<Value>(b"99999999999:", options).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub(crate) strict: bool,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) max_bytes_len: Option<usize>,
+    pub(crate) max_container_len: Option<usize>,
+    pub(crate) max_input_len: Option<usize>,
+    pub(crate) float: FloatPolicy,
+    pub(crate) bool: BoolPolicy,
+    pub(crate) duplicate_keys: DuplicateKeyPolicy,
+}
+
+/// What to do when a dictionary contains the same key more than once.
+///
+/// Duplicate keys are never valid bencode, but lenient decoders disagree on which of the values
+/// wins. A consumer that relies on another implementation having seen the same value, for example
+/// when checking the `info` dictionary of a torrent, should use [`DuplicateKeyPolicy::Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Fail with an error.
+    Error,
+    /// Keep the first value and skip the entries repeating its key.
+    FirstWins,
+    /// Pass every entry on to the type being deserialized, and do whatever its visitor does
+    /// with repeated keys. Maps such as `BTreeMap` and [`Value`](crate::value::Value) keep the
+    /// last value, while derived structs fail with a duplicate field error. This is the default.
+    PassThrough,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            strict: false,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_bytes_len: None,
+            max_container_len: None,
+            max_input_len: None,
+            float: FloatPolicy::Error,
+            bool: BoolPolicy::Integer,
+            duplicate_keys: DuplicateKeyPolicy::PassThrough,
+        }
+    }
+}
+
+impl Options {
+    /// Create a new set of options with the default (lenient) settings.
+    pub fn new() -> Options {
+        Self::default()
+    }
+
+    /// Reject input that is not in canonical form.
+    ///
+    /// In strict mode the following are errors:
+    ///
+    /// * integers with leading zeros, an explicit `+` sign, `i-0e` or no digits at all,
+    /// * byte string lengths with leading zeros or an explicit sign,
+    /// * dictionary keys that are not byte strings, or are not in strictly increasing
+    ///   lexicographic order (which also rules out duplicate keys).
+    ///
+    /// This is what consumers verifying an info-hash want, since only the canonical encoding
+    /// hashes consistently.
+    pub fn strict(mut self, strict: bool) -> Options {
+        self.strict = strict;
+        self
+    }
+
+    /// Limit how deeply lists and dictionaries may be nested. A top-level list has depth 1.
+    ///
+    /// A [`Value`](crate::value::Value), a [`RawValue`](crate::value::RawValue) and skipped
+    /// values, such as unknown fields, are read with an explicit stack, so for them the depth is
+    /// bounded only by this limit. Derived types and other `Deserialize` impls recurse once per
+    /// level of nesting, so raising this far beyond [`DEFAULT_MAX_DEPTH`] for them on hostile
+    /// input may exhaust the thread's stack. Dropping, comparing or serializing a deeply nested
+    /// `Value` with serde recurses as well; [`Value::to_bytes`](crate::value::Value::to_bytes)
+    /// does not.
+    pub fn max_depth(mut self, max_depth: usize) -> Options {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Limit the length of any single byte string. The limit is checked against the declared
+    /// length before any memory is allocated for the string.
+    pub fn max_bytes_len(mut self, max_bytes_len: usize) -> Options {
+        self.max_bytes_len = Some(max_bytes_len);
+        self
+    }
+
+    /// Limit the number of items in a single list, or entries in a single dictionary.
+    pub fn max_container_len(mut self, max_container_len: usize) -> Options {
+        self.max_container_len = Some(max_container_len);
+        self
+    }
+
+    /// Limit the total number of input bytes consumed while decoding.
+    pub fn max_input_len(mut self, max_input_len: usize) -> Options {
+        self.max_input_len = Some(max_input_len);
+        self
+    }
+
+    /// Set which representation floating point values are decoded from. See [`FloatPolicy`].
+    pub fn float(mut self, float: FloatPolicy) -> Options {
+        self.float = float;
+        self
+    }
+
+    /// Set which integers `bool` values are decoded from. See [`BoolPolicy`].
+    pub fn bool(mut self, bool: BoolPolicy) -> Options {
+        self.bool = bool;
+        self
+    }
+
+    /// Set how dictionaries with repeated keys are handled. See [`DuplicateKeyPolicy`].
+    ///
+    /// Checking for duplicates keeps a copy of every key of the open dictionaries. Strict mode
+    /// already rejects duplicates, since it requires keys to be in strictly increasing order.
+    pub fn duplicate_keys(mut self, duplicate_keys: DuplicateKeyPolicy) -> Options {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+}
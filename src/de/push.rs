@@ -0,0 +1,216 @@
+//! Decode bencode that arrives in pieces, without blocking.
+
+use super::{Decoder, Options};
+use crate::error::{Error, Result};
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use serde::de::DeserializeOwned;
+
+/// The outcome of feeding input to a [`PushDecoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// All of the input was taken, and the value is not complete yet.
+    NeedMore,
+    /// The value is complete after taking this many bytes of the input. The rest of the input
+    /// was not taken and belongs to whatever follows the value.
+    Complete(usize),
+}
+
+/// An incremental decoder that is handed input as it arrives, for event loops that read from
+/// non-blocking sockets.
+///
+/// Each call to [`feed`](PushDecoder::feed) scans the new bytes for the end of the current value,
+/// picking up where the previous call stopped, so the cost of a value stays linear in its length
+/// however it is split. Once the value is complete, [`decode`](PushDecoder::decode) deserializes
+/// it and resets the decoder for the next one. Only the current value is buffered.
+///
+/// The input is checked for the structure of bencode and the limits of the options while it is
+/// fed; everything else, including strict mode, is checked when the value is decoded. After an
+/// error, the decoder has to be [`reset`](PushDecoder::reset) before it can be used again.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{PushDecoder, Status};
+/// use serde_bencode::value::Value;
+///
+/// let mut decoder = PushDecoder::new();
+/// assert_eq!(decoder.feed(b"d4:spaml1:a").unwrap(), Status::NeedMore);
+/// assert_eq!(decoder.feed(b"1:beei42e").unwrap(), Status::Complete(5));
+/// assert_eq!(decoder.decode::<Value>().unwrap().to_string(), "d4:spaml1:a1:bee");
+///
+/// assert_eq!(decoder.feed(b"i42e").unwrap(), Status::Complete(4));
+/// assert_eq!(decoder.decode::<i64>().unwrap(), 42);
+/// ```
+#[derive(Debug, Default)]
+pub struct PushDecoder {
+    options: Options,
+    buf: Vec<u8>,
+    framer: Framer,
+    complete: bool,
+    decoder: Decoder,
+}
+
+impl PushDecoder {
+    /// Create a decoder with the default options.
+    pub fn new() -> PushDecoder {
+        Self::with_options(Options::default())
+    }
+
+    /// Create a decoder using the given options for every value.
+    pub fn with_options(options: Options) -> PushDecoder {
+        PushDecoder {
+            decoder: Decoder::with_options(options.clone()),
+            options,
+            buf: Vec::new(),
+            framer: Framer::default(),
+            complete: false,
+        }
+    }
+
+    /// Take as much of `input` as belongs to the current value.
+    ///
+    /// Once the value is complete, further input is not taken until it has been decoded, and
+    /// this returns `Complete(0)`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the input is not bencode, or if the value is nested deeper, contains a longer
+    /// byte string or is longer overall than the options allow.
+    pub fn feed(&mut self, input: &[u8]) -> Result<Status> {
+        if self.complete {
+            return Ok(Status::Complete(0));
+        }
+        let start = self.buf.len();
+        self.buf.extend_from_slice(input);
+        match self.framer.advance(&self.buf, &self.options)? {
+            Some(len) => {
+                self.buf.truncate(len);
+                self.complete = true;
+                Ok(Status::Complete(len - start))
+            }
+            None => {
+                if let Some(max) = self.options.max_input_len {
+                    if self.buf.len() > max {
+                        return Err(Error::LimitExceeded(format!(
+                            "Input is longer than the maximum of {} bytes",
+                            max
+                        )));
+                    }
+                }
+                Ok(Status::NeedMore)
+            }
+        }
+    }
+
+    /// Whether a complete value is waiting to be decoded.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// The input taken for the current value so far.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Deserialize the completed value as `T`, and reset the decoder for the next value, whether
+    /// or not that succeeds.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::EndOfStream`] if the value is not complete yet, and for the same
+    /// reasons as [`from_bytes_with_options`](super::from_bytes_with_options) otherwise.
+    pub fn decode<T: DeserializeOwned>(&mut self) -> Result<T> {
+        if !self.complete {
+            return Err(Error::EndOfStream);
+        }
+        let value = self.decoder.decode(&self.buf);
+        self.reset();
+        value
+    }
+
+    /// Discard the current value and start over, keeping the allocated buffer.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.framer = Framer::default();
+        self.complete = false;
+    }
+}
+
+/// Finds where the first complete value in a buffer ends, without decoding it.
+///
+/// Scanning resumes where the previous call stopped, so feeding a value in small pieces stays
+/// linear in its length.
+#[derive(Debug, Default)]
+pub(crate) struct Framer {
+    /// Start of the first token that has not been scanned completely.
+    pos: usize,
+    depth: usize,
+}
+
+impl Framer {
+    pub(crate) fn advance(&mut self, buf: &[u8], options: &Options) -> Result<Option<usize>> {
+        while let Some(&c) = buf.get(self.pos) {
+            match c {
+                b'i' => match memchr::memchr(b'e', &buf[self.pos..]) {
+                    Some(i) => self.pos += i + 1,
+                    None => return Ok(None),
+                },
+                b'0'..=b'9' => {
+                    let colon = match memchr::memchr(b':', &buf[self.pos..]) {
+                        Some(i) => self.pos + i,
+                        None => return Ok(None),
+                    };
+                    let len: usize = core::str::from_utf8(&buf[self.pos..colon])
+                        .ok()
+                        .and_then(|len| len.parse().ok())
+                        .ok_or_else(|| Error::Syntax("Invalid byte string length".to_string()))?;
+                    if let Some(max) = options.max_bytes_len {
+                        if len > max {
+                            return Err(Error::LimitExceeded(format!(
+                                "Byte string of length {} is longer than the maximum of {}",
+                                len, max
+                            )));
+                        }
+                    }
+                    let end = (colon + 1).saturating_add(len);
+                    if let Some(max) = options.max_input_len {
+                        if end > max {
+                            return Err(Error::LimitExceeded(format!(
+                                "Input is longer than the maximum of {} bytes",
+                                max
+                            )));
+                        }
+                    }
+                    if end > buf.len() {
+                        return Ok(None);
+                    }
+                    self.pos = end;
+                }
+                b'l' | b'd' => {
+                    if let Some(max) = options.max_depth {
+                        if self.depth >= max {
+                            return Err(Error::LimitExceeded(format!(
+                                "Nesting is deeper than the maximum of {}",
+                                max
+                            )));
+                        }
+                    }
+                    self.depth += 1;
+                    self.pos += 1;
+                }
+                b'e' if self.depth > 0 => {
+                    self.depth -= 1;
+                    self.pos += 1;
+                }
+                c => return Err(Error::Syntax(format!("Invalid character `{}`", c as char))),
+            }
+            if self.depth == 0 {
+                let len = self.pos;
+                *self = Framer::default();
+                return Ok(Some(len));
+            }
+        }
+        Ok(None)
+    }
+}
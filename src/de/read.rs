@@ -0,0 +1,321 @@
+//! Input sources for the deserializer.
+
+use crate::error::{Error, Result};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A source of bencode input for a [`Deserializer`](super::Deserializer).
+///
+/// This trait is sealed. It is implemented by [`SliceRead`], [`DigestRead`], with the `std`
+/// feature by [`IoRead`], and with the `bytes` feature by [`BytesRead`].
+pub trait Read: private::Sealed {
+    /// Look at the next byte of input without consuming it. Returns `None` at the end of input.
+    #[doc(hidden)]
+    fn peek_byte(&mut self) -> Result<Option<u8>>;
+
+    /// Fill `buf` completely, failing with [`Error::EndOfStream`] if the input ends first.
+    #[doc(hidden)]
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()>;
+
+    /// Consume `len` bytes without returning them, failing with [`Error::EndOfStream`] if the
+    /// input ends first.
+    #[doc(hidden)]
+    fn discard(&mut self, len: usize) -> Result<()>;
+
+    /// The number of bytes left in the input, if the source knows it.
+    #[doc(hidden)]
+    fn remaining(&self) -> Option<usize> {
+        None
+    }
+
+    /// Consume the input up to and including the next `delim`, appending the bytes before it to
+    /// `buf`. Returns `false` without consuming anything if the source cannot search its input
+    /// in bulk, or the delimiter is not in it; the caller then reads byte by byte.
+    #[doc(hidden)]
+    fn read_until(&mut self, _delim: u8, _buf: &mut Vec<u8>) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Receive bytes that have been read as part of the selected subtree.
+    #[doc(hidden)]
+    fn digest(&mut self, _bytes: &[u8]) {}
+
+    /// The rest of the input as a shared buffer that can be sliced without copying, if the
+    /// source holds one.
+    #[cfg(feature = "bytes")]
+    #[doc(hidden)]
+    fn shared(&self) -> Option<bytes::Bytes> {
+        None
+    }
+}
+
+/// Bencode input from a byte slice. Available without `std`.
+#[derive(Debug)]
+pub struct SliceRead<'a> {
+    slice: &'a [u8],
+    index: usize,
+}
+
+impl<'a> SliceRead<'a> {
+    /// Create a source reading `slice` from the start.
+    pub fn new(slice: &'a [u8]) -> SliceRead<'a> {
+        SliceRead { slice, index: 0 }
+    }
+}
+
+impl private::Sealed for SliceRead<'_> {}
+
+impl Read for SliceRead<'_> {
+    fn peek_byte(&mut self) -> Result<Option<u8>> {
+        Ok(self.slice.get(self.index).copied())
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let end = self
+            .index
+            .checked_add(buf.len())
+            .filter(|&end| end <= self.slice.len())
+            .ok_or(Error::EndOfStream)?;
+        buf.copy_from_slice(&self.slice[self.index..end]);
+        self.index = end;
+        Ok(())
+    }
+
+    fn discard(&mut self, len: usize) -> Result<()> {
+        self.index = self
+            .index
+            .checked_add(len)
+            .filter(|&end| end <= self.slice.len())
+            .ok_or(Error::EndOfStream)?;
+        Ok(())
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.slice.len() - self.index)
+    }
+
+    fn read_until(&mut self, delim: u8, buf: &mut Vec<u8>) -> Result<bool> {
+        let rest = &self.slice[self.index..];
+        match memchr::memchr(delim, rest) {
+            Some(len) => {
+                buf.extend_from_slice(&rest[..len]);
+                self.index += len + 1;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Bencode input from a [`bytes::Bytes`] buffer. Available with the `bytes` feature.
+///
+/// Byte strings decoded with [`bytes::deserialize`](crate::bytes::deserialize) are slices of
+/// the buffer, which share its memory instead of being copied.
+#[cfg(feature = "bytes")]
+#[derive(Debug)]
+pub struct BytesRead {
+    bytes: bytes::Bytes,
+    index: usize,
+}
+
+#[cfg(feature = "bytes")]
+impl BytesRead {
+    /// Create a source reading `bytes` from the start.
+    pub fn new(bytes: bytes::Bytes) -> BytesRead {
+        BytesRead { bytes, index: 0 }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl private::Sealed for BytesRead {}
+
+#[cfg(feature = "bytes")]
+impl Read for BytesRead {
+    fn peek_byte(&mut self) -> Result<Option<u8>> {
+        SliceRead {
+            slice: &self.bytes,
+            index: self.index,
+        }
+        .peek_byte()
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let mut slice = SliceRead {
+            slice: &self.bytes,
+            index: self.index,
+        };
+        slice.read_exact(buf)?;
+        self.index = slice.index;
+        Ok(())
+    }
+
+    fn discard(&mut self, len: usize) -> Result<()> {
+        let mut slice = SliceRead {
+            slice: &self.bytes,
+            index: self.index,
+        };
+        slice.discard(len)?;
+        self.index = slice.index;
+        Ok(())
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.bytes.len() - self.index)
+    }
+
+    fn read_until(&mut self, delim: u8, buf: &mut Vec<u8>) -> Result<bool> {
+        let mut slice = SliceRead {
+            slice: &self.bytes,
+            index: self.index,
+        };
+        let found = slice.read_until(delim, buf)?;
+        self.index = slice.index;
+        Ok(found)
+    }
+
+    fn shared(&self) -> Option<bytes::Bytes> {
+        Some(self.bytes.slice(self.index..))
+    }
+}
+
+/// Bencode input from a [`std::io::Read`]er.
+///
+/// Short and interrupted reads are retried, so the reader does not need to be buffered for
+/// correctness, though wrapping it in a [`BufReader`](std::io::BufReader) is much faster.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoRead<R> {
+    reader: R,
+    peeked: Option<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> IoRead<R> {
+    /// Create a source reading from `reader`.
+    pub fn new(reader: R) -> IoRead<R> {
+        IoRead {
+            reader,
+            peeked: None,
+        }
+    }
+
+    /// Give back the reader, together with the byte that was read from it to look ahead but not
+    /// consumed. That byte comes before anything left in the reader.
+    pub fn into_inner(self) -> (R, Option<u8>) {
+        (self.reader, self.peeked)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> private::Sealed for IoRead<R> {}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> Read for IoRead<R> {
+    fn peek_byte(&mut self) -> Result<Option<u8>> {
+        if self.peeked.is_none() {
+            let mut buf = [0; 1];
+            loop {
+                match self.reader.read(&mut buf) {
+                    Ok(0) => return Ok(None),
+                    Ok(_) => break,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(Error::Io(e)),
+                }
+            }
+            self.peeked = Some(buf[0]);
+        }
+        Ok(self.peeked)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let mut filled = 0;
+        if let (Some(byte), Some(first)) = (self.peeked, buf.first_mut()) {
+            *first = byte;
+            self.peeked = None;
+            filled = 1;
+        }
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) => return Err(Error::EndOfStream),
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::Io(e)),
+            }
+        }
+        Ok(())
+    }
+
+    fn discard(&mut self, mut len: usize) -> Result<()> {
+        if len > 0 && self.peeked.take().is_some() {
+            len -= 1;
+        }
+        let len = len as u64;
+        let copied = io::copy(&mut io::Read::take(&mut self.reader, len), &mut io::sink())
+            .map_err(Error::Io)?;
+        if copied < len {
+            return Err(Error::EndOfStream);
+        }
+        Ok(())
+    }
+}
+
+/// Bencode input that passes the raw encoding of one value to a callback as it is read.
+///
+/// Created by [`Deserializer::with_digest`](super::Deserializer::with_digest).
+pub struct DigestRead<R, F> {
+    reader: R,
+    digest: F,
+}
+
+impl<R, F> DigestRead<R, F> {
+    pub(crate) fn new(reader: R, digest: F) -> DigestRead<R, F> {
+        DigestRead { reader, digest }
+    }
+}
+
+impl<R, F> private::Sealed for DigestRead<R, F> {}
+
+impl<R: Read, F: FnMut(&[u8])> Read for DigestRead<R, F> {
+    fn peek_byte(&mut self) -> Result<Option<u8>> {
+        self.reader.peek_byte()
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.reader.read_exact(buf)
+    }
+
+    fn discard(&mut self, len: usize) -> Result<()> {
+        self.reader.discard(len)
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        self.reader.remaining()
+    }
+
+    fn read_until(&mut self, delim: u8, buf: &mut Vec<u8>) -> Result<bool> {
+        self.reader.read_until(delim, buf)
+    }
+
+    fn digest(&mut self, bytes: &[u8]) {
+        (self.digest)(bytes)
+    }
+
+    #[cfg(feature = "bytes")]
+    fn shared(&self) -> Option<bytes::Bytes> {
+        self.reader.shared()
+    }
+}
+
+impl<R: fmt::Debug, F> fmt::Debug for DigestRead<R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestRead")
+            .field("reader", &self.reader)
+            .finish_non_exhaustive()
+    }
+}
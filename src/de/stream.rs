@@ -0,0 +1,72 @@
+//! Deserialize a stream of consecutive bencode values.
+
+use super::{Deserializer, Read};
+use crate::error::Result;
+use core::marker::PhantomData;
+use serde::de;
+
+/// An iterator that deserializes a stream of back-to-back bencode values.
+///
+/// Bencode values are self-delimiting, so several of them can be concatenated without any
+/// separator, as happens with KRPC messages read from a single connection. The iterator yields
+/// one `Result<T>` per value and stops at the end of the input, or after the first error.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{Deserializer, StreamDeserializer};
+/// use serde_bencode::value::Value;
+///
+/// let de = Deserializer::from_slice(b"i1e3:twoli3ee");
+/// let mut stream = StreamDeserializer::<_, Value>::new(de);
+/// assert_eq!(stream.next().unwrap().unwrap(), Value::Int(1));
+/// assert_eq!(stream.byte_offset(), 3);
+/// assert_eq!(stream.next().unwrap().unwrap(), Value::from("two"));
+/// assert_eq!(stream.next().unwrap().unwrap(), Value::List(vec![Value::Int(3)]));
+/// assert!(stream.next().is_none());
+/// ```
+pub struct StreamDeserializer<'de, R: Read, T> {
+    de: Deserializer<R>,
+    failed: bool,
+    output: PhantomData<T>,
+    lifetime: PhantomData<&'de ()>,
+}
+
+impl<'de, R: Read, T: de::Deserialize<'de>> StreamDeserializer<'de, R, T> {
+    /// Create a new stream deserializer reading from `de`.
+    ///
+    /// The options of `de` apply to each value separately, except for the maximum input length,
+    /// which limits the stream as a whole.
+    pub fn new(de: Deserializer<R>) -> StreamDeserializer<'de, R, T> {
+        StreamDeserializer {
+            de,
+            failed: false,
+            output: PhantomData,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// Number of bytes consumed so far, which is the offset just past the last value that was
+    /// returned.
+    ///
+    /// After an error this points somewhere inside the value that failed.
+    pub fn byte_offset(&self) -> usize {
+        self.de.offset
+    }
+}
+
+impl<'de, R: Read, T: de::Deserialize<'de>> Iterator for StreamDeserializer<'de, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+        let result = match self.de.peek_byte() {
+            Ok(None) => return None,
+            Ok(Some(_)) => T::deserialize(&mut self.de),
+            Err(e) => Err(e),
+        };
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
@@ -0,0 +1,146 @@
+//! The deserializer's side of building a [`Value`](crate::value::Value) without recursing. See
+//! the token stream described in `value::tree`.
+
+use super::{visit_bytes, Deserializer, FrameKind, ParseResult, Read};
+use crate::error::{Error, Result};
+use crate::value::VALUE_TOKEN;
+use alloc::string::ToString;
+use serde::de::{self, IntoDeserializer};
+use serde::forward_to_deserialize_any;
+
+/// A single-entry map from [`VALUE_TOKEN`] to the token stream of the next value.
+pub(super) struct TreeAccess<'a, R: Read> {
+    de: &'a mut Deserializer<R>,
+    key_read: bool,
+}
+
+impl<'a, R: Read> TreeAccess<'a, R> {
+    pub(super) fn new(de: &'a mut Deserializer<R>) -> TreeAccess<'a, R> {
+        TreeAccess {
+            de,
+            key_read: false,
+        }
+    }
+}
+
+impl<'de, 'a, R: Read> de::MapAccess<'de> for TreeAccess<'a, R> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.key_read {
+            return Ok(None);
+        }
+        self.key_read = true;
+        seed.deserialize(VALUE_TOKEN.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        seed.deserialize(Tokens::new(self.de))
+    }
+}
+
+/// The tokens of the next value, as a sequence.
+struct Tokens<'a, R: Read> {
+    de: &'a mut Deserializer<R>,
+    /// The nesting depth the value starts and ends at.
+    depth: usize,
+    done: bool,
+}
+
+impl<'a, R: Read> Tokens<'a, R> {
+    fn new(de: &'a mut Deserializer<R>) -> Tokens<'a, R> {
+        // A container whose start has been pushed back is already on the stack.
+        let depth = match de.next {
+            Some(ParseResult::List | ParseResult::Map) => de.stack.len() - 1,
+            _ => de.stack.len(),
+        };
+        Tokens {
+            de,
+            depth,
+            done: false,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<ParseResult> {
+        loop {
+            let key_position = self.de.next.is_none()
+                && matches!(
+                    self.de.stack.last(),
+                    Some(frame) if frame.kind == FrameKind::Dict && frame.items % 2 == 0
+                );
+            let token = self.de.parse()?;
+            if key_position && token != ParseResult::End && self.de.duplicate {
+                // A repeated key under `DuplicateKeyPolicy::FirstWins`.
+                self.de.skip_value()?;
+                continue;
+            }
+            return Ok(token);
+        }
+    }
+}
+
+impl<'de, 'a, R: Read> de::Deserializer<'de> for Tokens<'a, R> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a, R: Read> de::SeqAccess<'de> for Tokens<'a, R> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        if self.done {
+            return Ok(None);
+        }
+        let token = self.next_token()?;
+        if self.de.stack.len() < self.depth {
+            return Err(Error::Syntax(
+                "Expected a value, found the end of a container".to_string(),
+            ));
+        }
+        self.done = self.de.stack.len() == self.depth;
+        seed.deserialize(TokenDeserializer(token)).map(Some)
+    }
+}
+
+/// Presents a single token to the visitor of `value::tree`.
+struct TokenDeserializer(ParseResult);
+
+impl<'de> de::Deserializer<'de> for TokenDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            ParseResult::Int(i) => visitor.visit_i64(i),
+            ParseResult::Int128(i) => visitor.visit_i128(i),
+            ParseResult::Uint128(i) => visitor.visit_u128(i),
+            #[cfg(feature = "arbitrary_precision")]
+            ParseResult::BigInt(digits) => visitor.visit_string(digits),
+            ParseResult::Bytes(bytes) => visit_bytes(bytes, visitor),
+            ParseResult::List => {
+                visitor.visit_seq(de::value::SeqDeserializer::new(core::iter::empty::<()>()))
+            }
+            ParseResult::Map => visitor.visit_map(de::value::MapDeserializer::new(
+                core::iter::empty::<((), ())>(),
+            )),
+            ParseResult::End => visitor.visit_unit(),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
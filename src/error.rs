@@ -1,22 +1,49 @@
 //! Structures used to handle errors when serializing or deserializing goes wrong.
 
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::fmt::Display;
+use core::result::Result as StdResult;
 use serde::de::Error as DeError;
 use serde::de::{Expected, Unexpected};
 use serde::ser::Error as SerError;
+#[cfg(feature = "std")]
 use std::error::Error as StdError;
-use std::fmt;
-use std::fmt::Display;
-use std::io::Error as IoError;
-use std::result::Result as StdResult;
+#[cfg(feature = "std")]
+use std::io;
 
 /// Alias for `Result<T, serde_bencode::Error>`.
 pub type Result<T> = StdResult<T, Error>;
 
 /// Represents all possible errors which can occur when serializing or deserializing bencode.
+///
+/// When a value inside a dictionary or list cannot be deserialized, the message ends with the
+/// path to it, such as ``in field `info.files[3].length` ``.
+///
+/// New variants may be added in future releases. Use [`Error::kind`] to tell broad classes of
+/// errors apart without matching on every variant.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::error::ErrorKind;
+/// use serde_bencode::value::Value;
+///
+/// let error = serde_bencode::from_bytes::<Value>(b"i1x2e").unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::Syntax);
+/// let error = serde_bencode::from_bytes::<u8>(b"i300e").unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::Data);
+/// ```
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Raised when an IO error occurred.
-    IoError(IoError),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+
+    /// Raised when the input is not well-formed bencode, for example because of an invalid
+    /// character, a malformed integer or length prefix, or a dictionary key without a value.
+    Syntax(String),
 
     /// Raised when the value being deserialized is of the incorrect type.
     InvalidType(String),
@@ -43,11 +70,84 @@ pub enum Error {
     /// Raised when deserializing a struct, but there is more than one field with the same name.
     DuplicateField(String),
 
-    /// Catchall for any other kind of error.
+    /// Raised in strict mode when the input is valid bencode but not in canonical form, for
+    /// example an integer with leading zeros or dictionary keys out of order.
+    NonCanonical(String),
+
+    /// Raised when the input exceeds one of the limits configured on the deserializer, such as
+    /// the maximum nesting depth or byte string length.
+    LimitExceeded(String),
+
+    /// An error reported by a `Serialize` or `Deserialize` implementation through
+    /// [`serde::ser::Error::custom`] or [`serde::de::Error::custom`].
     Custom(String),
 
     /// Unexpected end of input stream.
     EndOfStream,
+
+    /// Raised when a complete value was decoded, but more input follows it.
+    TrailingData,
+}
+
+/// The broad class of an [`Error`], as returned by [`Error::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Reading from or writing to an I/O stream failed.
+    Io,
+    /// The input is not well-formed bencode, is not canonical in strict mode, or continues after
+    /// the value.
+    Syntax,
+    /// The input ended in the middle of a value.
+    Eof,
+    /// The input is well-formed, but does not match the type it is deserialized as, or a value
+    /// cannot be represented in bencode.
+    Data,
+    /// The input exceeds one of the configured limits.
+    Limit,
+    /// A `Serialize` or `Deserialize` implementation reported an error of its own.
+    Custom,
+}
+
+impl Error {
+    /// The broad class of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(_) => ErrorKind::Io,
+            Error::Syntax(_) | Error::NonCanonical(_) | Error::TrailingData => ErrorKind::Syntax,
+            Error::EndOfStream => ErrorKind::Eof,
+            Error::InvalidType(_)
+            | Error::InvalidValue(_)
+            | Error::InvalidLength(_)
+            | Error::UnknownVariant(_)
+            | Error::UnknownField(_)
+            | Error::MissingField(_)
+            | Error::DuplicateField(_) => ErrorKind::Data,
+            Error::LimitExceeded(_) => ErrorKind::Limit,
+            Error::Custom(_) => ErrorKind::Custom,
+        }
+    }
+
+    /// Name the path to the value that could not be deserialized in the message, e.g.
+    /// `info.files[3].length`.
+    pub(crate) fn in_field(self, path: &str) -> Error {
+        let annotate = |s: String| format!("{} in field `{}`", s, path);
+        match self {
+            Error::Syntax(s) => Error::Syntax(annotate(s)),
+            Error::InvalidType(s) => Error::InvalidType(annotate(s)),
+            Error::InvalidValue(s) => Error::InvalidValue(annotate(s)),
+            Error::InvalidLength(s) => Error::InvalidLength(annotate(s)),
+            Error::UnknownVariant(s) => Error::UnknownVariant(annotate(s)),
+            Error::UnknownField(s) => Error::UnknownField(annotate(s)),
+            Error::MissingField(s) => Error::MissingField(annotate(s)),
+            Error::DuplicateField(s) => Error::DuplicateField(annotate(s)),
+            Error::NonCanonical(s) => Error::NonCanonical(annotate(s)),
+            Error::LimitExceeded(s) => Error::LimitExceeded(annotate(s)),
+            Error::Custom(s) => Error::Custom(annotate(s)),
+            error => error,
+        }
+    }
 }
 
 impl SerError for Error {
@@ -96,19 +196,32 @@ impl DeError for Error {
     }
 }
 
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+#[cfg(feature = "std")]
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match *self {
-            Error::IoError(ref error) => Some(error),
+            Error::Io(ref error) => Some(error),
             _ => None,
         }
     }
 }
 
+#[cfg(not(feature = "std"))]
+impl serde::de::StdError for Error {}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let message = match *self {
-            Error::IoError(ref error) => return error.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Io(ref error) => return error.fmt(f),
+            Error::Syntax(ref s) => s,
             Error::InvalidType(ref s) => s,
             Error::InvalidValue(ref s) => s,
             Error::InvalidLength(ref s) => s,
@@ -116,8 +229,11 @@ impl fmt::Display for Error {
             Error::UnknownField(ref s) => s,
             Error::MissingField(ref s) => s,
             Error::DuplicateField(ref s) => s,
+            Error::NonCanonical(ref s) => s,
+            Error::LimitExceeded(ref s) => s,
             Error::Custom(ref s) => s,
             Error::EndOfStream => "End of stream",
+            Error::TrailingData => "Trailing data after value",
         };
         f.write_str(message)
     }
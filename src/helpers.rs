@@ -0,0 +1,16 @@
+//! Adapters for fields with a conventional bencode representation, for use with
+//! `#[serde(with = "...")]`.
+
+pub mod base32;
+pub mod base64;
+#[cfg(feature = "arbitrary_precision")]
+pub mod big_int;
+#[cfg(feature = "std")]
+pub mod compact_peers;
+pub mod hex;
+pub mod pieces;
+pub(crate) mod text;
+#[cfg(feature = "std")]
+pub mod unix_seconds;
+
+pub use self::text::{Codec, Field};
@@ -0,0 +1,102 @@
+//! Store a base32 encoded string, such as an info-hash from a magnet link, as a raw byte string.
+//!
+//! This uses the RFC 4648 alphabet. The field can be a `String` holding the base32 text, which is
+//! decoded when serializing and encoded in uppercase without padding when deserializing, or a
+//! `[u8; N]` holding the raw bytes, which also accepts base32 text of the right length when
+//! deserializing. Decoding ignores case and trailing `=` padding.
+//!
+//! # Examples
+//! ```
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Magnet {
+//!     #[serde(with = "serde_bencode::helpers::base32")]
+//!     btih: [u8; 5],
+//! }
+//!
+//! let magnet: Magnet = serde_bencode::from_bytes(b"d4:btih8:MZXW6YTBe").unwrap();
+//! assert_eq!(&magnet.btih, b"fooba");
+//! assert_eq!(serde_bencode::to_bytes(&magnet).unwrap(), b"d4:btih5:foobae");
+//! ```
+
+use super::text::{self, Codec, Field};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{de, ser};
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+struct Base32;
+
+impl text::private::Sealed for Base32 {}
+
+impl Codec for Base32 {
+    const NAME: &'static str = "base32";
+
+    fn encode(bytes: &[u8]) -> String {
+        let mut text = String::with_capacity(bytes.len().div_ceil(5) * 8);
+        let mut buf = 0u16;
+        let mut bits = 0;
+        for &b in bytes {
+            buf = buf << 8 | u16::from(b);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                text.push(ALPHABET[usize::from(buf >> bits & 0x1f)] as char);
+            }
+        }
+        if bits > 0 {
+            text.push(ALPHABET[usize::from(buf << (5 - bits) & 0x1f)] as char);
+        }
+        text
+    }
+
+    fn decode(text: &[u8]) -> Option<Vec<u8>> {
+        let end = text.iter().rposition(|&c| c != b'=').map_or(0, |i| i + 1);
+        let mut bytes = Vec::with_capacity(end * 5 / 8);
+        let mut buf = 0u16;
+        let mut bits = 0;
+        for &c in &text[..end] {
+            let value = ALPHABET.iter().position(|&a| a == c.to_ascii_uppercase())?;
+            buf = buf << 5 | value as u16;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buf >> bits) as u8);
+            }
+        }
+        // Only zero bits that do not make up a whole byte may be left over.
+        if bits >= 5 || buf & ((1 << bits) - 1) != 0 {
+            return None;
+        }
+        Some(bytes)
+    }
+}
+
+/// Serialize base32 text, or raw bytes, as a raw byte string.
+///
+/// # Errors
+///
+/// Fails if a string field is not valid base32.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Field,
+    S: ser::Serializer,
+{
+    text::serialize::<Base32, T, S>(value, serializer)
+}
+
+/// Deserialize a raw byte string into base32 text, or into raw bytes.
+///
+/// # Errors
+///
+/// Fails if the input is not a byte string, or an array field gets a byte string that is neither
+/// the right length nor base32 text of the right length.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Field,
+    D: de::Deserializer<'de>,
+{
+    text::deserialize::<Base32, T, D>(deserializer)
+}
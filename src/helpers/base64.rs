@@ -0,0 +1,105 @@
+//! Store a base64 encoded string as a raw byte string.
+//!
+//! This uses the standard RFC 4648 alphabet. The field can be a `String` holding the base64
+//! text, which is decoded when serializing and encoded with padding when deserializing, or a
+//! `[u8; N]` holding the raw bytes, which also accepts base64 text of the right length when
+//! deserializing. Decoding accepts text with or without padding.
+//!
+//! # Examples
+//! ```
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Token {
+//!     #[serde(with = "serde_bencode::helpers::base64")]
+//!     secret: String,
+//! }
+//!
+//! let token: Token = serde_bencode::from_bytes(b"d6:secret4:spame").unwrap();
+//! assert_eq!(token.secret, "c3BhbQ==");
+//! assert_eq!(serde_bencode::to_bytes(&token).unwrap(), b"d6:secret4:spame");
+//! ```
+
+use super::text::{self, Codec, Field};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{de, ser};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) struct Base64;
+
+impl text::private::Sealed for Base64 {}
+
+impl Codec for Base64 {
+    const NAME: &'static str = "base64";
+
+    fn encode(bytes: &[u8]) -> String {
+        let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let buf = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |buf, (i, &b)| buf | u32::from(b) << (16 - 8 * i));
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    text.push(ALPHABET[(buf >> (18 - 6 * i) & 0x3f) as usize] as char);
+                } else {
+                    text.push('=');
+                }
+            }
+        }
+        text
+    }
+
+    fn decode(text: &[u8]) -> Option<Vec<u8>> {
+        let end = text.iter().rposition(|&c| c != b'=').map_or(0, |i| i + 1);
+        if text.len() - end > 2 {
+            return None;
+        }
+        let mut bytes = Vec::with_capacity(end * 3 / 4);
+        let mut buf = 0u32;
+        let mut bits = 0;
+        for &c in &text[..end] {
+            let value = ALPHABET.iter().position(|&a| a == c)?;
+            buf = buf << 6 | value as u32;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buf >> bits) as u8);
+            }
+        }
+        // Only zero bits that do not make up a whole byte may be left over.
+        if bits >= 6 || buf & ((1 << bits) - 1) != 0 {
+            return None;
+        }
+        Some(bytes)
+    }
+}
+
+/// Serialize base64 text, or raw bytes, as a raw byte string.
+///
+/// # Errors
+///
+/// Fails if a string field is not valid base64.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Field,
+    S: ser::Serializer,
+{
+    text::serialize::<Base64, T, S>(value, serializer)
+}
+
+/// Deserialize a raw byte string into base64 text, or into raw bytes.
+///
+/// # Errors
+///
+/// Fails if the input is not a byte string, or an array field gets a byte string that is neither
+/// the right length nor base64 text of the right length.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Field,
+    D: de::Deserializer<'de>,
+{
+    text::deserialize::<Base64, T, D>(deserializer)
+}
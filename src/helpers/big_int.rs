@@ -0,0 +1,43 @@
+//! Encode a string of decimal digits as a bencode integer of arbitrary size.
+//!
+//! # Examples
+//! ```
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Stats {
+//!     #[serde(with = "serde_bencode::helpers::big_int")]
+//!     downloaded: String,
+//! }
+//!
+//! let encoded = "d10:downloadedi99999999999999999999999999999999999999999ee";
+//! let stats: Stats = serde_bencode::from_str(encoded).unwrap();
+//! assert_eq!(stats.downloaded, "99999999999999999999999999999999999999999");
+//! assert_eq!(serde_bencode::to_string(&stats).unwrap(), encoded);
+//! ```
+
+use crate::value::BigInt;
+use alloc::string::String;
+use serde::{de, ser, Deserialize, Serialize};
+
+/// Serialize canonical decimal digits as an integer.
+///
+/// # Errors
+///
+/// Fails if `digits` is not the canonical decimal representation of an integer.
+pub fn serialize<T, S>(digits: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: AsRef<str> + ?Sized,
+    S: ser::Serializer,
+{
+    let n: BigInt = digits.as_ref().parse().map_err(ser::Error::custom)?;
+    n.serialize(serializer)
+}
+
+/// Deserialize an integer of any size into its canonical decimal digits.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    BigInt::deserialize(deserializer).map(BigInt::into_string)
+}
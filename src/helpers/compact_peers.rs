@@ -0,0 +1,142 @@
+//! Encode a list of peer addresses in the compact format used by trackers.
+//!
+//! IPv4 peers ([BEP 23]) are packed into 6 bytes each: the address followed by the port in
+//! network byte order. IPv6 peers ([BEP 7]) take 18 bytes each. The whole list is a single byte
+//! string, as in the `peers` and `peers6` keys of a tracker response.
+//!
+//! [BEP 23]: https://www.bittorrent.org/beps/bep_0023.html
+//! [BEP 7]: https://www.bittorrent.org/beps/bep_0007.html
+//!
+//! # Examples
+//! ```
+//! use serde_derive::{Deserialize, Serialize};
+//! use std::net::{SocketAddrV4, SocketAddrV6};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Response {
+//!     #[serde(with = "serde_bencode::helpers::compact_peers")]
+//!     peers: Vec<SocketAddrV4>,
+//!     #[serde(with = "serde_bencode::helpers::compact_peers")]
+//!     peers6: Vec<SocketAddrV6>,
+//! }
+//!
+//! let response = Response {
+//!     peers: vec!["10.0.0.1:6881".parse().unwrap()],
+//!     peers6: vec![],
+//! };
+//! let encoded = serde_bencode::to_bytes(&response).unwrap();
+//! assert_eq!(encoded, b"d5:peers6:\x0a\x00\x00\x01\x1a\xe16:peers60:e");
+//! assert_eq!(serde_bencode::from_bytes::<Response>(&encoded).unwrap(), response);
+//! ```
+
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use core::marker::PhantomData;
+use serde::{de, ser};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+pub(crate) mod private {
+    pub trait Sealed {}
+}
+
+/// A peer address with a fixed-size compact encoding.
+///
+/// This trait is sealed. It is implemented by [`SocketAddrV4`] and [`SocketAddrV6`], and with the
+/// `krpc` feature by `krpc::NodeInfo`, the compact node info of the DHT.
+pub trait CompactPeer: private::Sealed + Sized {
+    /// The length of one encoded address.
+    const LEN: usize;
+
+    #[doc(hidden)]
+    fn write(&self, buf: &mut Vec<u8>);
+
+    /// Decode an address from exactly [`LEN`](CompactPeer::LEN) bytes.
+    #[doc(hidden)]
+    fn read(bytes: &[u8]) -> Self;
+}
+
+impl private::Sealed for SocketAddrV4 {}
+
+impl CompactPeer for SocketAddrV4 {
+    const LEN: usize = 6;
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.ip().octets());
+        buf.extend_from_slice(&self.port().to_be_bytes());
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let ip: [u8; 4] = bytes[..4].try_into().unwrap();
+        let port = u16::from_be_bytes([bytes[4], bytes[5]]);
+        SocketAddrV4::new(Ipv4Addr::from(ip), port)
+    }
+}
+
+impl private::Sealed for SocketAddrV6 {}
+
+impl CompactPeer for SocketAddrV6 {
+    const LEN: usize = 18;
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.ip().octets());
+        buf.extend_from_slice(&self.port().to_be_bytes());
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let ip: [u8; 16] = bytes[..16].try_into().unwrap();
+        let port = u16::from_be_bytes([bytes[16], bytes[17]]);
+        SocketAddrV6::new(Ipv6Addr::from(ip), port, 0, 0)
+    }
+}
+
+/// Serialize peer addresses as one packed byte string.
+///
+/// The flow info and scope id of IPv6 addresses are not part of the compact format and are
+/// dropped.
+pub fn serialize<A, S>(peers: &[A], serializer: S) -> Result<S::Ok, S::Error>
+where
+    A: CompactPeer,
+    S: ser::Serializer,
+{
+    let mut buf = Vec::with_capacity(peers.len() * A::LEN);
+    for peer in peers {
+        peer.write(&mut buf);
+    }
+    serializer.serialize_bytes(&buf)
+}
+
+/// Deserialize a packed byte string into peer addresses.
+///
+/// # Errors
+///
+/// Fails if the input is not a byte string, or its length is not a multiple of the size of one
+/// address.
+pub fn deserialize<'de, A, D>(deserializer: D) -> Result<Vec<A>, D::Error>
+where
+    A: CompactPeer,
+    D: de::Deserializer<'de>,
+{
+    deserializer.deserialize_bytes(PeersVisitor(PhantomData))
+}
+
+struct PeersVisitor<A>(PhantomData<A>);
+
+impl<'de, A: CompactPeer> de::Visitor<'de> for PeersVisitor<A> {
+    type Value = Vec<A>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "a byte string of compact peers, {} bytes each",
+            A::LEN
+        )
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<A>, E> {
+        if !v.len().is_multiple_of(A::LEN) {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        Ok(v.chunks_exact(A::LEN).map(A::read).collect())
+    }
+}
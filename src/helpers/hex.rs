@@ -0,0 +1,86 @@
+//! Store a hex encoded string, such as an info-hash from a magnet link, as a raw byte string.
+//!
+//! The field can be a `String` holding the hex text, which is decoded when serializing and
+//! encoded in lowercase when deserializing, or a `[u8; N]` holding the raw bytes, which also
+//! accepts hex text of the right length when deserializing.
+//!
+//! # Examples
+//! ```
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Scrape {
+//!     #[serde(with = "serde_bencode::helpers::hex")]
+//!     info_hash: String,
+//! }
+//!
+//! let scrape = Scrape {
+//!     info_hash: "0123456789abcdef".to_string(),
+//! };
+//! let encoded = serde_bencode::to_bytes(&scrape).unwrap();
+//! assert_eq!(encoded, b"d9:info_hash8:\x01\x23\x45\x67\x89\xab\xcd\xefe");
+//! assert_eq!(serde_bencode::from_bytes::<Scrape>(&encoded).unwrap(), scrape);
+//! ```
+
+use super::text::{self, Codec, Field};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{de, ser};
+
+pub(crate) struct Hex;
+
+impl text::private::Sealed for Hex {}
+
+impl Codec for Hex {
+    const NAME: &'static str = "hex";
+
+    fn encode(bytes: &[u8]) -> String {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut text = String::with_capacity(bytes.len() * 2);
+        for &b in bytes {
+            text.push(DIGITS[(b >> 4) as usize] as char);
+            text.push(DIGITS[(b & 0xf) as usize] as char);
+        }
+        text
+    }
+
+    fn decode(text: &[u8]) -> Option<Vec<u8>> {
+        if !text.len().is_multiple_of(2) {
+            return None;
+        }
+        text.chunks_exact(2)
+            .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+            .collect()
+    }
+}
+
+fn digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Serialize hex text, or raw bytes, as a raw byte string.
+///
+/// # Errors
+///
+/// Fails if a string field is not valid hex.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Field,
+    S: ser::Serializer,
+{
+    text::serialize::<Hex, T, S>(value, serializer)
+}
+
+/// Deserialize a raw byte string into hex text, or into raw bytes.
+///
+/// # Errors
+///
+/// Fails if the input is not a byte string, or an array field gets a byte string that is neither
+/// the right length nor hex text of the right length.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Field,
+    D: de::Deserializer<'de>,
+{
+    text::deserialize::<Hex, T, D>(deserializer)
+}
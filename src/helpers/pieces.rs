@@ -0,0 +1,69 @@
+//! Split the `pieces` of a metainfo file into one hash per piece.
+//!
+//! The `pieces` key holds the hashes of all pieces concatenated into a single byte string: 20
+//! bytes of SHA-1 each in a v1 torrent. This adapter converts it to and from a `Vec<[u8; N]>`,
+//! where `N` is the size of one hash, so the same module handles the 32-byte SHA-256 hashes of
+//! v2 piece layers.
+//!
+//! # Examples
+//! ```
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Info {
+//!     #[serde(with = "serde_bencode::helpers::pieces")]
+//!     pieces: Vec<[u8; 20]>,
+//! }
+//!
+//! let mut input = b"d6:pieces40:".to_vec();
+//! input.extend_from_slice(&[1; 20]);
+//! input.extend_from_slice(&[2; 20]);
+//! input.push(b'e');
+//! let info: Info = serde_bencode::from_bytes(&input).unwrap();
+//! assert_eq!(info.pieces, vec![[1; 20], [2; 20]]);
+//! assert_eq!(serde_bencode::to_bytes(&info).unwrap(), input);
+//! ```
+
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use serde::{de, ser};
+
+/// Serialize piece hashes as one concatenated byte string.
+pub fn serialize<S: ser::Serializer, const N: usize>(
+    hashes: &[[u8; N]],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(&hashes.concat())
+}
+
+/// Deserialize a concatenated byte string into piece hashes of `N` bytes each.
+///
+/// # Errors
+///
+/// Fails if the input is not a byte string, or its length is not a multiple of `N`.
+pub fn deserialize<'de, D: de::Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<Vec<[u8; N]>, D::Error> {
+    deserializer.deserialize_bytes(PiecesVisitor::<N>)
+}
+
+struct PiecesVisitor<const N: usize>;
+
+impl<'de, const N: usize> de::Visitor<'de> for PiecesVisitor<N> {
+    type Value = Vec<[u8; N]>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a byte string of piece hashes, {} bytes each", N)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<[u8; N]>, E> {
+        if N == 0 || !v.len().is_multiple_of(N) {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        // Each chunk has exactly `N` bytes.
+        Ok(v.chunks_exact(N)
+            .map(|hash| hash.try_into().unwrap())
+            .collect())
+    }
+}
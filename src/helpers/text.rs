@@ -0,0 +1,106 @@
+//! Shared implementation of the adapters for byte strings with a text representation.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use core::marker::PhantomData;
+use serde::{de, ser};
+
+pub(super) mod private {
+    pub trait Sealed {}
+}
+
+/// A binary-to-text encoding, used by the [`hex`](super::hex), [`base32`](super::base32) and
+/// [`base64`](super::base64) helpers.
+///
+/// This trait is sealed. It is only implemented by the encodings of those helpers.
+pub trait Codec: private::Sealed {
+    #[doc(hidden)]
+    const NAME: &'static str;
+
+    #[doc(hidden)]
+    fn encode(bytes: &[u8]) -> String;
+
+    /// Decode `text`, returning `None` if it is not valid.
+    #[doc(hidden)]
+    fn decode(text: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// A field type that the [`hex`](super::hex), [`base32`](super::base32) and
+/// [`base64`](super::base64) helpers can store as a raw byte string.
+///
+/// A `String` holds the encoded text, and is decoded when serializing. A `[u8; N]` holds the raw
+/// bytes, and also accepts the encoded text when deserializing.
+///
+/// This trait is sealed. It is implemented by [`String`] and `[u8; N]`.
+pub trait Field: private::Sealed + Sized {
+    #[doc(hidden)]
+    fn to_raw<C: Codec>(&self) -> Option<Cow<'_, [u8]>>;
+
+    #[doc(hidden)]
+    fn from_raw<C: Codec>(bytes: &[u8]) -> Option<Self>;
+}
+
+impl private::Sealed for String {}
+
+impl Field for String {
+    fn to_raw<C: Codec>(&self) -> Option<Cow<'_, [u8]>> {
+        C::decode(self.as_bytes()).map(Cow::Owned)
+    }
+
+    fn from_raw<C: Codec>(bytes: &[u8]) -> Option<Self> {
+        Some(C::encode(bytes))
+    }
+}
+
+impl<const N: usize> private::Sealed for [u8; N] {}
+
+impl<const N: usize> Field for [u8; N] {
+    fn to_raw<C: Codec>(&self) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(self))
+    }
+
+    fn from_raw<C: Codec>(bytes: &[u8]) -> Option<Self> {
+        match bytes.try_into() {
+            Ok(array) => Some(array),
+            Err(_) => C::decode(bytes)?.try_into().ok(),
+        }
+    }
+}
+
+pub(crate) fn serialize<C, T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    C: Codec,
+    T: Field,
+    S: ser::Serializer,
+{
+    let raw = value
+        .to_raw::<C>()
+        .ok_or_else(|| ser::Error::custom(format_args!("invalid {} string", C::NAME)))?;
+    serializer.serialize_bytes(&raw)
+}
+
+pub(crate) fn deserialize<'de, C, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    C: Codec,
+    T: Field,
+    D: de::Deserializer<'de>,
+{
+    deserializer.deserialize_bytes(FieldVisitor::<C, T>(PhantomData))
+}
+
+struct FieldVisitor<C, T>(PhantomData<(C, T)>);
+
+impl<'de, C: Codec, T: Field> de::Visitor<'de> for FieldVisitor<C, T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "a raw or {} encoded byte string", C::NAME)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<T, E> {
+        T::from_raw::<C>(v).ok_or_else(|| E::invalid_value(de::Unexpected::Bytes(v), &self))
+    }
+}
@@ -0,0 +1,107 @@
+//! Encode a [`SystemTime`] as an integer number of seconds since the Unix epoch, as in the
+//! `creation date` key of a metainfo file.
+//!
+//! Times before the epoch are negative. Fractions of a second are rounded down when serializing.
+//! Use the [`option`] module for optional fields.
+//!
+//! # Examples
+//! ```
+//! use serde_derive::{Deserialize, Serialize};
+//! use std::time::{Duration, SystemTime, UNIX_EPOCH};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Metainfo {
+//!     #[serde(rename = "creation date", with = "serde_bencode::helpers::unix_seconds")]
+//!     creation_date: SystemTime,
+//! }
+//!
+//! let metainfo: Metainfo = serde_bencode::from_str("d13:creation datei1700000000ee").unwrap();
+//! assert_eq!(metainfo.creation_date, UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+//! ```
+
+use core::convert::TryFrom;
+use serde::{de, ser, Deserialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Serialize a time as whole seconds since the Unix epoch.
+///
+/// # Errors
+///
+/// Fails if the number of seconds does not fit in an `i64`.
+pub fn serialize<S: ser::Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => i64::try_from(after.as_secs()).ok(),
+        Err(before) => {
+            let before = before.duration();
+            let secs = before.as_secs() + u64::from(before.subsec_nanos() > 0);
+            0i64.checked_sub_unsigned(secs)
+        }
+    };
+    let secs = secs.ok_or_else(|| ser::Error::custom("time is out of range for Unix seconds"))?;
+    serializer.serialize_i64(secs)
+}
+
+/// Deserialize an integer number of seconds since the Unix epoch.
+///
+/// # Errors
+///
+/// Fails if the input is not an integer, or is out of the range supported by [`SystemTime`] on
+/// this platform.
+pub fn deserialize<'de, D: de::Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+    let secs = i64::deserialize(deserializer)?;
+    let offset = Duration::from_secs(secs.unsigned_abs());
+    let time = if secs >= 0 {
+        UNIX_EPOCH.checked_add(offset)
+    } else {
+        UNIX_EPOCH.checked_sub(offset)
+    };
+    time.ok_or_else(|| {
+        de::Error::invalid_value(de::Unexpected::Signed(secs), &"a representable Unix time")
+    })
+}
+
+/// Encode an optional [`SystemTime`] as Unix seconds. `None` is handled like any other `Option`.
+pub mod option {
+    use serde::{de, ser, Deserialize, Serialize};
+    use std::time::SystemTime;
+
+    /// Wraps a time so that it is serialized in Unix seconds.
+    struct Seconds<'a>(&'a SystemTime);
+
+    impl Serialize for Seconds<'_> {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::serialize(self.0, serializer)
+        }
+    }
+
+    struct Time(SystemTime);
+
+    impl<'de> Deserialize<'de> for Time {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Time, D::Error> {
+            super::deserialize(deserializer).map(Time)
+        }
+    }
+
+    /// Serialize an optional time as whole seconds since the Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the number of seconds does not fit in an `i64`.
+    pub fn serialize<S: ser::Serializer>(
+        time: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        time.as_ref().map(Seconds).serialize(serializer)
+    }
+
+    /// Deserialize an optional integer number of seconds since the Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`super::deserialize`].
+    pub fn deserialize<'de, D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        Option::<Time>::deserialize(deserializer).map(|time| time.map(|Time(time)| time))
+    }
+}
@@ -0,0 +1,435 @@
+//! Typed messages of the KRPC protocol spoken by the BitTorrent DHT, as described in [BEP 5].
+//!
+//! Every [`Message`] carries a transaction ID, chosen by the querying node and echoed in the
+//! reply, and is either a [`Query`], a [`Response`] or an [`Error`]. Node IDs and info-hashes are
+//! 20-byte arrays; the `nodes` and `values` of a response are decoded from their compact
+//! encodings into [`NodeInfo`] and [`SocketAddrV4`]. Keys that this module does not know about,
+//! such as those of extensions, are ignored when deserializing.
+//!
+//! [BEP 5]: https://www.bittorrent.org/beps/bep_0005.html
+//!
+//! # Examples
+//! ```
+//! use serde_bencode::krpc::{Message, MessageKind, Query};
+//!
+//! let ping = Message::query(b"aa".to_vec(), Query::Ping { id: *b"abcdefghij0123456789" });
+//! let bytes = serde_bencode::to_bytes(&ping)?;
+//! assert_eq!(bytes, b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe");
+//!
+//! let decoded: Message = serde_bencode::from_bytes(&bytes)?;
+//! assert!(matches!(decoded.kind, MessageKind::Query(Query::Ping { .. })));
+//! assert_eq!(decoded, ping);
+//! # Ok::<(), serde_bencode::Error>(())
+//! ```
+
+use crate::helpers::compact_peers::{private, CompactPeer};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use serde::ser::SerializeMap;
+use serde::{de, ser};
+use serde_bytes::{ByteBuf, Bytes};
+use serde_derive::{Deserialize, Serialize};
+use std::net::SocketAddrV4;
+
+/// A KRPC message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// The transaction ID (`t`), usually a couple of arbitrary bytes.
+    pub transaction_id: Vec<u8>,
+    /// The client version (`v`), if the sender included one.
+    pub version: Option<Vec<u8>>,
+    /// What the message is.
+    pub kind: MessageKind,
+}
+
+/// The three kinds of KRPC message, as named by the `y` key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageKind {
+    /// A query (`y` is `q`).
+    Query(Query),
+    /// A successful reply to a query (`y` is `r`).
+    Response(Response),
+    /// A failed reply to a query (`y` is `e`).
+    Error(Error),
+}
+
+/// The queries of the DHT, with their arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    /// Check that a node is alive.
+    Ping {
+        /// The ID of the querying node.
+        id: [u8; 20],
+    },
+    /// Ask for the contact information of the nodes closest to `target`.
+    FindNode {
+        /// The ID of the querying node.
+        id: [u8; 20],
+        /// The node ID being looked up.
+        target: [u8; 20],
+    },
+    /// Ask for the peers of a torrent.
+    GetPeers {
+        /// The ID of the querying node.
+        id: [u8; 20],
+        /// The info-hash of the torrent.
+        info_hash: [u8; 20],
+    },
+    /// Announce that the querying node is downloading a torrent.
+    AnnouncePeer {
+        /// The ID of the querying node.
+        id: [u8; 20],
+        /// The info-hash of the torrent.
+        info_hash: [u8; 20],
+        /// The port the peer listens on.
+        port: u16,
+        /// Use the source port of the query instead of `port`.
+        implied_port: bool,
+        /// The token received in the response to an earlier `get_peers` query.
+        token: Vec<u8>,
+    },
+}
+
+/// The reply to a query.
+///
+/// Responses do not name the query they answer, so all the keys that any of the queries above
+/// may return are present, and those that were absent are empty. A response to `ping` only has
+/// an `id`; one to `find_node` has `nodes`; one to `get_peers` has a `token` and either `values`
+/// or `nodes`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    /// The ID of the responding node.
+    pub id: [u8; 20],
+    /// The nodes closest to the target, in the compact node info format.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "crate::helpers::compact_peers"
+    )]
+    pub nodes: Vec<NodeInfo>,
+    /// The token to include in a later `announce_peer` query.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "serde_bytes")]
+    pub token: Option<Vec<u8>>,
+    /// The peers of the torrent, each in the compact peer format.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "values")]
+    pub values: Vec<SocketAddrV4>,
+}
+
+/// The contact information of a node: its ID and address, 26 bytes in the compact format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeInfo {
+    /// The ID of the node.
+    pub id: [u8; 20],
+    /// The address the node listens on.
+    pub addr: SocketAddrV4,
+}
+
+/// The reply to a query that failed, encoded as a list of the code and the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The error code, such as [`Error::METHOD_UNKNOWN`].
+    pub code: i64,
+    /// A description of the error.
+    pub message: String,
+}
+
+impl Message {
+    /// Create a query without a version.
+    pub fn query(transaction_id: Vec<u8>, query: Query) -> Message {
+        Message {
+            transaction_id,
+            version: None,
+            kind: MessageKind::Query(query),
+        }
+    }
+
+    /// Create a response without a version.
+    pub fn response(transaction_id: Vec<u8>, response: Response) -> Message {
+        Message {
+            transaction_id,
+            version: None,
+            kind: MessageKind::Response(response),
+        }
+    }
+
+    /// Create an error without a version.
+    pub fn error(transaction_id: Vec<u8>, error: Error) -> Message {
+        Message {
+            transaction_id,
+            version: None,
+            kind: MessageKind::Error(error),
+        }
+    }
+}
+
+impl Query {
+    /// The name of the query, as in the `q` key.
+    pub fn method_name(&self) -> &'static str {
+        match self {
+            Query::Ping { .. } => "ping",
+            Query::FindNode { .. } => "find_node",
+            Query::GetPeers { .. } => "get_peers",
+            Query::AnnouncePeer { .. } => "announce_peer",
+        }
+    }
+
+    /// The ID of the querying node.
+    pub fn id(&self) -> &[u8; 20] {
+        match self {
+            Query::Ping { id }
+            | Query::FindNode { id, .. }
+            | Query::GetPeers { id, .. }
+            | Query::AnnouncePeer { id, .. } => id,
+        }
+    }
+}
+
+impl Response {
+    /// Create a response with only the ID of the responding node, as to a `ping`.
+    pub fn new(id: [u8; 20]) -> Response {
+        Response {
+            id,
+            nodes: Vec::new(),
+            token: None,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl NodeInfo {
+    /// The length of one encoded node.
+    pub const LEN: usize = 26;
+}
+
+impl private::Sealed for NodeInfo {}
+
+impl CompactPeer for NodeInfo {
+    const LEN: usize = NodeInfo::LEN;
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.id);
+        self.addr.write(buf);
+    }
+
+    fn read(bytes: &[u8]) -> NodeInfo {
+        NodeInfo {
+            id: bytes[..20].try_into().unwrap(),
+            addr: SocketAddrV4::read(&bytes[20..]),
+        }
+    }
+}
+
+impl Error {
+    /// A generic error.
+    pub const GENERIC: i64 = 201;
+    /// An error of the responding node.
+    pub const SERVER: i64 = 202;
+    /// A malformed packet, invalid arguments or a bad token.
+    pub const PROTOCOL: i64 = 203;
+    /// The query is not known to the responding node.
+    pub const METHOD_UNKNOWN: i64 = 204;
+
+    /// Create an error with the given code and message.
+    pub fn new(code: i64, message: impl Into<String>) -> Error {
+        Error {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl ser::Serialize for Error {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.code, &self.message).serialize(serializer)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Error {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Error, D::Error> {
+        let (code, message) = <(i64, String)>::deserialize(deserializer)?;
+        Ok(Error { code, message })
+    }
+}
+
+/// The arguments of every query, merged into one dictionary (`a`). The fields are in sorted
+/// order, like those of [`Response`].
+#[derive(Serialize, Deserialize)]
+struct Arguments {
+    id: [u8; 20],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    implied_port: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    info_hash: Option<[u8; 20]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<[u8; 20]>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "serde_bytes")]
+    token: Option<Vec<u8>>,
+}
+
+impl Arguments {
+    fn new(query: &Query) -> Arguments {
+        let mut arguments = Arguments {
+            id: *query.id(),
+            implied_port: None,
+            info_hash: None,
+            port: None,
+            target: None,
+            token: None,
+        };
+        match query {
+            Query::Ping { .. } => {}
+            Query::FindNode { target, .. } => arguments.target = Some(*target),
+            Query::GetPeers { info_hash, .. } => arguments.info_hash = Some(*info_hash),
+            Query::AnnouncePeer {
+                info_hash,
+                port,
+                implied_port,
+                token,
+                ..
+            } => {
+                arguments.info_hash = Some(*info_hash);
+                arguments.port = Some(*port);
+                // Leaving the key out means the same as 0.
+                arguments.implied_port = if *implied_port { Some(1) } else { None };
+                arguments.token = Some(token.clone());
+            }
+        }
+        arguments
+    }
+
+    fn into_query<E: de::Error>(self, method: &str) -> Result<Query, E> {
+        let id = self.id;
+        Ok(match method {
+            "ping" => Query::Ping { id },
+            "find_node" => Query::FindNode {
+                id,
+                target: self.target.ok_or_else(|| E::missing_field("target"))?,
+            },
+            "get_peers" => Query::GetPeers {
+                id,
+                info_hash: self
+                    .info_hash
+                    .ok_or_else(|| E::missing_field("info_hash"))?,
+            },
+            "announce_peer" => Query::AnnouncePeer {
+                id,
+                info_hash: self
+                    .info_hash
+                    .ok_or_else(|| E::missing_field("info_hash"))?,
+                port: self.port.ok_or_else(|| E::missing_field("port"))?,
+                implied_port: self.implied_port.unwrap_or(0) != 0,
+                token: self.token.ok_or_else(|| E::missing_field("token"))?,
+            },
+            _ => return Err(E::unknown_variant(method, METHODS)),
+        })
+    }
+}
+
+const METHODS: &[&str] = &["ping", "find_node", "get_peers", "announce_peer"];
+
+impl ser::Serialize for Message {
+    /// Serialize the message with its keys in sorted order, whatever the options of the
+    /// serializer.
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        let y = match &self.kind {
+            MessageKind::Query(query) => {
+                map.serialize_entry("a", &Arguments::new(query))?;
+                map.serialize_entry("q", query.method_name())?;
+                "q"
+            }
+            MessageKind::Response(response) => {
+                map.serialize_entry("r", response)?;
+                "r"
+            }
+            MessageKind::Error(error) => {
+                map.serialize_entry("e", error)?;
+                "e"
+            }
+        };
+        map.serialize_entry("t", Bytes::new(&self.transaction_id))?;
+        if let Some(version) = &self.version {
+            map.serialize_entry("v", Bytes::new(version))?;
+        }
+        map.serialize_entry("y", y)?;
+        map.end()
+    }
+}
+
+/// The keys of a message of any kind.
+#[derive(Deserialize)]
+struct Envelope {
+    t: ByteBuf,
+    y: String,
+    v: Option<ByteBuf>,
+    q: Option<String>,
+    a: Option<Arguments>,
+    r: Option<Response>,
+    e: Option<Error>,
+}
+
+impl<'de> de::Deserialize<'de> for Message {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Message, D::Error> {
+        let envelope = Envelope::deserialize(deserializer)?;
+        let kind = match envelope.y.as_str() {
+            "q" => {
+                let method = envelope.q.ok_or_else(|| de::Error::missing_field("q"))?;
+                let arguments = envelope.a.ok_or_else(|| de::Error::missing_field("a"))?;
+                MessageKind::Query(arguments.into_query(&method)?)
+            }
+            "r" => MessageKind::Response(envelope.r.ok_or_else(|| de::Error::missing_field("r"))?),
+            "e" => MessageKind::Error(envelope.e.ok_or_else(|| de::Error::missing_field("e"))?),
+            y => return Err(de::Error::unknown_variant(y, &["q", "r", "e"])),
+        };
+        Ok(Message {
+            transaction_id: envelope.t.into_vec(),
+            version: envelope.v.map(ByteBuf::into_vec),
+            kind,
+        })
+    }
+}
+
+/// The `values` of a response: a list with one compact peer string per peer.
+mod values {
+    use crate::helpers::compact_peers::CompactPeer;
+    use serde::{de, ser, Deserialize};
+    use serde_bytes::{ByteBuf, Bytes};
+    use std::net::SocketAddrV4;
+    use std::vec::Vec;
+
+    pub fn serialize<S: ser::Serializer>(
+        peers: &[SocketAddrV4],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let encoded: Vec<Vec<u8>> = peers
+            .iter()
+            .map(|peer| {
+                let mut buf = Vec::with_capacity(SocketAddrV4::LEN);
+                peer.write(&mut buf);
+                buf
+            })
+            .collect();
+        serializer.collect_seq(encoded.iter().map(|peer| Bytes::new(peer)))
+    }
+
+    pub fn deserialize<'de, D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<SocketAddrV4>, D::Error> {
+        Vec::<ByteBuf>::deserialize(deserializer)?
+            .iter()
+            .map(|peer| {
+                if peer.len() == SocketAddrV4::LEN {
+                    Ok(SocketAddrV4::read(peer))
+                } else {
+                    Err(de::Error::invalid_length(
+                        peer.len(),
+                        &"a 6-byte compact peer",
+                    ))
+                }
+            })
+            .collect()
+    }
+}
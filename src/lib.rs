@@ -35,12 +35,72 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! # Features
+//!
+//! * `std` (enabled by default): decoding from [`std::io::Read`] through
+//!   [`Deserializer::new`], and encoding many values to a [`std::io::Write`] with
+//!   `ser::Encoder`. Without it the crate is `no_std` and only needs `alloc`; decode from byte
+//!   slices with [`from_bytes`] or [`Deserializer::from_slice`]. [`Error`] implements the
+//!   standard error trait either way.
+//! * `arbitrary_precision`: keep integers that do not fit in 128 bits as
+//!   [`value::BigInt`](value) instead of failing.
+//! * `preserve_order`: keep the entries of [`Value::Dict`](value::Value::Dict) in input order
+//!   instead of sorting them by key.
+//! * `tokio`: decode from and encode to tokio's asynchronous readers and writers with
+//!   `de::AsyncBencodeDecoder`, `de::from_async_reader` and `ser::to_async_writer`.
+//! * `json`: convert [`Value`](value::Value) to and from `serde_json::Value`.
+//! * `torrent`: typed structures for metainfo files in the `torrent` module, including the
+//!   v1 and v2 info-hashes.
+//! * `krpc`: typed messages of the DHT's KRPC protocol in the `krpc` module.
+//! * `bytes`: decode byte strings into `bytes::Bytes` that share the memory of the input, with
+//!   the `bytes` module and `de::BytesRead`, and encode into any `bytes::BufMut`.
+//! * `bumpalo`: decode into `arena::Value` trees whose nodes are allocated from a
+//!   `bumpalo::Bump` arena.
+//! * `smallvec`: store byte strings and dictionary keys of up to 16 bytes inside
+//!   [`Value`](value::Value) instead of allocating them, see [`value::ByteString`].
+//! * `memmap2`: decode files through a memory map instead of reading them into a buffer, with
+//!   the `mmap` module.
+//! * `cli`: the `bencode` binary, which converts files between bencode and JSON and validates
+//!   them. Install it with `cargo install serde_bencode --features cli`.
+
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+#[cfg(feature = "bytes")]
+pub mod bytes;
 pub mod de;
 pub mod error;
+pub mod helpers;
+#[cfg(feature = "krpc")]
+pub mod krpc;
+#[cfg(feature = "memmap2")]
+pub mod mmap;
+pub mod pretty;
 pub mod ser;
+pub mod token;
+#[cfg(feature = "torrent")]
+pub mod torrent;
 pub mod value;
 
-pub use de::{from_bytes, from_str, Deserializer};
+pub use de::{canonicalize, from_bytes, from_bytes_partial, from_str, validate, Deserializer};
 pub use error::{Error, Result};
 pub use ser::{to_bytes, to_string, Serializer};
+
+// Used by the `bencode!` macro; not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec;
+
+    pub fn key<K: AsRef<[u8]>>(key: K) -> crate::value::ByteString {
+        key.as_ref().into()
+    }
+}
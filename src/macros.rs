@@ -0,0 +1,112 @@
+/// Construct a [`Value`](crate::value::Value) from a bencode-like literal.
+///
+/// Dictionaries are written as `{ key: value }` and lists as `[value, ...]`. Keys are string or
+/// byte string literals, or any parenthesized expression implementing `AsRef<[u8]>`. Any other
+/// value is an expression that converts into a `Value`, such as an integer, a string or byte
+/// string, or another `Value`.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::bencode;
+///
+/// let url = "http://tracker.example/announce";
+/// let info_key = "info";
+/// let torrent = bencode!({
+///     "announce": url,
+///     (info_key): {
+///         "name": "example.iso",
+///         "piece length": 262144,
+///         "pieces": b"\x01\x02\x03",
+///     },
+///     "url-list": ["http://a.example/", "http://b.example/"],
+/// });
+/// assert_eq!(
+///     serde_bencode::to_bytes(&torrent).unwrap(),
+///     &b"d8:announce31:http://tracker.example/announce4:infod4:name11:example.iso\
+///        12:piece lengthi262144e6:pieces3:\x01\x02\x03e8:url-listl17:http://a.example/\
+///        17:http://b.example/ee"[..],
+/// );
+/// ```
+#[macro_export]
+macro_rules! bencode {
+    ({ $($tt:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut map = $crate::value::Map::new();
+        $crate::__bencode_dict!(map $($tt)*);
+        $crate::value::Value::Dict(map)
+    }};
+    ([ $($tt:tt)* ]) => {
+        $crate::value::Value::List($crate::__bencode_list!([] $($tt)*))
+    };
+    ($other:expr) => {
+        $crate::value::Value::from($other)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __bencode_list {
+    ([$($elems:expr,)*]) => {
+        $crate::__private::vec![$($elems,)*]
+    };
+    ([$($elems:expr,)*] [$($inner:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::__bencode_list!([$($elems,)* $crate::bencode!([$($inner)*]),] $($($rest)*)?)
+    };
+    ([$($elems:expr,)*] {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::__bencode_list!([$($elems,)* $crate::bencode!({$($inner)*}),] $($($rest)*)?)
+    };
+    ([$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::__bencode_list!([$($elems,)* $crate::bencode!($next),] $($rest)*)
+    };
+    ([$($elems:expr,)*] $last:expr) => {
+        $crate::__bencode_list!([$($elems,)* $crate::bencode!($last),])
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __bencode_dict {
+    ($map:ident) => {};
+    ($map:ident $key:tt : [$($inner:tt)*] $(, $($rest:tt)*)?) => {
+        $map.insert($crate::__private::key($key), $crate::bencode!([$($inner)*]));
+        $crate::__bencode_dict!($map $($($rest)*)?);
+    };
+    ($map:ident $key:tt : {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $map.insert($crate::__private::key($key), $crate::bencode!({$($inner)*}));
+        $crate::__bencode_dict!($map $($($rest)*)?);
+    };
+    ($map:ident $key:tt : $value:expr, $($rest:tt)*) => {
+        $map.insert($crate::__private::key($key), $crate::bencode!($value));
+        $crate::__bencode_dict!($map $($rest)*);
+    };
+    ($map:ident $key:tt : $value:expr) => {
+        $map.insert($crate::__private::key($key), $crate::bencode!($value));
+    };
+}
+
+/// Implement the integer and `char` methods of a deserializer by parsing dictionary keys as
+/// decimal text, through an inherent `deserialize_parsed` method.
+macro_rules! deserialize_parsed_key {
+    () => {
+        deserialize_parsed_key! {
+            deserialize_i8 => visit_i8,
+            deserialize_i16 => visit_i16,
+            deserialize_i32 => visit_i32,
+            deserialize_i64 => visit_i64,
+            deserialize_i128 => visit_i128,
+            deserialize_u8 => visit_u8,
+            deserialize_u16 => visit_u16,
+            deserialize_u32 => visit_u32,
+            deserialize_u64 => visit_u64,
+            deserialize_u128 => visit_u128,
+            deserialize_char => visit_char,
+        }
+    };
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: serde::de::Visitor<'de>>(self, visitor: V) -> $crate::Result<V::Value> {
+                self.deserialize_parsed(visitor, V::$visit::<$crate::Error>)
+            }
+        )*
+    };
+}
@@ -0,0 +1,159 @@
+//! Decoding files through a memory map. Available with the `memmap2` feature.
+//!
+//! [`from_file`] maps a file into memory and decodes straight from the mapping, so that indexing
+//! a directory of metainfo files does not read each of them into a buffer first. A
+//! [`MappedFile`] owns the mapping and keeps it alive, for decoding without copying at all:
+//! [`MappedFile::lazy`] returns a [`LazyValue`] whose byte strings are slices of the file.
+//!
+//! ```
+//! # fn main() -> Result<(), serde_bencode::Error> {
+//! use serde_bencode::mmap::{self, MappedFile};
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Torrent {
+//!     announce: String,
+//! }
+//!
+//! let path = std::env::temp_dir().join("serde_bencode-mmap-doc.torrent");
+//! std::fs::write(&path, b"d8:announce3:url4:infod4:name4:spamee")?;
+//!
+//! // SAFETY: Nothing modifies the file while it is mapped.
+//! let torrent: Torrent = unsafe { mmap::from_file(&path)? };
+//! assert_eq!(torrent.announce, "url");
+//!
+//! // SAFETY: As above.
+//! let file = unsafe { MappedFile::open(&path)? };
+//! let lazy = file.lazy()?;
+//! let info = lazy.as_dict().and_then(|torrent| torrent.get(b"info")).unwrap();
+//! let name = info.as_dict().and_then(|info| info.get(b"name")).unwrap();
+//! assert_eq!(name.as_str(), Some("spam"));
+//! # std::fs::remove_file(&path)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Modifying mapped files
+//!
+//! A mapping shows the current contents of the file, not a copy. If the file is truncated or
+//! written to while it is mapped, by this or any other process, decoding may read inconsistent
+//! data or crash. This cannot be checked, so the functions that map a file are `unsafe` and leave
+//! it to the caller to ensure it does not happen.
+
+use crate::de::Options;
+use crate::error::Result;
+use crate::value::LazyValue;
+use memmap2::Mmap;
+use serde::de;
+use std::fs::File;
+use std::path::Path;
+
+/// A file mapped into memory, which byte strings can be borrowed from for as long as it lives.
+#[derive(Debug)]
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    /// Map the file at `path` into memory.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be opened or mapped.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be truncated or modified, by this or any other process, for as long as
+    /// the returned `MappedFile` lives. See [Modifying mapped files](self#modifying-mapped-files).
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<MappedFile> {
+        let file = File::open(path)?;
+        // SAFETY: The mapping is read-only, and the caller guarantees that the file is not
+        // modified while it is mapped.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(MappedFile { map })
+    }
+
+    /// The contents of the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Deserialize the contents of the file as `T`, like [`from_bytes`](crate::from_bytes).
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`from_bytes`](crate::from_bytes).
+    pub fn decode<'a, T: de::Deserialize<'a>>(&'a self) -> Result<T> {
+        crate::from_bytes(&self.map)
+    }
+
+    /// Deserialize the contents of the file as `T` like [`decode`](MappedFile::decode), using
+    /// the given options.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as
+    /// [`from_bytes_with_options`](crate::de::from_bytes_with_options).
+    pub fn decode_with_options<'a, T: de::Deserialize<'a>>(
+        &'a self,
+        options: Options,
+    ) -> Result<T> {
+        crate::de::from_bytes_with_options(&self.map, options)
+    }
+
+    /// Check the contents of the file and split its outermost container, borrowing all byte
+    /// strings from the mapping. See [`LazyValue::from_slice`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`LazyValue::from_slice`].
+    pub fn lazy(&self) -> Result<LazyValue<'_>> {
+        LazyValue::from_slice(&self.map)
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Map the file at `path` into memory and deserialize its contents as `T`.
+///
+/// The mapping is dropped before this returns, so `T` cannot borrow from it; use
+/// [`MappedFile`] for that.
+///
+/// # Errors
+///
+/// Fails if the file cannot be opened or mapped, and for the same reasons as
+/// [`from_bytes`](crate::from_bytes).
+///
+/// # Safety
+///
+/// The file must not be truncated or modified, by this or any other process, until this returns.
+/// See [Modifying mapped files](self#modifying-mapped-files).
+pub unsafe fn from_file<T: de::DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    // SAFETY: The mapping is dropped before this returns, and the caller guarantees that the
+    // file is not modified until then.
+    unsafe { MappedFile::open(path)? }.decode()
+}
+
+/// Map the file at `path` into memory and deserialize its contents as `T` like [`from_file`],
+/// using the given options.
+///
+/// # Errors
+///
+/// Fails if the file cannot be opened or mapped, and for the same reasons as
+/// [`from_bytes_with_options`](crate::de::from_bytes_with_options).
+///
+/// # Safety
+///
+/// The file must not be truncated or modified, by this or any other process, until this returns.
+/// See [Modifying mapped files](self#modifying-mapped-files).
+pub unsafe fn from_file_with_options<T: de::DeserializeOwned, P: AsRef<Path>>(
+    path: P,
+    options: Options,
+) -> Result<T> {
+    // SAFETY: As in `from_file`.
+    unsafe { MappedFile::open(path)? }.decode_with_options(options)
+}
@@ -0,0 +1,134 @@
+//! Rendering bencode as indented, human-readable text for debugging.
+//!
+//! The output looks like JSON: lists are written as `[...]` and dictionaries as `{...}` with one
+//! entry per line. Byte strings that are valid UTF-8 are quoted and escaped like Rust string
+//! literals. Other byte strings are shown as their length followed by their hex encoding, which is
+//! cut short after [`MAX_HEX_BYTES`] bytes.
+//!
+//! ```
+//! let response = b"d8:completei5e5:peers6:\x7f\x00\x00\x01\x1a\xe1e";
+//! assert_eq!(
+//!     serde_bencode::pretty::to_string(response).unwrap(),
+//!     "{\n  \"complete\": 5,\n  \"peers\": <6 bytes 7f0000011ae1>\n}",
+//! );
+//! ```
+//!
+//! The format is meant for people and is not guaranteed to stay the same between releases.
+
+use crate::de::{parse_events, EventHandler, Position, SliceRead};
+use crate::error::Result;
+use crate::helpers::hex::Hex;
+use crate::helpers::text::Codec;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::ops::ControlFlow;
+use core::str;
+
+/// How many bytes of a byte string that is not valid UTF-8 are shown.
+pub const MAX_HEX_BYTES: usize = 32;
+
+/// Render one bencode value as indented text.
+///
+/// # Errors
+///
+/// Fails if the input is not a single well-formed bencode value.
+pub fn to_string(b: &[u8]) -> Result<String> {
+    let mut printer = Printer::default();
+    // The printer never stops early.
+    let _ = parse_events(SliceRead::new(b), &mut printer)?;
+    Ok(printer.out)
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    /// For each open container, whether it is a dictionary and how many keys and values have been
+    /// written to it.
+    stack: Vec<(bool, usize)>,
+}
+
+impl Printer {
+    /// Write the separator and indentation that go before the next key, value or list item.
+    fn begin_item(&mut self) {
+        let depth = self.stack.len();
+        if let Some((dict, items)) = self.stack.last_mut() {
+            *items += 1;
+            if *dict && items.is_multiple_of(2) {
+                self.out.push_str(": ");
+                return;
+            }
+            if *items > 1 {
+                self.out.push(',');
+            }
+            self.newline(depth);
+        }
+    }
+
+    fn newline(&mut self, depth: usize) {
+        self.out.push('\n');
+        for _ in 0..depth {
+            self.out.push_str("  ");
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        match str::from_utf8(bytes) {
+            Ok(s) => {
+                let _ = write!(self.out, "\"{}\"", s.escape_debug());
+            }
+            Err(_) => {
+                let shown = &bytes[..bytes.len().min(MAX_HEX_BYTES)];
+                let _ = write!(self.out, "<{} bytes {}", bytes.len(), Hex::encode(shown));
+                if shown.len() < bytes.len() {
+                    self.out.push_str("...");
+                }
+                self.out.push('>');
+            }
+        }
+    }
+}
+
+impl EventHandler for Printer {
+    fn on_int(&mut self, value: i64, _pos: Position) -> ControlFlow<()> {
+        self.begin_item();
+        let _ = write!(self.out, "{}", value);
+        ControlFlow::Continue(())
+    }
+
+    fn on_big_int(&mut self, digits: &str, _pos: Position) -> ControlFlow<()> {
+        self.begin_item();
+        self.out.push_str(digits);
+        ControlFlow::Continue(())
+    }
+
+    fn on_bytes(&mut self, value: &[u8], _pos: Position) -> ControlFlow<()> {
+        self.begin_item();
+        self.write_bytes(value);
+        ControlFlow::Continue(())
+    }
+
+    fn begin_list(&mut self, _pos: Position) -> ControlFlow<()> {
+        self.begin_item();
+        self.out.push('[');
+        self.stack.push((false, 0));
+        ControlFlow::Continue(())
+    }
+
+    fn begin_dict(&mut self, _pos: Position) -> ControlFlow<()> {
+        self.begin_item();
+        self.out.push('{');
+        self.stack.push((true, 0));
+        ControlFlow::Continue(())
+    }
+
+    fn end(&mut self, _pos: Position) -> ControlFlow<()> {
+        if let Some((dict, items)) = self.stack.pop() {
+            if items > 0 {
+                self.newline(self.stack.len());
+            }
+            self.out.push(if dict { '}' } else { ']' });
+        }
+        ControlFlow::Continue(())
+    }
+}
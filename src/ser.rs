@@ -1,16 +1,48 @@
 //! Serialize a Rust data structure into bencode data.
 
+mod byte;
+#[cfg(feature = "std")]
+mod encoder;
+mod options;
+mod output;
 mod string;
+mod tuple;
+
+#[cfg(feature = "std")]
+pub use self::encoder::Encoder;
+pub use self::options::{BoolPolicy, FloatPolicy, KeyPolicy, NonePolicy, Options, UnitPolicy};
+pub use self::output::Output;
+
+use self::output::Counter;
+use self::tuple::SerializeTuple;
 
 use crate::error::{Error, Result};
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Range;
+use core::str;
 use serde::ser;
-use std::mem;
-use std::str;
 
 /// A structure for serializing Rust values into bencode.
+///
+/// Tuples and fixed-size arrays whose elements are all `u8` are written as byte strings, so that
+/// types like `[u8; 20]` come out the way bencode protocols expect them. Empty tuples, and tuples
+/// with an element of any other type, are written as lists. [`Options::byte_tuples`] turns this
+/// off.
 #[derive(Default, Debug)]
-pub struct Serializer {
-    buf: Vec<u8>,
+pub struct Serializer<W = Vec<u8>> {
+    buf: W,
+    options: Options,
+    /// The rest of the path to the value selected by [`to_bytes_with_digest`], if this
+    /// serializer is on it.
+    path: Option<Vec<Vec<u8>>>,
+    /// Where the selected value ended up in `buf`.
+    selected: Option<Range<usize>>,
+    /// The number of lists and dictionaries the value being serialized is nested in.
+    depth: usize,
 }
 
 impl Serializer {
@@ -19,13 +51,57 @@ impl Serializer {
         Self::default()
     }
 
+    /// Create a new serializer using the given options.
+    pub fn with_options(options: Options) -> Serializer {
+        Serializer::with_output(options)
+    }
+
     /// Consume the serializer and return the contents as a byte vector.
     pub fn into_vec(self) -> Vec<u8> {
         self.buf
     }
+}
+
+impl<W: Output> Serializer<W> {
+    fn with_output(options: Options) -> Serializer<W> {
+        Serializer {
+            buf: W::default(),
+            options,
+            path: None,
+            selected: None,
+            depth: 0,
+        }
+    }
 
     fn push<T: AsRef<[u8]>>(&mut self, token: T) {
-        self.buf.extend_from_slice(token.as_ref());
+        self.buf.push(token.as_ref());
+    }
+
+    /// Enter a list or dictionary, failing if that exceeds the configured depth.
+    fn enter(&mut self) -> Result<()> {
+        if let Some(max) = self.options.max_depth {
+            if self.depth >= max {
+                return Err(Error::LimitExceeded(format!(
+                    "Nesting is deeper than the maximum of {}",
+                    max
+                )));
+            }
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Start a list or dictionary with `token`.
+    fn open(&mut self, token: &str) -> Result<()> {
+        self.enter()?;
+        self.push(token);
+        Ok(())
+    }
+
+    /// End the innermost list or dictionary.
+    fn close(&mut self) {
+        self.depth -= 1;
+        self.push("e");
     }
 }
 
@@ -35,30 +111,19 @@ impl AsRef<[u8]> for Serializer {
     }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl<W: Output> ser::SerializeSeq for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
         value.serialize(&mut **self)
     }
     fn end(self) -> Result<()> {
-        self.push("e");
+        self.close();
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-    fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
-        value.serialize(&mut **self)
-    }
-    fn end(self) -> Result<()> {
-        ser::SerializeSeq::end(self)
-    }
-}
-
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<W: Output> ser::SerializeTupleStruct for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
@@ -69,33 +134,67 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<W: Output> ser::SerializeTupleVariant for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
         value.serialize(&mut **self)
     }
     fn end(self) -> Result<()> {
-        self.push("ee");
+        self.close();
+        self.close();
         Ok(())
     }
 }
 
 #[doc(hidden)]
 // TODO: This should be pub(crate).
-pub struct SerializeMap<'a> {
-    ser: &'a mut Serializer,
-    entries: Vec<(Vec<u8>, Vec<u8>)>,
+pub struct SerializeMap<'a, W = Vec<u8>> {
+    ser: &'a mut Serializer<W>,
+    entries: Vec<(Vec<u8>, W)>,
     cur_key: Option<Vec<u8>>,
+    selected: Option<(Vec<u8>, Range<usize>)>,
 }
 
-impl<'a> SerializeMap<'a> {
-    pub fn new(ser: &'a mut Serializer, len: usize) -> SerializeMap {
-        SerializeMap {
+impl<'a, W: Output> SerializeMap<'a, W> {
+    pub fn new(ser: &'a mut Serializer<W>, len: usize) -> Result<SerializeMap<'a, W>> {
+        ser.enter()?;
+        // Only a dictionary at the start of the output is the one the digest path refers to; any
+        // other dictionary is nested inside a list or enum variant.
+        if !ser.buf.is_empty() {
+            ser.path = None;
+        }
+        Ok(SerializeMap {
             ser,
             entries: Vec::with_capacity(len),
             cur_key: None,
+            selected: None,
+        })
+    }
+
+    fn push_entry<T: ?Sized + ser::Serialize>(&mut self, key: Vec<u8>, value: &T) -> Result<()> {
+        let path = match &self.ser.path {
+            Some(path) if path.first() == Some(&key) => Some(path[1..].to_vec()),
+            _ => None,
+        };
+        let whole = matches!(&path, Some(path) if path.is_empty());
+        let mut ser = Serializer::<W>::with_output(self.ser.options.clone());
+        ser.path = path.filter(|path| !path.is_empty());
+        ser.depth = self.ser.depth;
+        value.serialize(&mut ser)?;
+        if ser.buf.is_empty() {
+            return Ok(());
+        }
+        let selected = if whole {
+            Some(0..ser.buf.len())
+        } else {
+            ser.selected
+        };
+        if let (Some(range), None) = (selected, &self.selected) {
+            self.selected = Some((key.clone(), range));
         }
+        self.entries.push((key, ser.buf));
+        Ok(())
     }
 
     fn end_map(&mut self) -> Result<()> {
@@ -105,18 +204,34 @@ impl<'a> SerializeMap<'a> {
             ));
         }
         let mut entries = mem::take(&mut self.entries);
-        entries.sort_by(|&(ref a, _), &(ref b, _)| a.cmp(b));
+        if self.ser.options.sort_keys {
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(Error::InvalidValue(format!(
+                    "Duplicate dictionary key `{}`",
+                    String::from_utf8_lossy(&pair[0].0)
+                )));
+            }
+        }
         self.ser.push("d");
+        let selected = self.selected.take();
         for (k, v) in entries {
             ser::Serializer::serialize_bytes(&mut *self.ser, k.as_ref())?;
-            self.ser.push(v);
+            match &selected {
+                Some((key, range)) if *key == k && self.ser.selected.is_none() => {
+                    let offset = self.ser.buf.len();
+                    self.ser.selected = Some(offset + range.start..offset + range.end);
+                }
+                _ => {}
+            }
+            self.ser.buf.append(v);
         }
-        self.ser.push("e");
+        self.ser.close();
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeMap for SerializeMap<'a> {
+impl<'a, W: Output> ser::SerializeMap for SerializeMap<'a, W> {
     type Ok = ();
     type Error = Error;
     fn serialize_key<T: ?Sized + ser::Serialize>(&mut self, key: &T) -> Result<()> {
@@ -126,7 +241,8 @@ impl<'a> ser::SerializeMap for SerializeMap<'a> {
                     .to_string(),
             ));
         }
-        self.cur_key = Some(key.serialize(&mut string::StringSerializer)?);
+        self.cur_key =
+            Some(key.serialize(&mut string::StringSerializer::new(self.ser.options.keys))?);
         Ok(())
     }
     fn serialize_value<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
@@ -135,13 +251,7 @@ impl<'a> ser::SerializeMap for SerializeMap<'a> {
                 "`serialize_value` called without calling `serialize_key`".to_string(),
             )
         })?;
-        let mut ser = Serializer::new();
-        value.serialize(&mut ser)?;
-        let value = ser.into_vec();
-        if !value.is_empty() {
-            self.entries.push((key, value));
-        }
-        Ok(())
+        self.push_entry(key, value)
     }
     fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<()>
     where
@@ -154,21 +264,15 @@ impl<'a> ser::SerializeMap for SerializeMap<'a> {
                     .to_string(),
             ));
         }
-        let key = key.serialize(&mut string::StringSerializer)?;
-        let mut ser = Serializer::new();
-        value.serialize(&mut ser)?;
-        let value = ser.into_vec();
-        if !value.is_empty() {
-            self.entries.push((key, value));
-        }
-        Ok(())
+        let key = key.serialize(&mut string::StringSerializer::new(self.ser.options.keys))?;
+        self.push_entry(key, value)
     }
     fn end(mut self) -> Result<()> {
         self.end_map()
     }
 }
 
-impl<'a> ser::SerializeStruct for SerializeMap<'a> {
+impl<'a, W: Output> ser::SerializeStruct for SerializeMap<'a, W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + ser::Serialize>(
@@ -176,14 +280,14 @@ impl<'a> ser::SerializeStruct for SerializeMap<'a> {
         key: &'static str,
         value: &T,
     ) -> Result<()> {
-        ser::SerializeMap::serialize_entry(self, key, value)
+        self.push_entry(unescape_identifier(key).into_owned(), value)
     }
     fn end(mut self) -> Result<()> {
         self.end_map()
     }
 }
 
-impl<'a> ser::SerializeStructVariant for SerializeMap<'a> {
+impl<'a, W: Output> ser::SerializeStructVariant for SerializeMap<'a, W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + ser::Serialize>(
@@ -191,28 +295,31 @@ impl<'a> ser::SerializeStructVariant for SerializeMap<'a> {
         key: &'static str,
         value: &T,
     ) -> Result<()> {
-        ser::SerializeMap::serialize_entry(self, key, value)
+        self.push_entry(unescape_identifier(key).into_owned(), value)
     }
     fn end(mut self) -> Result<()> {
         self.end_map()?;
-        self.ser.push("e");
+        self.ser.close();
         Ok(())
     }
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl<'a, W: Output> ser::Serializer for &'a mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
-    type SerializeTuple = Self;
+    type SerializeTuple = SerializeTuple<'a, W>;
     type SerializeTupleStruct = Self;
     type SerializeTupleVariant = Self;
-    type SerializeMap = SerializeMap<'a>;
-    type SerializeStruct = SerializeMap<'a>;
-    type SerializeStructVariant = SerializeMap<'a>;
+    type SerializeMap = SerializeMap<'a, W>;
+    type SerializeStruct = SerializeMap<'a, W>;
+    type SerializeStructVariant = SerializeMap<'a, W>;
 
     fn serialize_bool(self, value: bool) -> Result<()> {
-        self.serialize_i64(value as i64)
+        match self.options.bool {
+            BoolPolicy::Integer | BoolPolicy::NonZero => self.serialize_i64(value as i64),
+            BoolPolicy::Error => Err(Error::InvalidValue("Cannot serialize bool".to_string())),
+        }
     }
     fn serialize_i8(self, value: i8) -> Result<()> {
         self.serialize_i64(value as i64)
@@ -229,6 +336,12 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.push("e");
         Ok(())
     }
+    fn serialize_i128(self, value: i128) -> Result<()> {
+        self.push("i");
+        self.push(value.to_string());
+        self.push("e");
+        Ok(())
+    }
     fn serialize_u8(self, value: u8) -> Result<()> {
         self.serialize_u64(value as u64)
     }
@@ -244,11 +357,35 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.push("e");
         Ok(())
     }
-    fn serialize_f32(self, _value: f32) -> Result<()> {
-        Err(Error::InvalidValue("Cannot serialize f32".to_string()))
+    fn serialize_u128(self, value: u128) -> Result<()> {
+        self.push("i");
+        self.push(value.to_string());
+        self.push("e");
+        Ok(())
+    }
+    fn serialize_f32(self, value: f32) -> Result<()> {
+        match self.options.float {
+            FloatPolicy::String if value.is_finite() => self.serialize_str(&value.to_string()),
+            _ => self.serialize_f64(value.into()),
+        }
     }
-    fn serialize_f64(self, _value: f64) -> Result<()> {
-        Err(Error::InvalidValue("Cannot serialize f64".to_string()))
+    fn serialize_f64(self, value: f64) -> Result<()> {
+        match self.options.float {
+            FloatPolicy::Error => Err(Error::InvalidValue("Cannot serialize float".to_string())),
+            // The bounds are exactly representable, so within them the cast does not saturate and
+            // only drops a fractional part.
+            FloatPolicy::Integer
+                if (-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&value)
+                    && value as i64 as f64 == value =>
+            {
+                self.serialize_i64(value as i64)
+            }
+            FloatPolicy::String if value.is_finite() => self.serialize_str(&value.to_string()),
+            _ => Err(Error::InvalidValue(format!(
+                "Cannot serialize float `{}` losslessly",
+                value
+            ))),
+        }
     }
     fn serialize_char(self, value: char) -> Result<()> {
         let mut buffer = [0; 4];
@@ -265,6 +402,15 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         Ok(())
     }
     fn serialize_unit(self) -> Result<()> {
+        match self.options.unit {
+            UnitPolicy::Skip => {}
+            UnitPolicy::EmptyBytes => self.push("0:"),
+            UnitPolicy::EmptyList => self.push("le"),
+            UnitPolicy::EmptyDict => self.push("de"),
+            UnitPolicy::Error => {
+                return Err(Error::InvalidValue("Cannot serialize unit".to_string()))
+            }
+        }
         Ok(())
     }
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
@@ -276,13 +422,28 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
-        self.serialize_str(variant)
+        self.serialize_bytes(&unescape_identifier(variant))
     }
     fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<()> {
+        if name == crate::value::RAW_VALUE_TOKEN {
+            let raw = value.serialize(&mut string::StringSerializer::strict())?;
+            self.push(raw);
+            return Ok(());
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        {
+            if name == crate::value::BIG_INT_TOKEN {
+                let digits = value.serialize(&mut string::StringSerializer::strict())?;
+                self.push("i");
+                self.push(digits);
+                self.push("e");
+                return Ok(());
+            }
+        }
         value.serialize(self)
     }
     fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
@@ -292,24 +453,27 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         value: &T,
     ) -> Result<()> {
-        self.push("d");
-        self.serialize_bytes(variant.as_bytes())?;
+        self.open("d")?;
+        self.serialize_bytes(&unescape_identifier(variant))?;
         value.serialize(&mut *self)?;
-        self.push("e");
+        self.close();
         Ok(())
     }
     fn serialize_none(self) -> Result<()> {
-        Ok(())
+        match self.options.none {
+            NonePolicy::Skip => Ok(()),
+            NonePolicy::Error => Err(Error::InvalidValue("Cannot serialize None".to_string())),
+        }
     }
     fn serialize_some<T: ?Sized + ser::Serialize>(self, value: &T) -> Result<()> {
         value.serialize(self)
     }
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self> {
-        self.push("l");
+        self.open("l")?;
         Ok(self)
     }
-    fn serialize_tuple(self, size: usize) -> Result<Self> {
-        self.serialize_seq(Some(size))
+    fn serialize_tuple(self, size: usize) -> Result<Self::SerializeTuple> {
+        SerializeTuple::new(self, size)
     }
     fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self> {
         self.serialize_seq(Some(len))
@@ -321,13 +485,13 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.push("d");
-        self.serialize_bytes(variant.as_bytes())?;
-        self.push("l");
+        self.open("d")?;
+        self.serialize_bytes(&unescape_identifier(variant))?;
+        self.open("l")?;
         Ok(self)
     }
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
-        Ok(SerializeMap::new(self, len.unwrap_or(0)))
+        SerializeMap::new(self, len.unwrap_or(0))
     }
     fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
         self.serialize_map(Some(len))
@@ -339,14 +503,55 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.push("d");
-        self.serialize_bytes(variant.as_bytes())?;
-        Ok(SerializeMap::new(self, len))
+        self.open("d")?;
+        self.serialize_bytes(&unescape_identifier(variant))?;
+        SerializeMap::new(self, len)
+    }
+}
+
+/// Turn a field or variant name back into the dictionary key it was escaped from, see
+/// [`Deserializer`](crate::Deserializer#binary-keys).
+fn unescape_identifier(name: &str) -> Cow<'_, [u8]> {
+    if !name.contains('\\') {
+        return Cow::Borrowed(name.as_bytes());
+    }
+    let mut key = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let escaped = match tail {
+            [b'x', hi @ (b'8'..=b'9' | b'a'..=b'f'), lo @ (b'0'..=b'9' | b'a'..=b'f'), ..]
+                if b == b'\\' =>
+            {
+                Some(hex_digit(*hi) << 4 | hex_digit(*lo))
+            }
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                key.push(byte);
+                rest = &tail[3..];
+            }
+            None => {
+                key.push(b);
+                rest = tail;
+            }
+        }
+    }
+    Cow::Owned(key)
+}
+
+fn hex_digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        _ => c - b'a' + 10,
     }
 }
 
 /// Serialize the given data into a bencode byte vector.
 ///
+/// Tuples and fixed-size arrays whose elements are all `u8`, such as `[u8; 20]` info-hashes, are
+/// written as byte strings rather than lists; see [`Options::byte_tuples`].
+///
 /// # Examples
 /// ```
 /// # fn main() -> Result<(), serde_bencode::Error> {
@@ -375,13 +580,190 @@ impl<'a> ser::Serializer for &'a mut Serializer {
 /// # Errors
 ///
 /// Serialization can fail if `T`'s implementation of `Serialize` decides to fail or `T` contains
-/// floating point values, which bencode cannot serialize.
+/// floating point values, which bencode cannot serialize unless a [`FloatPolicy`] is configured.
 pub fn to_bytes<T: ser::Serialize>(b: &T) -> Result<Vec<u8>> {
-    let mut ser = Serializer::new();
+    to_bytes_with_options(b, Options::default())
+}
+
+/// Serialize the given data into a bencode byte vector, using the given options.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::ser::{to_bytes_with_options, Options};
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Fake {
+///     b: i64,
+///     a: i64,
+/// }
+///
+/// let fake = Fake { b: 1, a: 2 };
+/// assert_eq!(serde_bencode::to_bytes(&fake)?, b"d1:ai2e1:bi1ee");
+///
+/// let unsorted = Options::new().sort_keys(false);
+/// assert_eq!(to_bytes_with_options(&fake, unsorted)?, b"d1:bi1e1:ai2ee");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// In addition to the errors returned by [`to_bytes`], this fails if the data violates any of
+/// the restrictions enabled in `options`.
+pub fn to_bytes_with_options<T: ser::Serialize>(b: &T, options: Options) -> Result<Vec<u8>> {
+    let mut ser = Serializer::with_options(options);
+    b.serialize(&mut ser)?;
+    Ok(ser.into_vec())
+}
+
+/// Serialize the given data into a bencode byte vector, passing the encoding of the value at
+/// `path` to `digest`.
+///
+/// `path` is a sequence of dictionary keys starting from the top-level dictionary; an empty path
+/// selects the whole value. `digest` is called once with the exact bytes of the selected value as
+/// they appear in the output, or not at all if there is no value at `path`. This computes the
+/// info-hash of a metainfo file without serializing the `info` dictionary a second time.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::bencode;
+/// use serde_bencode::ser::{to_bytes_with_digest, Options};
+///
+/// let torrent = bencode!({
+///     "announce": "url",
+///     "info": { "length": 4 },
+/// });
+/// let mut info = Vec::new();
+/// let bytes = to_bytes_with_digest(&torrent, Options::default(), &["info"], |bytes| {
+///     info.extend_from_slice(bytes)
+/// })?;
+/// assert_eq!(bytes, b"d8:announce3:url4:infod6:lengthi4eee");
+/// assert_eq!(info, b"d6:lengthi4ee");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails for the same reasons as [`to_bytes_with_options`].
+pub fn to_bytes_with_digest<T, K, F>(
+    b: &T,
+    options: Options,
+    path: &[K],
+    mut digest: F,
+) -> Result<Vec<u8>>
+where
+    T: ser::Serialize,
+    K: AsRef<[u8]>,
+    F: FnMut(&[u8]),
+{
+    let mut ser = Serializer::with_options(options);
+    if !path.is_empty() {
+        ser.path = Some(path.iter().map(|key| key.as_ref().to_vec()).collect());
+    }
     b.serialize(&mut ser)?;
+    let selected = match ser.selected.take() {
+        _ if path.is_empty() => Some(0..ser.buf.len()),
+        selected => selected,
+    };
+    if let Some(range) = selected {
+        digest(&ser.buf[range]);
+    }
     Ok(ser.into_vec())
 }
 
+/// Serialize the given data, appending its bencode encoding to `out`.
+///
+/// This reuses the capacity of `out`, so serializing many values into the same buffer, clearing
+/// it in between, allocates for the output only while the buffer grows.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::ser::to_bytes_in;
+///
+/// let mut out = Vec::with_capacity(64);
+/// for i in 0..3 {
+///     out.clear();
+///     to_bytes_in(&("ping", i), &mut out)?;
+///     assert_eq!(out, format!("l4:pingi{}ee", i).as_bytes());
+/// }
+/// to_bytes_in(&"more", &mut out)?;
+/// assert_eq!(out, b"l4:pingi2ee4:more");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails for the same reasons as [`to_bytes`]. `out` is left as it was if it does.
+pub fn to_bytes_in<T: ser::Serialize>(b: &T, out: &mut Vec<u8>) -> Result<()> {
+    to_bytes_in_with_options(b, out, Options::default())
+}
+
+/// Serialize the given data, appending its bencode encoding to `out` like [`to_bytes_in`], using
+/// the given options.
+///
+/// # Errors
+///
+/// This fails for the same reasons as [`to_bytes_with_options`]. `out` is left as it was if it
+/// does.
+pub fn to_bytes_in_with_options<T: ser::Serialize>(
+    b: &T,
+    out: &mut Vec<u8>,
+    options: Options,
+) -> Result<()> {
+    let start = out.len();
+    let mut ser = Serializer::with_options(options);
+    ser.buf = mem::take(out);
+    let result = b.serialize(&mut ser);
+    *out = ser.into_vec();
+    if result.is_err() {
+        out.truncate(start);
+    }
+    result
+}
+
+/// Compute the length of the bencode encoding of the given data, without building the output.
+///
+/// The result is exactly `to_bytes(b)?.len()`. Dictionary values are still buffered while their
+/// keys are sorted, but only their lengths are kept. This is useful for pre-sizing buffers or
+/// writing length-prefixed frames.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::ser::serialized_len;
+///
+/// let value = ("spam", vec![1, 2, 3]);
+/// assert_eq!(serialized_len(&value)?, serde_bencode::to_bytes(&value)?.len());
+/// assert_eq!(serialized_len(&value)?, 19);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails for the same reasons as [`to_bytes`].
+pub fn serialized_len<T: ser::Serialize>(b: &T) -> Result<usize> {
+    serialized_len_with_options(b, Options::default())
+}
+
+/// Compute the length of the bencode encoding of the given data like [`serialized_len`], using
+/// the given options.
+///
+/// # Errors
+///
+/// This fails for the same reasons as [`to_bytes_with_options`].
+pub fn serialized_len_with_options<T: ser::Serialize>(b: &T, options: Options) -> Result<usize> {
+    let mut ser = Serializer::<Counter>::with_output(options);
+    b.serialize(&mut ser)?;
+    Ok(ser.buf.0)
+}
+
 /// Serialize the given data into a String of bencode.
 ///
 /// # Examples
@@ -411,7 +793,7 @@ pub fn to_bytes<T: ser::Serialize>(b: &T) -> Result<Vec<u8>> {
 /// # Errors
 ///
 /// Serialization can fail if `T`'s implementation of `Serialize` decides to fail or `T` contains
-/// floating point values, which bencode cannot serialize.
+/// floating point values, which bencode cannot serialize unless a [`FloatPolicy`] is configured.
 pub fn to_string<T: ser::Serialize>(b: &T) -> Result<String> {
     let mut ser = Serializer::new();
     b.serialize(&mut ser)?;
@@ -419,3 +801,34 @@ pub fn to_string<T: ser::Serialize>(b: &T) -> Result<String> {
         .map(|s| s.to_string())
         .map_err(|_| Error::InvalidValue("Not an UTF-8".to_string()))
 }
+
+/// Serialize the given data as bencode into an asynchronous writer.
+///
+/// The value is serialized in memory first and then written with a single
+/// [`write_all`](tokio::io::AsyncWriteExt::write_all). The writer is not flushed.
+///
+/// # Examples
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), serde_bencode::Error> {
+/// let mut out = Vec::new();
+/// serde_bencode::ser::to_async_writer(&mut out, &("spam", 42)).await?;
+/// assert_eq!(out, b"l4:spami42ee");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails on I/O errors, and for the same reasons as [`to_bytes`].
+#[cfg(feature = "tokio")]
+pub async fn to_async_writer<W, T>(mut writer: W, b: &T) -> Result<()>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+    T: ser::Serialize,
+{
+    use tokio::io::AsyncWriteExt;
+
+    let bytes = to_bytes(b)?;
+    writer.write_all(&bytes).await.map_err(Error::Io)
+}
@@ -0,0 +1,143 @@
+//! Serializer that only accepts a single `u8`, for writing byte arrays as byte strings.
+
+use crate::error::{Error, Result};
+use alloc::string::String;
+use serde::ser;
+
+/// Accepts a `u8` and rejects everything else, so that a tuple can find out whether its elements
+/// are bytes.
+pub(super) struct ByteSerializer;
+
+/// The error for anything that is not a `u8`. It never reaches the caller, so it carries no
+/// message.
+fn not_a_byte<T>() -> Result<T> {
+    Err(Error::Custom(String::new()))
+}
+
+impl ser::Serializer for ByteSerializer {
+    type Ok = u8;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<u8, Error>;
+    type SerializeTuple = ser::Impossible<u8, Error>;
+    type SerializeTupleStruct = ser::Impossible<u8, Error>;
+    type SerializeTupleVariant = ser::Impossible<u8, Error>;
+    type SerializeMap = ser::Impossible<u8, Error>;
+    type SerializeStruct = ser::Impossible<u8, Error>;
+    type SerializeStructVariant = ser::Impossible<u8, Error>;
+
+    fn serialize_u8(self, value: u8) -> Result<u8> {
+        Ok(value)
+    }
+    fn serialize_bool(self, _value: bool) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_i8(self, _value: i8) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_i16(self, _value: i16) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_i32(self, _value: i32) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_i64(self, _value: i64) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_u16(self, _value: u16) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_u32(self, _value: u32) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_u64(self, _value: u64) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_f32(self, _value: f32) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_f64(self, _value: f64) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_char(self, _value: char) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_str(self, _value: &str) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_bytes(self, _value: &[u8]) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_unit(self) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_none(self) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_some<T: ?Sized + ser::Serialize>(self, _value: &T) -> Result<u8> {
+        not_a_byte()
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        not_a_byte()
+    }
+    fn serialize_tuple(self, _size: usize) -> Result<Self::SerializeTuple> {
+        not_a_byte()
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        not_a_byte()
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        not_a_byte()
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        not_a_byte()
+    }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        not_a_byte()
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        not_a_byte()
+    }
+}
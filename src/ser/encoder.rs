@@ -0,0 +1,90 @@
+//! Encoding many values onto one writer.
+
+use super::{to_bytes_in_with_options, Options};
+use crate::error::Result;
+use alloc::vec::Vec;
+use serde::ser;
+use std::io::Write;
+
+/// Serializes a sequence of values back to back onto one [`Write`]r, the counterpart of
+/// [`StreamDeserializer`](crate::de::StreamDeserializer).
+///
+/// Each value is serialized into a buffer that the encoder keeps from one call to the next, and
+/// then written with a single [`write_all`](Write::write_all), so a value that fails to
+/// serialize writes nothing. The writer is only flushed when [`flush`](Encoder::flush) is called,
+/// which suits long-lived connections carrying many small messages, such as KRPC over TCP.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::ser::Encoder;
+///
+/// let mut encoder = Encoder::new(Vec::new());
+/// encoder.encode(&("ping", 1))?;
+/// encoder.encode(&"pong")?;
+/// encoder.flush()?;
+/// assert_eq!(encoder.into_inner(), b"l4:pingi1ee4:pong");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Encoder<W: Write> {
+    writer: W,
+    options: Options,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create an encoder that writes to `writer` with the default options.
+    pub fn new(writer: W) -> Encoder<W> {
+        Self::with_options(writer, Options::default())
+    }
+
+    /// Create an encoder that writes to `writer`, using the given options for every value.
+    pub fn with_options(writer: W, options: Options) -> Encoder<W> {
+        Encoder {
+            writer,
+            options,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Serialize `value` and write its encoding to the writer, without flushing it.
+    ///
+    /// # Errors
+    ///
+    /// This fails on I/O errors, and for the same reasons as
+    /// [`to_bytes_with_options`](super::to_bytes_with_options). Nothing is written if the value
+    /// cannot be serialized.
+    pub fn encode<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        self.buf.clear();
+        to_bytes_in_with_options(&value, &mut self.buf, self.options.clone())?;
+        self.writer.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    /// Flush the writer.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the writer cannot be flushed.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// The writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// The writer, for writing to it directly between values.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Give back the writer. It is not flushed.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
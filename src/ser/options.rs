@@ -0,0 +1,204 @@
+//! Options for configuring how bencode is encoded.
+
+use crate::de::DEFAULT_MAX_DEPTH;
+
+/// Configuration for a [`Serializer`](super::Serializer).
+///
+/// The defaults produce canonical bencode, as required by the specification, and limit nesting
+/// to the same [`DEFAULT_MAX_DEPTH`] as the deserializer.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::ser::{to_bytes_with_options, Options};
+/// use std::collections::BTreeMap;
+///
+/// let mut map = BTreeMap::new();
+/// map.insert("b", 1);
+/// map.insert("a", 2);
+/// assert_eq!(to_bytes_with_options(&map, Options::new()).unwrap(), b"d1:ai2e1:bi1ee");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub(crate) sort_keys: bool,
+    pub(crate) none: NonePolicy,
+    pub(crate) unit: UnitPolicy,
+    pub(crate) bool: BoolPolicy,
+    pub(crate) float: FloatPolicy,
+    pub(crate) keys: KeyPolicy,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) byte_tuples: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            sort_keys: true,
+            none: NonePolicy::Skip,
+            unit: UnitPolicy::Skip,
+            bool: BoolPolicy::Integer,
+            float: FloatPolicy::Error,
+            keys: KeyPolicy::String,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            byte_tuples: true,
+        }
+    }
+}
+
+/// How `Option::None` is serialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonePolicy {
+    /// Write nothing. Dictionary entries and struct fields with a `None` value are left out
+    /// entirely, which is how optional keys are usually modelled. This is the default.
+    Skip,
+    /// Fail with an error.
+    Error,
+}
+
+/// How `()`, unit structs and the like are serialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitPolicy {
+    /// Write nothing, like [`NonePolicy::Skip`]. This is the default.
+    Skip,
+    /// Write an empty byte string, `0:`.
+    EmptyBytes,
+    /// Write an empty list, `le`.
+    EmptyList,
+    /// Write an empty dictionary, `de`.
+    EmptyDict,
+    /// Fail with an error.
+    Error,
+}
+
+/// How `f32` and `f64` values are represented. Bencode has no floating point type, so there is no
+/// lossless default.
+///
+/// The same policy type is used by the serializer and by the
+/// [deserializer](crate::de::Options::float); both sides have to agree for values to round-trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatPolicy {
+    /// Fail with an error. This is the default.
+    Error,
+    /// Represent floats with an integral value as integers, e.g. `2.0` as `i2e`. Other values,
+    /// including NaN and infinities, are an error.
+    Integer,
+    /// Represent floats as byte strings holding their decimal representation, e.g. `2.5` as
+    /// `3:2.5`. NaN and infinities are an error.
+    String,
+}
+
+/// How `bool` values are represented. Bencode has no boolean type, so flags such as the
+/// `private` key of a torrent are conventionally the integers `0` and `1`.
+///
+/// The same policy type is used by the serializer and by the
+/// [deserializer](crate::de::Options::bool).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoolPolicy {
+    /// Write `i1e` for `true` and `i0e` for `false`, and only decode those two integers. This is
+    /// the default.
+    Integer,
+    /// Like [`BoolPolicy::Integer`], but decode any integer other than `0` as `true`.
+    NonZero,
+    /// Fail with an error.
+    Error,
+}
+
+/// How dictionary keys that are integers, `char`s or unit enum variants, such as the keys of a
+/// `BTreeMap<u64, T>`, are serialized. Bencode dictionary keys are always byte strings.
+///
+/// Keys are sorted after they have been converted, so integer keys come out in the order of
+/// their text, e.g. `10` before `9`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyPolicy {
+    /// Write integers as byte strings holding their decimal representation, `char`s as their
+    /// UTF-8 encoding and unit variants as their name, which is what the
+    /// [deserializer](crate::Deserializer) parses such keys from. This is the default.
+    String,
+    /// Fail with an error, so only string and byte string keys are accepted.
+    Error,
+}
+
+impl Options {
+    /// Create a new set of options with the default settings.
+    pub fn new() -> Options {
+        Self::default()
+    }
+
+    /// Write dictionary keys in raw-byte lexicographic order, and reject duplicate keys.
+    ///
+    /// This is on by default and is what the bencode specification requires; it also makes the
+    /// output independent of the iteration order of maps such as `HashMap`, so hashes of the
+    /// output are stable. Turning it off writes entries in the order they are serialized, which
+    /// is only useful to reproduce non-conforming input.
+    pub fn sort_keys(mut self, sort_keys: bool) -> Options {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Set how `None` values are serialized. See [`NonePolicy`].
+    pub fn none(mut self, none: NonePolicy) -> Options {
+        self.none = none;
+        self
+    }
+
+    /// Set how unit values are serialized. See [`UnitPolicy`].
+    pub fn unit(mut self, unit: UnitPolicy) -> Options {
+        self.unit = unit;
+        self
+    }
+
+    /// Set how `bool` values are serialized. See [`BoolPolicy`].
+    pub fn bool(mut self, bool: BoolPolicy) -> Options {
+        self.bool = bool;
+        self
+    }
+
+    /// Set how floating point values are serialized. See [`FloatPolicy`].
+    pub fn float(mut self, float: FloatPolicy) -> Options {
+        self.float = float;
+        self
+    }
+
+    /// Set how integer, `char` and unit variant dictionary keys are serialized. See
+    /// [`KeyPolicy`].
+    pub fn keys(mut self, keys: KeyPolicy) -> Options {
+        self.keys = keys;
+        self
+    }
+
+    /// Write tuples and fixed-size arrays whose elements are all `u8`, such as an info-hash of
+    /// type `[u8; 20]`, as byte strings instead of lists.
+    ///
+    /// This is on by default. Whether a tuple qualifies is decided from the values of its
+    /// elements as they are serialized, so `(1u8, 2u8)` is written as `2:\x01\x02` as well;
+    /// empty tuples are always written as `le`. The deserializer accepts either form for such
+    /// types. Turning this off writes every tuple as a list, for protocols that expect lists of
+    /// small integers.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::ser::{to_bytes_with_options, Options};
+    ///
+    /// assert_eq!(to_bytes_with_options(&[1u8, 2], Options::new()).unwrap(), b"2:\x01\x02");
+    /// let options = Options::new().byte_tuples(false);
+    /// assert_eq!(to_bytes_with_options(&[1u8, 2], options).unwrap(), b"li1ei2ee");
+    /// ```
+    pub fn byte_tuples(mut self, byte_tuples: bool) -> Options {
+        self.byte_tuples = byte_tuples;
+        self
+    }
+
+    /// Limit how deeply lists and dictionaries may be nested, failing with
+    /// [`Error::LimitExceeded`](crate::Error::LimitExceeded) beyond it. A top-level list has
+    /// depth 1.
+    ///
+    /// Serializing nested values recurses once per level, so raising this far beyond
+    /// [`DEFAULT_MAX_DEPTH`] for untrusted data, such as a [`Value`](crate::value::Value) decoded
+    /// without limits, may exhaust the thread's stack. [`Value::to_bytes`] encodes a `Value`
+    /// without recursing.
+    ///
+    /// [`Value::to_bytes`]: crate::value::Value::to_bytes
+    pub fn max_depth(mut self, max_depth: usize) -> Options {
+        self.max_depth = Some(max_depth);
+        self
+    }
+}
@@ -0,0 +1,63 @@
+//! Destinations for serialized bencode.
+
+use alloc::vec::Vec;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A destination for the bytes produced by a [`Serializer`](super::Serializer).
+///
+/// This trait is sealed. It is implemented by `Vec<u8>`.
+pub trait Output: private::Sealed + Default {
+    #[doc(hidden)]
+    fn push(&mut self, bytes: &[u8]);
+
+    #[doc(hidden)]
+    fn len(&self) -> usize;
+
+    #[doc(hidden)]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append the output of a nested serializer, such as a buffered dictionary value.
+    #[doc(hidden)]
+    fn append(&mut self, other: Self);
+}
+
+impl private::Sealed for Vec<u8> {}
+
+impl Output for Vec<u8> {
+    fn push(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn append(&mut self, mut other: Self) {
+        Vec::append(self, &mut other);
+    }
+}
+
+/// Counts the serialized bytes instead of storing them.
+#[derive(Debug, Default)]
+pub(crate) struct Counter(pub(crate) usize);
+
+impl private::Sealed for Counter {}
+
+impl Output for Counter {
+    fn push(&mut self, bytes: &[u8]) {
+        self.0 += bytes.len();
+    }
+
+    fn len(&self) -> usize {
+        self.0
+    }
+
+    fn append(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
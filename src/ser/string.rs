@@ -1,10 +1,13 @@
 //! Serializer for serializing *just* strings.
 
+use super::KeyPolicy;
 use crate::error::{Error, Result};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str;
 use serde::de;
 use serde::ser;
-use std::fmt;
-use std::str;
 
 struct Expected;
 impl de::Expected for Expected {
@@ -20,11 +23,33 @@ fn unexpected<T>(unexp: de::Unexpected) -> Result<T> {
 #[doc(hidden)]
 /// StringSerializer for serializing *just* strings (bytes are also strings in bencode).
 /// The string is returned as Result<Vec<u8>>::Ok without any prefixing (without bencode string
-/// length prefix).
+/// length prefix). Integer, `char` and unit variant dictionary keys are handled according to a
+/// [`KeyPolicy`].
 // TODO: This should be pub(crate).
-pub struct StringSerializer;
+pub struct StringSerializer {
+    keys: KeyPolicy,
+}
+
+impl StringSerializer {
+    /// Create a serializer for dictionary keys.
+    pub(crate) fn new(keys: KeyPolicy) -> StringSerializer {
+        StringSerializer { keys }
+    }
 
-impl<'a> ser::Serializer for &'a mut StringSerializer {
+    /// Create a serializer that only accepts strings and bytes.
+    pub(crate) fn strict() -> StringSerializer {
+        StringSerializer::new(KeyPolicy::Error)
+    }
+
+    fn serialize_text<T: ToString>(&self, value: T, unexp: de::Unexpected) -> Result<Vec<u8>> {
+        match self.keys {
+            KeyPolicy::String => Ok(value.to_string().into_bytes()),
+            KeyPolicy::Error => unexpected(unexp),
+        }
+    }
+}
+
+impl ser::Serializer for &mut StringSerializer {
     type Ok = Vec<u8>;
     type Error = Error;
     type SerializeSeq = ser::Impossible<Vec<u8>, Error>;
@@ -48,7 +73,10 @@ impl<'a> ser::Serializer for &'a mut StringSerializer {
         self.serialize_i64(value as i64)
     }
     fn serialize_i64(self, value: i64) -> Result<Vec<u8>> {
-        unexpected(de::Unexpected::Signed(value))
+        self.serialize_text(value, de::Unexpected::Signed(value))
+    }
+    fn serialize_i128(self, value: i128) -> Result<Vec<u8>> {
+        self.serialize_text(value, de::Unexpected::Other("128-bit integer"))
     }
     fn serialize_u8(self, value: u8) -> Result<Vec<u8>> {
         self.serialize_u64(value as u64)
@@ -60,7 +88,10 @@ impl<'a> ser::Serializer for &'a mut StringSerializer {
         self.serialize_u64(value as u64)
     }
     fn serialize_u64(self, value: u64) -> Result<Vec<u8>> {
-        unexpected(de::Unexpected::Unsigned(value))
+        self.serialize_text(value, de::Unexpected::Unsigned(value))
+    }
+    fn serialize_u128(self, value: u128) -> Result<Vec<u8>> {
+        self.serialize_text(value, de::Unexpected::Other("128-bit integer"))
     }
     fn serialize_f32(self, value: f32) -> Result<Vec<u8>> {
         self.serialize_f64(value as f64)
@@ -69,7 +100,7 @@ impl<'a> ser::Serializer for &'a mut StringSerializer {
         unexpected(de::Unexpected::Float(value))
     }
     fn serialize_char(self, value: char) -> Result<Vec<u8>> {
-        self.serialize_bytes(&[value as u8])
+        self.serialize_text(value, de::Unexpected::Char(value))
     }
     fn serialize_str(self, value: &str) -> Result<Vec<u8>> {
         self.serialize_bytes(value.as_bytes())
@@ -87,16 +118,19 @@ impl<'a> ser::Serializer for &'a mut StringSerializer {
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Vec<u8>> {
-        unexpected(de::Unexpected::UnitVariant)
+        match self.keys {
+            KeyPolicy::String => Ok(super::unescape_identifier(variant).into_owned()),
+            KeyPolicy::Error => unexpected(de::Unexpected::UnitVariant),
+        }
     }
     fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(
         self,
         _name: &'static str,
-        _value: &T,
+        value: &T,
     ) -> Result<Vec<u8>> {
-        unexpected(de::Unexpected::NewtypeStruct)
+        value.serialize(self)
     }
     fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
         self,
@@ -0,0 +1,54 @@
+//! Serializer for tuples, which writes fixed-size byte arrays as byte strings.
+
+use super::byte::ByteSerializer;
+use super::{Output, Serializer};
+use crate::error::{Error, Result};
+use alloc::vec::Vec;
+use core::mem;
+use serde::ser;
+
+/// Serializes a tuple as a byte string if all of its elements are `u8`, so that fixed-size byte
+/// arrays such as info-hashes are written as byte strings, and as a list otherwise.
+pub struct SerializeTuple<'a, W = Vec<u8>> {
+    ser: &'a mut Serializer<W>,
+    /// The elements so far while they are all bytes, or `None` once the list has been started.
+    bytes: Option<Vec<u8>>,
+}
+
+impl<'a, W: Output> SerializeTuple<'a, W> {
+    pub(super) fn new(ser: &'a mut Serializer<W>, len: usize) -> Result<SerializeTuple<'a, W>> {
+        // An empty tuple has no elements that could tell bytes apart from other values.
+        let bytes = if len > 0 && ser.options.byte_tuples {
+            Some(Vec::with_capacity(len))
+        } else {
+            ser.open("l")?;
+            None
+        };
+        Ok(SerializeTuple { ser, bytes })
+    }
+}
+
+impl<'a, W: Output> ser::SerializeTuple for SerializeTuple<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        if let Some(bytes) = &mut self.bytes {
+            if let Ok(byte) = value.serialize(ByteSerializer) {
+                bytes.push(byte);
+                return Ok(());
+            }
+            self.ser.open("l")?;
+            for byte in mem::take(bytes) {
+                ser::Serializer::serialize_u8(&mut *self.ser, byte)?;
+            }
+            self.bytes = None;
+        }
+        value.serialize(&mut *self.ser)
+    }
+    fn end(self) -> Result<()> {
+        match self.bytes {
+            Some(bytes) => ser::Serializer::serialize_bytes(self.ser, &bytes),
+            None => ser::SerializeSeq::end(self.ser),
+        }
+    }
+}
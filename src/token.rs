@@ -0,0 +1,203 @@
+//! A low-level pull parser over the tokens of bencode input.
+//!
+//! The [`Tokenizer`] splits a byte slice into [`Token`]s without going through serde or
+//! allocating. Byte strings borrow from the input. This is useful for indexers and extractors
+//! that only care about a small part of a large document.
+//!
+//! # Examples
+//!
+//! Find the `pieces` of a metainfo file while skipping over everything else:
+//!
+//! ```
+//! use serde_bencode::token::{Token, Tokenizer};
+//!
+//! let input = b"d8:announce3:url4:infod6:lengthi4e6:pieces4:\x01\x02\x03\x04ee";
+//! let mut tokens = Tokenizer::new(input);
+//! let mut pieces = None;
+//! while let Some(token) = tokens.next() {
+//!     match token? {
+//!         (_, Token::Bytes(b"pieces")) if tokens.depth() == 2 => {
+//!             if let Some((_, Token::Bytes(value))) = tokens.next().transpose()? {
+//!                 pieces = Some(value);
+//!             }
+//!         }
+//!         // Skip the values of all top-level keys except `info`.
+//!         (_, Token::Bytes(key)) if tokens.depth() == 1 && key != b"info" => {
+//!             tokens.skip_value()?;
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! assert_eq!(pieces, Some(&b"\x01\x02\x03\x04"[..]));
+//! # Ok::<(), serde_bencode::Error>(())
+//! ```
+
+use crate::error::{Error, Result};
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::str;
+
+/// A single bencode token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// An integer.
+    Int(i64),
+    /// A byte string, borrowed from the input.
+    Bytes(&'a [u8]),
+    /// The start of a list.
+    ListStart,
+    /// The start of a dictionary.
+    DictStart,
+    /// The end of the innermost list or dictionary.
+    End,
+}
+
+/// An iterator over the tokens of a bencode byte slice.
+///
+/// Each item is the offset of the token's first byte together with the token. The input may
+/// contain several consecutive values; iteration stops at the end of the input, or after the first
+/// error. Unlike the [`Deserializer`](crate::Deserializer), the tokenizer does not check that
+/// dictionary keys are byte strings, and integers must fit in an `i64`.
+#[derive(Debug, Clone)]
+pub struct Tokenizer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    depth: usize,
+    failed: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Create a tokenizer reading `input` from the start.
+    pub fn new(input: &'a [u8]) -> Tokenizer<'a> {
+        Tokenizer {
+            input,
+            pos: 0,
+            depth: 0,
+            failed: false,
+        }
+    }
+
+    /// The offset of the next unread byte.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// The number of lists and dictionaries that have been opened but not closed yet.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The next unread byte, which identifies the kind of the next token. Returns `None` at the
+    /// end of the input.
+    pub fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    /// Skip over the next complete value, returning its raw encoding, or `None` if the input
+    /// ends here.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value is malformed or incomplete, or if the next token closes a container
+    /// instead of starting a value.
+    pub fn skip_value(&mut self) -> Result<Option<&'a [u8]>> {
+        let result = self.skip_value_inner();
+        self.failed |= result.is_err();
+        result
+    }
+
+    fn skip_value_inner(&mut self) -> Result<Option<&'a [u8]>> {
+        let start = self.pos;
+        let depth = self.depth;
+        loop {
+            match self.next_token()? {
+                None => return Ok(None),
+                Some((_, Token::End)) if self.depth < depth => {
+                    return Err(Error::Syntax(
+                        "Expected a value, found the end of a container".to_string(),
+                    ))
+                }
+                Some(_) if self.depth == depth => return Ok(Some(&self.input[start..self.pos])),
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<(usize, Token<'a>)>> {
+        let start = self.pos;
+        let rest = &self.input[start..];
+        let token = match rest.first() {
+            None if self.depth > 0 => return Err(Error::EndOfStream),
+            None => return Ok(None),
+            Some(b'i') => {
+                let end = find(rest, b'e')?;
+                let digits = &rest[1..end];
+                let i = str::from_utf8(digits)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| {
+                        Error::Syntax(format!(
+                            "Can't parse `{}` as integer",
+                            String::from_utf8_lossy(digits)
+                        ))
+                    })?;
+                self.pos += end + 1;
+                Token::Int(i)
+            }
+            Some(b'0'..=b'9') => {
+                let colon = find(rest, b':')?;
+                let digits = &rest[..colon];
+                let len: usize = str::from_utf8(digits)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| {
+                        Error::Syntax(format!(
+                            "Can't parse `{}` as string length",
+                            String::from_utf8_lossy(digits)
+                        ))
+                    })?;
+                let bytes = rest
+                    .get(colon + 1..)
+                    .and_then(|rest| rest.get(..len))
+                    .ok_or(Error::EndOfStream)?;
+                self.pos += colon + 1 + len;
+                Token::Bytes(bytes)
+            }
+            Some(b'l') => {
+                self.pos += 1;
+                self.depth += 1;
+                Token::ListStart
+            }
+            Some(b'd') => {
+                self.pos += 1;
+                self.depth += 1;
+                Token::DictStart
+            }
+            Some(b'e') if self.depth > 0 => {
+                self.pos += 1;
+                self.depth -= 1;
+                Token::End
+            }
+            Some(&c) => return Err(Error::Syntax(format!("Invalid character `{}`", c as char))),
+        };
+        Ok(Some((start, token)))
+    }
+}
+
+/// Find the position of `byte` in `input`, failing if the input ends first.
+fn find(input: &[u8], byte: u8) -> Result<usize> {
+    memchr::memchr(byte, input).ok_or(Error::EndOfStream)
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<(usize, Token<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.next_token().transpose();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
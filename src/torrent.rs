@@ -0,0 +1,343 @@
+//! Typed structures for metainfo (`.torrent`) files, as described in [BEP 3] and, for version 2
+//! of the protocol, [BEP 52].
+//!
+//! The `info` dictionary is kept as a [`RawValue`], so that [`Metainfo::info_hash`] and
+//! [`Metainfo::info_hash_v2`] hash exactly the bytes that were received, and decoded on demand
+//! with [`Metainfo::info`]. Both the single-file and the multi-file layout of [`Info`] are
+//! supported, as well as the `file tree` of v2 and hybrid torrents; the optional keys that are
+//! absent from the input are `None`, and are left out when serializing.
+//!
+//! [BEP 3]: https://www.bittorrent.org/beps/bep_0003.html
+//! [BEP 52]: https://www.bittorrent.org/beps/bep_0052.html
+//!
+//! # Examples
+//! ```
+//! use serde_bencode::torrent::{Info, Metainfo};
+//!
+//! let info = Info {
+//!     name: "example.iso".to_string(),
+//!     piece_length: 262144,
+//!     pieces: vec![0; 20],
+//!     length: Some(1000),
+//!     files: None,
+//!     private: None,
+//!     md5sum: None,
+//!     meta_version: None,
+//!     file_tree: None,
+//! };
+//! let metainfo = Metainfo::new("http://tracker.example/announce", &info)?;
+//! let bytes = serde_bencode::to_bytes(&metainfo)?;
+//!
+//! let decoded: Metainfo = serde_bencode::from_bytes(&bytes)?;
+//! assert_eq!(decoded.info()?, info);
+//! assert_eq!(decoded.info_hash(), metainfo.info_hash());
+//! # Ok::<(), serde_bencode::Error>(())
+//! ```
+
+use crate::error::Result;
+use crate::value::RawValue;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use serde::de::{self, Deserialize};
+use serde::ser::{self, Serialize, SerializeMap};
+use serde_bytes::{ByteBuf, Bytes};
+use serde_derive::{Deserialize, Serialize};
+use sha2::Digest;
+use std::time::SystemTime;
+
+/// The top-level dictionary of a metainfo file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metainfo {
+    /// The URL of the tracker. Trackerless torrents may leave it out.
+    pub announce: Option<String>,
+    /// Tiers of tracker URLs ([BEP 12](https://www.bittorrent.org/beps/bep_0012.html)).
+    #[serde(rename = "announce-list")]
+    pub announce_list: Option<Vec<Vec<String>>>,
+    /// A free-form comment.
+    pub comment: Option<String>,
+    /// The program that created the file.
+    #[serde(rename = "created by")]
+    pub created_by: Option<String>,
+    /// When the file was created.
+    #[serde(
+        rename = "creation date",
+        default,
+        with = "crate::helpers::unix_seconds::option"
+    )]
+    pub creation_date: Option<SystemTime>,
+    /// The character encoding of the strings in `info`.
+    pub encoding: Option<String>,
+    /// The `info` dictionary, exactly as encoded.
+    pub info: RawValue,
+    /// The hashes of the pieces of each file in a v2 torrent.
+    #[serde(rename = "piece layers", default, with = "piece_layers")]
+    pub piece_layers: Option<PieceLayers>,
+}
+
+/// The `info` dictionary of a metainfo file.
+///
+/// A single-file torrent has a `length` and no `files`; a multi-file torrent has `files`, and
+/// its `name` is the name of the directory they are in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Info {
+    /// The suggested name of the file or directory.
+    pub name: String,
+    /// The number of bytes in each piece.
+    #[serde(rename = "piece length")]
+    pub piece_length: u64,
+    /// The concatenated SHA-1 hashes of all pieces, 20 bytes each. Empty in a v2-only torrent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "serde_bytes")]
+    pub pieces: Vec<u8>,
+    /// The length of the file, in single-file mode.
+    pub length: Option<u64>,
+    /// The files, in multi-file mode.
+    pub files: Option<Vec<FileEntry>>,
+    /// Set to 1 to restrict peer discovery to the trackers
+    /// ([BEP 27](https://www.bittorrent.org/beps/bep_0027.html)).
+    pub private: Option<i64>,
+    /// The MD5 sum of the file in hex, in single-file mode.
+    pub md5sum: Option<String>,
+    /// The version of the metainfo format, 2 for v2 and hybrid torrents.
+    #[serde(rename = "meta version")]
+    pub meta_version: Option<u64>,
+    /// The files of a v2 torrent, by name.
+    #[serde(rename = "file tree")]
+    pub file_tree: Option<BTreeMap<String, FileTree>>,
+}
+
+/// A file in a multi-file torrent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// The length of the file.
+    pub length: u64,
+    /// The path of the file below the torrent's directory, one element per component.
+    pub path: Vec<String>,
+    /// The MD5 sum of the file in hex.
+    pub md5sum: Option<String>,
+}
+
+/// The `piece layers` of a v2 torrent, keyed by the [`pieces_root`](File::pieces_root) of
+/// each file larger than one piece. Each value is the concatenated 32-byte hashes of the file's
+/// pieces.
+pub type PieceLayers = BTreeMap<[u8; 32], Vec<u8>>;
+
+/// An entry of the `file tree` of a v2 torrent.
+///
+/// A file is encoded as a dictionary with a single empty key, which holds its attributes; a
+/// directory as a dictionary of the entries in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileTree {
+    /// A file.
+    File(File),
+    /// A directory containing the named entries.
+    Directory(BTreeMap<String, FileTree>),
+}
+
+/// The attributes of a file in the `file tree` of a v2 torrent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct File {
+    /// The length of the file.
+    pub length: u64,
+    /// The root of the Merkle tree of the file's 16 KiB blocks. Empty files have none.
+    #[serde(rename = "pieces root", default)]
+    pub pieces_root: Option<[u8; 32]>,
+}
+
+impl Metainfo {
+    /// Create a metainfo with a single tracker and no optional keys.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `info` cannot be serialized.
+    pub fn new(announce: &str, info: &Info) -> Result<Metainfo> {
+        Ok(Metainfo {
+            announce: Some(announce.to_string()),
+            announce_list: None,
+            comment: None,
+            created_by: None,
+            creation_date: None,
+            encoding: None,
+            info: RawValue::from_value(info)?,
+            piece_layers: None,
+        })
+    }
+
+    /// Decode the `info` dictionary.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the dictionary does not match [`Info`].
+    pub fn info(&self) -> Result<Info> {
+        self.info.decode()
+    }
+
+    /// The SHA-1 hash of the encoded `info` dictionary, which identifies the torrent.
+    pub fn info_hash(&self) -> [u8; 20] {
+        sha1_smol::Sha1::from(self.info.as_bytes()).digest().bytes()
+    }
+
+    /// The SHA-256 hash of the encoded `info` dictionary, which identifies a v2 or hybrid
+    /// torrent.
+    ///
+    /// This is the hash of the bytes that were decoded, or of the canonical encoding for a
+    /// metainfo built with [`Metainfo::new`]. The tracker and DHT protocols of v1 use the first
+    /// 20 bytes of it.
+    pub fn info_hash_v2(&self) -> [u8; 32] {
+        sha2::Sha256::digest(self.info.as_bytes()).into()
+    }
+}
+
+impl Info {
+    /// The total length of all files.
+    pub fn total_length(&self) -> u64 {
+        match (&self.files, &self.length, &self.file_tree) {
+            (Some(files), _, _) => files.iter().map(|file| file.length).sum(),
+            (None, Some(length), _) => *length,
+            (None, None, Some(tree)) => tree.values().map(FileTree::total_length).sum(),
+            (None, None, None) => 0,
+        }
+    }
+}
+
+impl FileTree {
+    /// The length of the file, or the total length of the files in the directory.
+    pub fn total_length(&self) -> u64 {
+        match self {
+            FileTree::File(file) => file.length,
+            FileTree::Directory(entries) => entries.values().map(FileTree::total_length).sum(),
+        }
+    }
+
+    /// All files below this entry with their paths, in order. The path of a [`FileTree::File`]
+    /// itself is empty.
+    pub fn files(&self) -> Vec<(Vec<&str>, &File)> {
+        let mut files = Vec::new();
+        self.collect_files(&mut Vec::new(), &mut files);
+        files
+    }
+
+    fn collect_files<'a>(
+        &'a self,
+        path: &mut Vec<&'a str>,
+        files: &mut Vec<(Vec<&'a str>, &'a File)>,
+    ) {
+        match self {
+            FileTree::File(file) => files.push((path.clone(), file)),
+            FileTree::Directory(entries) => {
+                for (name, entry) in entries {
+                    path.push(name);
+                    entry.collect_files(path, files);
+                    path.pop();
+                }
+            }
+        }
+    }
+}
+
+impl Serialize for FileTree {
+    fn serialize<S: ser::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        match self {
+            FileTree::File(file) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("", file)?;
+                map.end()
+            }
+            FileTree::Directory(entries) => entries.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FileTree {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<FileTree, D::Error> {
+        deserializer.deserialize_map(FileTreeVisitor)
+    }
+}
+
+struct FileTreeVisitor;
+
+impl<'de> de::Visitor<'de> for FileTreeVisitor {
+    type Value = FileTree;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a file or directory of a file tree")
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(
+        self,
+        mut access: A,
+    ) -> core::result::Result<FileTree, A::Error> {
+        let mut entries = BTreeMap::new();
+        while let Some(name) = access.next_key::<String>()? {
+            if name.is_empty() {
+                if !entries.is_empty() {
+                    return Err(de::Error::custom(
+                        "a file tree entry is both a file and a directory",
+                    ));
+                }
+                let file = access.next_value()?;
+                if access.next_key::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::custom(
+                        "a file tree entry is both a file and a directory",
+                    ));
+                }
+                return Ok(FileTree::File(file));
+            }
+            entries.insert(name, access.next_value()?);
+        }
+        Ok(FileTree::Directory(entries))
+    }
+}
+
+/// The `piece layers` dictionary, keyed by raw 32-byte hashes and holding byte strings.
+mod piece_layers {
+    use super::{fmt, BTreeMap, ByteBuf, Bytes, PieceLayers};
+    use serde::ser::SerializeMap;
+    use serde::{de, ser};
+
+    pub fn serialize<S: ser::Serializer>(
+        layers: &Option<PieceLayers>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let layers = match layers {
+            Some(layers) => layers,
+            None => return serializer.serialize_none(),
+        };
+        let mut map = serializer.serialize_map(Some(layers.len()))?;
+        for (root, layer) in layers {
+            map.serialize_entry(Bytes::new(root), Bytes::new(layer))?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<PieceLayers>, D::Error> {
+        deserializer.deserialize_map(LayersVisitor).map(Some)
+    }
+
+    struct LayersVisitor;
+
+    impl<'de> de::Visitor<'de> for LayersVisitor {
+        type Value = PieceLayers;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a dictionary of piece layers keyed by 32-byte hashes")
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+            let mut layers = BTreeMap::new();
+            while let Some(root) = access.next_key::<[u8; 32]>()? {
+                let layer: ByteBuf = access.next_value()?;
+                layers.insert(root, layer.into_vec());
+            }
+            Ok(layers)
+        }
+    }
+}
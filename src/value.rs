@@ -1,25 +1,203 @@
 //! Structures for representing bencoded values with Rust data types.
+//!
+//! # Capturing unknown keys
+//!
+//! Metainfo files and tracker responses often carry extension keys that a typed structure does not
+//! know about. They can be preserved by flattening the remaining dictionary entries into a map of
+//! [`Value`]s. Using [`ByteBuf`](serde_bytes::ByteBuf) keys keeps entries whose keys are not valid
+//! UTF-8, and since dictionary keys are always written in sorted order, canonical input is
+//! re-serialized byte-for-byte.
+//!
+//! ```
+//! use serde_bencode::value::Value;
+//! use serde_bytes::ByteBuf;
+//! use serde_derive::{Deserialize, Serialize};
+//! use std::collections::BTreeMap;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Torrent {
+//!     announce: String,
+//!     #[serde(flatten)]
+//!     rest: BTreeMap<ByteBuf, Value>,
+//! }
+//!
+//! let input = b"d8:announce3:url8:url-listl1:ae2:\xff\xfei1ee";
+//! let torrent: Torrent = serde_bencode::from_bytes(input).unwrap();
+//! assert_eq!(torrent.rest.len(), 2);
+//! assert_eq!(serde_bencode::to_bytes(&torrent).unwrap(), &input[..]);
+//! ```
 
+#[cfg(feature = "arbitrary_precision")]
+mod big_int;
+mod byte_string;
+mod cmp;
+mod deserializer;
+mod encode;
+mod entry;
+mod index;
+#[cfg(feature = "json")]
+mod json;
+mod lazy;
+pub mod map;
+mod merge;
+mod raw_dict;
+mod raw_value;
+mod text;
+mod tree;
+
+#[cfg(feature = "arbitrary_precision")]
+pub use self::big_int::BigInt;
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) use self::big_int::{BigIntAccess, TOKEN as BIG_INT_TOKEN};
+pub use self::byte_string::ByteString;
+pub use self::entry::Entry;
+pub use self::index::Index;
+#[cfg(feature = "json")]
+pub use self::json::JsonBytes;
+pub use self::lazy::{LazyDict, LazyList, LazyValue};
+pub use self::map::Map;
+pub use self::merge::MergePolicy;
+pub use self::raw_dict::RawDict;
+pub use self::raw_value::RawValue;
+pub(crate) use self::raw_value::TOKEN as RAW_VALUE_TOKEN;
+pub(crate) use self::tree::TOKEN as VALUE_TOKEN;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
 use serde::de;
-use serde::ser::{self, SerializeMap, SerializeSeq};
-use serde_bytes::{ByteBuf, Bytes};
+use serde::ser::{self, SerializeSeq};
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt;
 
 /// All possible values which may be serialized in bencode.
+///
+/// Features can add variants, such as `Value::BigInt` with `arbitrary_precision`, so matches on
+/// a `Value` need a wildcard arm.
 #[derive(PartialEq, Eq, Clone, Debug)]
+#[non_exhaustive]
 pub enum Value {
     /// A generic list of bytes.
-    Bytes(Vec<u8>),
+    Bytes(ByteString),
 
     /// An integer.
     Int(i64),
 
+    /// An integer outside of the `i64` range.
+    #[cfg(feature = "arbitrary_precision")]
+    BigInt(BigInt),
+
     /// A list of other bencoded values.
     List(Vec<Value>),
 
     /// A map of (key, value) pairs.
-    Dict(HashMap<Vec<u8>, Value>),
+    Dict(Map),
+}
+
+impl Value {
+    /// Index into a list by position or into a dictionary by key, returning `None` if the value
+    /// is not a container of the right kind or the entry does not exist.
+    ///
+    /// Indexing with `[]` works the same way, but panics instead of returning `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::bencode;
+    ///
+    /// let torrent = bencode!({ "info": { "name": "a.iso" }, "url-list": ["http://a/"] });
+    /// assert_eq!(torrent.get("info").and_then(|info| info.get(b"name")), Some(&bencode!("a.iso")));
+    /// assert_eq!(torrent["url-list"][0], bencode!("http://a/"));
+    /// assert_eq!(torrent.get("comment"), None);
+    /// ```
+    pub fn get<I: Index>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    /// Mutable version of [`get`](Value::get).
+    pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Value> {
+        index.index_into_mut(self)
+    }
+
+    /// Look up a nested value by a path of dictionary keys and list indices.
+    ///
+    /// The path uses the syntax of a JSON Pointer ([RFC 6901]): it is empty, referring to `self`,
+    /// or a sequence of `/`-prefixed tokens. `~1` in a token stands for `/` and `~0` for `~`.
+    /// Tokens are matched against dictionary keys byte for byte, so the path may contain bytes
+    /// that are not valid UTF-8. Returns `None` if any step of the path does not exist.
+    ///
+    /// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::value::Value;
+    ///
+    /// let torrent: Value =
+    ///     serde_bencode::from_bytes(b"d4:infod5:filesld6:lengthi42eeeee").unwrap();
+    /// assert_eq!(torrent.pointer("/info/files/0/length"), Some(&Value::Int(42)));
+    /// assert_eq!(torrent.pointer(b"/info/\xff"), None);
+    /// ```
+    pub fn pointer<P: AsRef<[u8]>>(&self, pointer: P) -> Option<&Value> {
+        let mut target = self;
+        for token in parse_pointer(pointer.as_ref())? {
+            target = match target {
+                Value::Dict(map) => map.get(&token[..])?,
+                Value::List(list) => list.get(parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// Look up a nested value by path, like [`pointer`](Value::pointer), and return a mutable
+    /// reference to it.
+    pub fn pointer_mut<P: AsRef<[u8]>>(&mut self, pointer: P) -> Option<&mut Value> {
+        let mut target = self;
+        for token in parse_pointer(pointer.as_ref())? {
+            target = match target {
+                Value::Dict(map) => map.get_mut(&token[..])?,
+                Value::List(list) => list.get_mut(parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+}
+
+/// Split a pointer into its unescaped tokens, or return `None` if it is malformed.
+fn parse_pointer(pointer: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let rest = match pointer {
+        [] => return Some(Vec::new()),
+        [b'/', rest @ ..] => rest,
+        _ => return None,
+    };
+    rest.split(|&b| b == b'/')
+        .map(|token| {
+            let mut unescaped = Vec::with_capacity(token.len());
+            let mut bytes = token.iter();
+            while let Some(&b) = bytes.next() {
+                unescaped.push(match b {
+                    b'~' => match bytes.next() {
+                        Some(b'0') => b'~',
+                        Some(b'1') => b'/',
+                        _ => return None,
+                    },
+                    b => b,
+                });
+            }
+            Some(unescaped)
+        })
+        .collect()
+}
+
+/// Parse a list index, which must not have leading zeros.
+fn parse_index(token: &[u8]) -> Option<usize> {
+    match token {
+        [b'0'] => Some(0),
+        [b'1'..=b'9', ..] => core::str::from_utf8(token).ok()?.parse().ok(),
+        _ => None,
+    }
 }
 
 impl ser::Serialize for Value {
@@ -31,6 +209,8 @@ impl ser::Serialize for Value {
         match *self {
             Value::Bytes(ref v) => s.serialize_bytes(v),
             Value::Int(v) => s.serialize_i64(v),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigInt(ref v) => v.serialize(s),
             Value::List(ref v) => {
                 let mut seq = s.serialize_seq(Some(v.len()))?;
                 for e in v {
@@ -38,13 +218,7 @@ impl ser::Serialize for Value {
                 }
                 seq.end()
             }
-            Value::Dict(ref vs) => {
-                let mut map = s.serialize_map(Some(vs.len()))?;
-                for (k, v) in vs {
-                    map.serialize_entry(&Bytes::new(k), v)?;
-                }
-                map.end()
-            }
+            Value::Dict(ref v) => v.serialize(s),
         }
     }
 }
@@ -64,8 +238,45 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
     }
 
     #[inline]
-    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
-        Ok(Value::Int(value as i64))
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_u128(value.into())
+    }
+
+    #[inline]
+    fn visit_i128<E>(self, value: i128) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match i64::try_from(value) {
+            Ok(value) => Ok(Value::Int(value)),
+            #[cfg(feature = "arbitrary_precision")]
+            Err(_) => Ok(Value::BigInt(value.into())),
+            #[cfg(not(feature = "arbitrary_precision"))]
+            Err(_) => Err(E::invalid_value(
+                de::Unexpected::Other("128-bit integer"),
+                &self,
+            )),
+        }
+    }
+
+    #[inline]
+    fn visit_u128<E>(self, value: u128) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match i64::try_from(value) {
+            Ok(value) => Ok(Value::Int(value)),
+            #[cfg(feature = "arbitrary_precision")]
+            Err(_) => Ok(Value::BigInt(value.into())),
+            #[cfg(not(feature = "arbitrary_precision"))]
+            Err(_) => Err(E::invalid_value(
+                de::Unexpected::Other("128-bit integer"),
+                &self,
+            )),
+        }
     }
 
     #[inline]
@@ -73,7 +284,7 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
     where
         E: de::Error,
     {
-        Ok(Value::Bytes(value.into()))
+        Ok(Value::Bytes(value.as_bytes().into()))
     }
 
     #[inline]
@@ -103,12 +314,30 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
     where
         V: de::MapAccess<'de>,
     {
-        let mut map = HashMap::new();
-        while let Some((k, v)) = access.next_entry::<ByteBuf, _>()? {
-            map.insert(k.into_vec(), v);
+        let mut map = Map::new();
+        while let Some(k) = access.next_key::<ByteString>()? {
+            if map.is_empty() && k[..] == *VALUE_TOKEN.as_bytes() {
+                return access.next_value_seed(self::tree::TreeSeed);
+            }
+            #[cfg(feature = "arbitrary_precision")]
+            {
+                if map.is_empty() && k[..] == *BIG_INT_TOKEN.as_bytes() {
+                    let digits: String = access.next_value()?;
+                    return digits.parse().map(Value::BigInt).map_err(de::Error::custom);
+                }
+            }
+            map.insert(k, access.next_value()?);
         }
         Ok(Value::Dict(map))
     }
+
+    /// Called by deserializers other than this crate's, which do not know the token.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
 }
 
 impl<'de> de::Deserialize<'de> for Value {
@@ -117,7 +346,8 @@ impl<'de> de::Deserialize<'de> for Value {
     where
         D: de::Deserializer<'de>,
     {
-        deserializer.deserialize_any(ValueVisitor)
+        // This crate's deserializer recognizes the token and builds the value without recursing.
+        deserializer.deserialize_newtype_struct(VALUE_TOKEN, ValueVisitor)
     }
 }
 
@@ -127,21 +357,57 @@ impl From<i64> for Value {
     }
 }
 
+macro_rules! impl_from_small_int {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(v: $ty) -> Value {
+                    Value::Int(v.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_small_int!(i8 i16 i32 u8 u16 u32);
+
+#[cfg(feature = "arbitrary_precision")]
+impl From<BigInt> for Value {
+    fn from(v: BigInt) -> Value {
+        match v.as_i64() {
+            Some(v) => Value::Int(v),
+            None => Value::BigInt(v),
+        }
+    }
+}
+
 impl From<String> for Value {
     fn from(s: String) -> Value {
-        Value::Bytes(s.into_bytes())
+        Value::Bytes(s.into())
     }
 }
 
-impl<'a> From<&'a str> for Value {
+impl From<&str> for Value {
     fn from(v: &str) -> Value {
-        Value::Bytes(v.as_bytes().to_vec())
+        Value::Bytes(v.as_bytes().into())
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(v: &[u8]) -> Value {
+        Value::Bytes(v.into())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for Value {
+    fn from(v: &[u8; N]) -> Value {
+        Value::Bytes(v[..].into())
     }
 }
 
 impl From<Vec<u8>> for Value {
     fn from(v: Vec<u8>) -> Value {
-        Value::Bytes(v)
+        Value::Bytes(v.into())
     }
 }
 
@@ -151,8 +417,15 @@ impl From<Vec<Value>> for Value {
     }
 }
 
+impl From<BTreeMap<Vec<u8>, Value>> for Value {
+    fn from(v: BTreeMap<Vec<u8>, Value>) -> Value {
+        Value::Dict(v.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "std")]
 impl From<HashMap<Vec<u8>, Value>> for Value {
     fn from(v: HashMap<Vec<u8>, Value>) -> Value {
-        Value::Dict(v)
+        Value::Dict(v.into_iter().collect())
     }
 }
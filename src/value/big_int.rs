@@ -0,0 +1,254 @@
+//! Integers of arbitrary size.
+
+use crate::error::Error;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+use serde::de::{self, IntoDeserializer};
+use serde::ser;
+
+/// Name used to smuggle the digits of a [`BigInt`] through serde's data model.
+pub(crate) const TOKEN: &str = "$serde_bencode::private::BigInt";
+
+/// An integer of arbitrary size, kept as its decimal digits.
+///
+/// Bencode does not restrict the size of integers, so a conforming producer may emit values that
+/// do not fit any of Rust's integer types. With the `arbitrary_precision` feature enabled, such
+/// integers are decoded into a `BigInt` (or [`Value::BigInt`](super::Value::BigInt)) instead of
+/// failing, and are encoded back unchanged.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::value::BigInt;
+///
+/// let n: BigInt = serde_bencode::from_str("i123456789012345678901234567890123456789012e").unwrap();
+/// assert_eq!(n.as_str(), "123456789012345678901234567890123456789012");
+/// assert_eq!(n.as_u128(), None);
+/// assert_eq!(
+///     serde_bencode::to_string(&n).unwrap(),
+///     "i123456789012345678901234567890123456789012e"
+/// );
+/// ```
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct BigInt {
+    digits: String,
+}
+
+impl BigInt {
+    /// The canonical decimal representation: an optional `-` followed by digits without leading
+    /// zeros.
+    pub fn as_str(&self) -> &str {
+        &self.digits
+    }
+
+    /// Consume the integer, returning its decimal representation.
+    pub fn into_string(self) -> String {
+        self.digits
+    }
+
+    /// Convert to an `i64`, if the value is in range.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.digits.parse().ok()
+    }
+
+    /// Convert to a `u64`, if the value is in range.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.digits.parse().ok()
+    }
+
+    /// Convert to an `i128`, if the value is in range.
+    pub fn as_i128(&self) -> Option<i128> {
+        self.digits.parse().ok()
+    }
+
+    /// Convert to a `u128`, if the value is in range.
+    pub fn as_u128(&self) -> Option<u128> {
+        self.digits.parse().ok()
+    }
+
+    /// Build from digits which may have leading zeros or a negative zero, as accepted by the
+    /// lenient decoder. Returns `None` if `digits` is not an integer at all.
+    pub(crate) fn from_lenient(digits: &str) -> Option<BigInt> {
+        let (negative, magnitude) = match digits.strip_prefix('-') {
+            Some(magnitude) => (true, magnitude),
+            None => (false, digits),
+        };
+        if magnitude.is_empty() || !magnitude.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let magnitude = magnitude.trim_start_matches('0');
+        let digits = match (negative, magnitude) {
+            (_, "") => "0".to_string(),
+            (true, magnitude) => format!("-{}", magnitude),
+            (false, magnitude) => magnitude.to_string(),
+        };
+        Some(BigInt { digits })
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = Error;
+
+    /// Parse the canonical decimal representation of an integer.
+    fn from_str(s: &str) -> Result<BigInt, Error> {
+        match BigInt::from_lenient(s) {
+            Some(n) if n.digits == s => Ok(n),
+            _ => Err(Error::InvalidValue(format!(
+                "`{}` is not a canonical decimal integer",
+                s
+            ))),
+        }
+    }
+}
+
+/// Orders integers by their numeric value.
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        let (a, b) = (self.digits.as_str(), other.digits.as_str());
+        match (a.strip_prefix('-'), b.strip_prefix('-')) {
+            (Some(a), Some(b)) => cmp_magnitude(b, a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => cmp_magnitude(a, b),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compare digits without leading zeros.
+fn cmp_magnitude(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.digits)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for BigInt {
+                fn from(n: $ty) -> BigInt {
+                    BigInt { digits: n.to_string() }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8 i16 i32 i64 i128 u8 u16 u32 u64 u128);
+
+impl ser::Serialize for BigInt {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        // The bencode serializer recognizes the token and writes the digits as an integer;
+        // other formats see a plain string.
+        s.serialize_newtype_struct(TOKEN, &self.digits)
+    }
+}
+
+struct BigIntVisitor;
+
+impl<'de> de::Visitor<'de> for BigIntVisitor {
+    type Value = BigInt;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer")
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<BigInt, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<BigInt, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i128<E>(self, value: i128) -> Result<BigInt, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u128<E>(self, value: u128) -> Result<BigInt, E> {
+        Ok(value.into())
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<BigInt, E>
+    where
+        E: de::Error,
+    {
+        value
+            .parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+
+    fn visit_map<V>(self, mut access: V) -> Result<BigInt, V::Error>
+    where
+        V: de::MapAccess<'de>,
+    {
+        match access.next_key::<String>()? {
+            Some(ref key) if key == TOKEN => {}
+            _ => return Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+        }
+        let digits: String = access.next_value()?;
+        digits.parse().map_err(de::Error::custom)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for BigInt {
+    fn deserialize<D>(deserializer: D) -> Result<BigInt, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(BigIntVisitor)
+    }
+}
+
+/// Presents the digits of an out-of-range integer as a single-entry map keyed by [`TOKEN`],
+/// which the `BigInt` and `Value` visitors recognize.
+pub(crate) struct BigIntAccess {
+    digits: Option<String>,
+}
+
+impl BigIntAccess {
+    pub(crate) fn new(digits: String) -> BigIntAccess {
+        BigIntAccess {
+            digits: Some(digits),
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for BigIntAccess {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if self.digits.is_none() {
+            return Ok(None);
+        }
+        seed.deserialize(TOKEN.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let digits = self
+            .digits
+            .take()
+            .ok_or_else(|| Error::Custom("value requested before key".to_string()))?;
+        seed.deserialize(digits.into_deserializer())
+    }
+}
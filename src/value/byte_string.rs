@@ -0,0 +1,230 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::{Borrow, BorrowMut};
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use serde::{de, ser};
+
+#[cfg(not(feature = "smallvec"))]
+type Repr = Vec<u8>;
+#[cfg(feature = "smallvec")]
+type Repr = smallvec::SmallVec<[u8; 16]>;
+
+/// The byte string of a [`Value::Bytes`](super::Value::Bytes) and the key type of a
+/// [`Map`](super::Map).
+///
+/// It dereferences to `[u8]` and converts from `Vec<u8>`, `&[u8]` and strings with `into()`.
+/// With the `smallvec` feature, byte strings of up to 16 bytes are stored inline instead of on
+/// the heap, so that decoding the dictionary keys and file names of a large multi-file torrent,
+/// nearly all of which are that short, does not allocate for each of them. It takes no more space
+/// than a `Vec<u8>` either way, so [`Value`](super::Value) does not grow.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::value::{ByteString, Value};
+///
+/// let name = ByteString::from("a.iso");
+/// assert_eq!(name, b"a.iso");
+/// assert_eq!(Value::Bytes(name.clone()), Value::from("a.iso"));
+/// assert_eq!(name.into_vec(), b"a.iso".to_vec());
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteString(Repr);
+
+impl ByteString {
+    /// Create an empty byte string.
+    pub fn new() -> ByteString {
+        ByteString(Repr::new())
+    }
+
+    /// A byte string of `len` zeroes, to be filled in place.
+    pub(crate) fn zeroed(len: usize) -> ByteString {
+        #[cfg(feature = "smallvec")]
+        return ByteString(Repr::from_elem(0, len));
+        #[cfg(not(feature = "smallvec"))]
+        return ByteString(alloc::vec![0; len]);
+    }
+
+    /// The bytes as a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Take the bytes out as a `Vec<u8>`, without copying them unless they are stored inline.
+    pub fn into_vec(self) -> Vec<u8> {
+        #[cfg(feature = "smallvec")]
+        return self.0.into_vec();
+        #[cfg(not(feature = "smallvec"))]
+        return self.0;
+    }
+
+    /// Whether the bytes are stored inline rather than on the heap. Always `false` without the
+    /// `smallvec` feature.
+    pub fn is_inline(&self) -> bool {
+        #[cfg(feature = "smallvec")]
+        return !self.0.spilled();
+        #[cfg(not(feature = "smallvec"))]
+        return false;
+    }
+}
+
+impl fmt::Debug for ByteString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl Deref for ByteString {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl DerefMut for ByteString {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl AsRef<[u8]> for ByteString {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsMut<[u8]> for ByteString {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl Borrow<[u8]> for ByteString {
+    fn borrow(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl BorrowMut<[u8]> for ByteString {
+    fn borrow_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl From<Vec<u8>> for ByteString {
+    fn from(bytes: Vec<u8>) -> ByteString {
+        #[cfg(feature = "smallvec")]
+        return ByteString(Repr::from_vec(bytes));
+        #[cfg(not(feature = "smallvec"))]
+        return ByteString(bytes);
+    }
+}
+
+impl From<&[u8]> for ByteString {
+    fn from(bytes: &[u8]) -> ByteString {
+        ByteString(bytes.into())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for ByteString {
+    fn from(bytes: &[u8; N]) -> ByteString {
+        ByteString::from(&bytes[..])
+    }
+}
+
+impl From<String> for ByteString {
+    fn from(s: String) -> ByteString {
+        ByteString::from(s.into_bytes())
+    }
+}
+
+impl From<&str> for ByteString {
+    fn from(s: &str) -> ByteString {
+        ByteString::from(s.as_bytes())
+    }
+}
+
+impl From<ByteString> for Vec<u8> {
+    fn from(bytes: ByteString) -> Vec<u8> {
+        bytes.into_vec()
+    }
+}
+
+impl PartialEq<[u8]> for ByteString {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl PartialEq<&[u8]> for ByteString {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl<const N: usize> PartialEq<[u8; N]> for ByteString {
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&[u8; N]> for ByteString {
+    fn eq(&self, other: &&[u8; N]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl PartialEq<Vec<u8>> for ByteString {
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl ser::Serialize for ByteString {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for ByteString {
+    // Read straight into a `ByteString` rather than through a `ByteBuf`, so that short byte
+    // strings are not allocated with the `smallvec` feature.
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<ByteString, D::Error> {
+        deserializer.deserialize_byte_buf(ByteStringVisitor)
+    }
+}
+
+struct ByteStringVisitor;
+
+impl<'de> de::Visitor<'de> for ByteStringVisitor {
+    type Value = ByteString;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<ByteString, E> {
+        Ok(value.into())
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<ByteString, E> {
+        Ok(value.into())
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<ByteString, E> {
+        Ok(value.into())
+    }
+
+    fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<ByteString, E> {
+        Ok(value.into())
+    }
+
+    fn visit_seq<V: de::SeqAccess<'de>>(self, mut access: V) -> Result<ByteString, V::Error> {
+        let mut bytes = Vec::new();
+        while let Some(b) = access.next_element()? {
+            bytes.push(b);
+        }
+        Ok(bytes.into())
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(feature = "arbitrary_precision")]
+use super::BigInt;
+use super::{Map, Value};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+/// A total order on values, so that they can be kept in a `BTreeSet` or used as `BTreeMap` keys.
+///
+/// Values of different kinds are ordered integers first, then byte strings, lists and
+/// dictionaries. Integers are ordered by numeric value, byte strings byte by byte, lists element
+/// by element, and dictionaries entry by entry in key order, like the sequence of their sorted
+/// `(key, value)` pairs.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::bencode;
+/// use std::collections::BTreeSet;
+///
+/// let trackers = vec![bencode!("b"), bencode!("a"), bencode!("b")];
+/// let trackers: BTreeSet<_> = trackers.into_iter().collect();
+/// assert_eq!(trackers.into_iter().collect::<Vec<_>>(), vec![bencode!("a"), bencode!("b")]);
+/// assert!(bencode!(10) < bencode!("1"));
+/// assert!(bencode!([1, 2]) < bencode!([1, 3]));
+/// ```
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            #[cfg(feature = "arbitrary_precision")]
+            (Value::Int(a), Value::BigInt(b)) => cmp_big_int(*a, b),
+            #[cfg(feature = "arbitrary_precision")]
+            (Value::BigInt(a), Value::Int(b)) => cmp_big_int(*b, a).reverse(),
+            #[cfg(feature = "arbitrary_precision")]
+            (Value::BigInt(a), Value::BigInt(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            (Value::List(a), Value::List(b)) => a.cmp(b),
+            (Value::Dict(a), Value::Dict(b)) => sorted_entries(a).cmp(sorted_entries(b)),
+            (a, b) => rank(a).cmp(&rank(b)),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hashes values consistently with their equality; dictionaries are hashed in key order.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        rank(self).hash(state);
+        match self {
+            Value::Int(i) => i.hash(state),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigInt(n) => n.hash(state),
+            Value::Bytes(bytes) => bytes.hash(state),
+            Value::List(list) => list.hash(state),
+            Value::Dict(map) => {
+                map.len().hash(state);
+                for entry in sorted_entries(map) {
+                    entry.hash(state);
+                }
+            }
+        }
+    }
+}
+
+/// The position of the kind of a value in the order, with integers of either size together.
+fn rank(value: &Value) -> u8 {
+    match value {
+        Value::Int(_) => 0,
+        #[cfg(feature = "arbitrary_precision")]
+        Value::BigInt(_) => 0,
+        Value::Bytes(_) => 1,
+        Value::List(_) => 2,
+        Value::Dict(_) => 3,
+    }
+}
+
+/// Compare an `i64` with a `BigInt`. A `BigInt` equal to the `i64` is ordered after it, to keep
+/// the order consistent with equality.
+#[cfg(feature = "arbitrary_precision")]
+fn cmp_big_int(a: i64, b: &BigInt) -> Ordering {
+    match b.as_i64() {
+        Some(b) => a.cmp(&b).then(Ordering::Less),
+        None if b.as_str().starts_with('-') => Ordering::Greater,
+        None => Ordering::Less,
+    }
+}
+
+#[cfg(not(feature = "preserve_order"))]
+fn sorted_entries(map: &Map) -> impl Iterator<Item = (&[u8], &Value)> {
+    map.iter().map(|(k, v)| (&k[..], v))
+}
+
+#[cfg(feature = "preserve_order")]
+fn sorted_entries(map: &Map) -> impl Iterator<Item = (&[u8], &Value)> {
+    let mut entries: alloc::vec::Vec<_> = map.iter().map(|(k, v)| (&k[..], v)).collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    entries.into_iter()
+}
@@ -0,0 +1,626 @@
+//! Deserialize Rust data structures from an already decoded [`Value`].
+
+#[cfg(feature = "arbitrary_precision")]
+use super::BigIntAccess;
+use super::{ByteString, Value, RAW_VALUE_TOKEN};
+use crate::de::{parse_key, visit_byte_tuple, visit_identifier};
+use crate::error::{Error, Result};
+use alloc::string::{String, ToString};
+use core::str;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, Error as _, IntoDeserializer, Unexpected};
+use serde::forward_to_deserialize_any;
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Value {
+    type Deserializer = &'de Value;
+
+    fn into_deserializer(self) -> &'de Value {
+        self
+    }
+}
+
+fn visit_seq<'de, I, V>(iter: I, visitor: V) -> Result<V::Value>
+where
+    I: Iterator,
+    I::Item: IntoDeserializer<'de, Error>,
+    V: de::Visitor<'de>,
+{
+    let mut seq = SeqDeserializer::new(iter);
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+fn visit_map<'de, I, K, T, V>(iter: I, visitor: V) -> Result<V::Value>
+where
+    I: Iterator<Item = (K, T)>,
+    K: IntoDeserializer<'de, Error>,
+    T: IntoDeserializer<'de, Error>,
+    V: de::Visitor<'de>,
+{
+    let mut map = MapDeserializer::new(iter);
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+/// The error for floats, which bencode has no representation for unless a
+/// [`FloatPolicy`](crate::de::FloatPolicy) says which one to read.
+fn float_error() -> Error {
+    Error::InvalidType("Cannot deserialize float from a `Value`".to_string())
+}
+
+/// Deserializes a `Value` the same way the [`Deserializer`](crate::Deserializer) deserializes
+/// the bencode it was decoded from with the default [`Options`](crate::de::Options): floats are
+/// rejected and `bool`s are read from the integers 0 and 1. To use other policies, encode the
+/// value and decode the bytes with [`from_bytes_with_options`](crate::de::from_bytes_with_options)
+/// instead.
+///
+/// # Examples
+/// ```
+/// use serde::Deserialize;
+/// use serde_bencode::value::Value;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct File {
+///     length: u64,
+///     path: Vec<String>,
+/// }
+///
+/// let torrent: Value = serde_bencode::from_bytes(b"d4:infod6:lengthi5e4:pathl1:aeee").unwrap();
+/// let file = File::deserialize(&torrent["info"]).unwrap();
+/// assert_eq!(file, File { length: 5, path: vec!["a".to_string()] });
+/// ```
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => match String::from_utf8(bytes.into_vec()) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            Value::Int(i) => visitor.visit_i64(i),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigInt(n) => visitor.visit_map(BigIntAccess::new(n.into_string())),
+            Value::List(list) => visit_seq(list.into_iter(), visitor),
+            Value::Dict(map) => visit_map(map.into_iter().map(|(k, v)| (DictKey(k), v)), visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 unit seq map unit_struct tuple_struct
+        identifier
+    }
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(float_error())
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(float_error())
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, size: usize, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => visit_byte_tuple(&bytes, size, visitor),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Int(0) => visitor.visit_bool(false),
+            Value::Int(1) => visitor.visit_bool(true),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::Dict(map) => visit_map(
+                map.into_iter()
+                    .map(|(k, v)| (Identifier::new(k, fields), v)),
+                visitor,
+            ),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => visitor.visit_byte_buf(bytes.into_vec()),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => match String::from_utf8(bytes.into_vec()) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => Err(Error::invalid_value(
+                    Unexpected::Bytes(e.as_bytes()),
+                    &"utf-8 string",
+                )),
+            },
+            v => Err(v.unexpected("bytes")),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == RAW_VALUE_TOKEN {
+            return visitor.visit_byte_buf(self.to_bytes()?);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::Bytes(name) => visitor.visit_enum(EnumDeserializer {
+                variant: Identifier::new(name, variants),
+                content: None::<Value>,
+            }),
+            Value::Dict(map) if map.len() == 1 => {
+                let (name, content) = map.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant: Identifier::new(name, variants),
+                    content: Some(content),
+                })
+            }
+            v => Err(v.unexpected("bytes or a dictionary with a single entry")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de Value {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => BorrowedBytes(bytes).deserialize_any(visitor),
+            Value::Int(i) => visitor.visit_i64(*i),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigInt(n) => visitor.visit_map(BigIntAccess::new(n.as_str().into())),
+            Value::List(list) => visit_seq(list.iter(), visitor),
+            Value::Dict(map) => visit_map(map.iter().map(|(k, v)| (BorrowedBytes(k), v)), visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 unit seq map unit_struct tuple_struct
+        identifier
+    }
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(float_error())
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(float_error())
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, size: usize, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => visit_byte_tuple(bytes, size, visitor),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            &Value::Int(0) => visitor.visit_bool(false),
+            &Value::Int(1) => visitor.visit_bool(true),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::Dict(map) => visit_map(
+                map.iter()
+                    .map(|(k, v)| (Identifier::new(&k[..], fields), v)),
+                visitor,
+            ),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => visitor.visit_borrowed_bytes(bytes),
+            v => v.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => BorrowedBytes(bytes).deserialize_str(visitor),
+            v => Err(v.unexpected("bytes")),
+        }
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == RAW_VALUE_TOKEN {
+            return visitor.visit_byte_buf(self.to_bytes()?);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::Bytes(name) => visitor.visit_enum(EnumDeserializer {
+                variant: Identifier::new(&name[..], variants),
+                content: None::<&Value>,
+            }),
+            Value::Dict(map) if map.len() == 1 => {
+                let (name, content) = map.iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant: Identifier::new(&name[..], variants),
+                    content: Some(content),
+                })
+            }
+            v => Err(v.unexpected("bytes or a dictionary with a single entry")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+impl Value {
+    fn unexpected(&self, expected: &str) -> Error {
+        let unexpected = match self {
+            Value::Bytes(bytes) => Unexpected::Bytes(bytes),
+            Value::Int(i) => Unexpected::Signed(*i),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigInt(_) => Unexpected::Other("big integer"),
+            Value::List(_) => Unexpected::Seq,
+            Value::Dict(_) => Unexpected::Map,
+        };
+        Error::invalid_type(unexpected, &expected)
+    }
+}
+
+/// A byte string borrowed from a `Value`, used for dictionary keys and variant names.
+struct BorrowedBytes<'de>(&'de [u8]);
+
+impl<'de> IntoDeserializer<'de, Error> for BorrowedBytes<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for BorrowedBytes<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match str::from_utf8(self.0) {
+            Ok(s) => visitor.visit_borrowed_str(s),
+            Err(_) => visitor.visit_borrowed_bytes(self.0),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 unit seq map unit_struct tuple_struct struct identifier ignored_any
+    }
+
+    deserialize_parsed_key!();
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(EnumDeserializer {
+            variant: self,
+            content: None::<&Value>,
+        })
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_borrowed_bytes(self.0)
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, size: usize, visitor: V) -> Result<V::Value> {
+        visit_byte_tuple(self.0, size, visitor)
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let s = str::from_utf8(self.0)
+            .map_err(|_| Error::invalid_value(Unexpected::Bytes(self.0), &"utf-8 string"))?;
+        visitor.visit_borrowed_str(s)
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == RAW_VALUE_TOKEN {
+            return Value::Bytes(self.0.into()).deserialize_newtype_struct(name, visitor);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+}
+
+impl BorrowedBytes<'_> {
+    fn deserialize_parsed<'de, V, T>(
+        self,
+        visitor: V,
+        visit: fn(V, T) -> Result<V::Value>,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+        T: str::FromStr,
+    {
+        let parsed = parse_key(self.0, &visitor)?;
+        visit(visitor, parsed)
+    }
+}
+
+/// A dictionary key taken out of a `Value`, which integer map keys are parsed from.
+struct DictKey(ByteString);
+
+impl<'de> IntoDeserializer<'de, Error> for DictKey {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl DictKey {
+    fn deserialize_parsed<'de, V, T>(
+        self,
+        visitor: V,
+        visit: fn(V, T) -> Result<V::Value>,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+        T: str::FromStr,
+    {
+        let parsed = parse_key(&self.0, &visitor)?;
+        visit(visitor, parsed)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for DictKey {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        Value::Bytes(self.0).deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 unit seq map unit_struct tuple_struct struct identifier ignored_any
+    }
+
+    deserialize_parsed_key!();
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        Value::Bytes(self.0).deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        Value::Bytes(self.0).deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, size: usize, visitor: V) -> Result<V::Value> {
+        Value::Bytes(self.0).deserialize_tuple(size, visitor)
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        Value::Bytes(self.0).deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        Value::Bytes(self.0).deserialize_string(visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == RAW_VALUE_TOKEN {
+            return Value::Bytes(self.0).deserialize_newtype_struct(name, visitor);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        Value::Bytes(self.0).deserialize_enum(name, variants, visitor)
+    }
+}
+
+/// A struct field or enum variant name, matched against the expected names like the
+/// [`Deserializer`](crate::Deserializer) does.
+struct Identifier<K> {
+    key: K,
+    names: &'static [&'static str],
+}
+
+impl<K> Identifier<K> {
+    fn new(key: K, names: &'static [&'static str]) -> Identifier<K> {
+        Identifier { key, names }
+    }
+}
+
+impl<'de, K: AsRef<[u8]>> IntoDeserializer<'de, Error> for Identifier<K> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de, K: AsRef<[u8]>> de::Deserializer<'de> for Identifier<K> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visit_identifier(self.key.as_ref(), self.names, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool char i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+/// Access to an enum variant: a byte string naming a unit variant, or the single entry of a
+/// dictionary mapping the variant name to its content.
+struct EnumDeserializer<K, V> {
+    variant: K,
+    content: Option<V>,
+}
+
+impl<'de, K, V> de::EnumAccess<'de> for EnumDeserializer<K, V>
+where
+    K: de::Deserializer<'de, Error = Error>,
+    V: de::Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+    type Variant = VariantDeserializer<V>;
+
+    fn variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<(T::Value, VariantDeserializer<V>)> {
+        let variant = seed.deserialize(self.variant)?;
+        Ok((variant, VariantDeserializer(self.content)))
+    }
+}
+
+struct VariantDeserializer<V>(Option<V>);
+
+impl<V> VariantDeserializer<V> {
+    fn content(self, expected: &str) -> Result<V> {
+        self.0
+            .ok_or_else(|| Error::invalid_type(Unexpected::UnitVariant, &expected))
+    }
+}
+
+impl<'de, V> de::VariantAccess<'de> for VariantDeserializer<V>
+where
+    V: de::Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.0 {
+            None => Ok(()),
+            Some(_) => Err(Error::invalid_type(
+                Unexpected::Map,
+                &"unit variant as a byte string",
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self.content("newtype variant")?)
+    }
+
+    fn tuple_variant<T: de::Visitor<'de>>(self, _len: usize, visitor: T) -> Result<T::Value> {
+        self.content("tuple variant")?.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<T: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: T,
+    ) -> Result<T::Value> {
+        self.content("struct variant")?
+            .deserialize_struct("", fields, visitor)
+    }
+}
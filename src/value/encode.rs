@@ -0,0 +1,119 @@
+use super::Value;
+use crate::error::{Error, Result};
+use crate::ser::Options;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::slice;
+
+/// What is left to encode of an open list or dictionary.
+enum Frame<'a> {
+    List(slice::Iter<'a, Value>),
+    Dict(alloc::vec::IntoIter<(&'a [u8], &'a Value)>),
+}
+
+impl Value {
+    /// Encode this value with an explicit stack instead of recursing, so that values of any
+    /// depth can be encoded without exhausting the thread's stack.
+    ///
+    /// The output is the same as that of [`to_bytes`](crate::to_bytes), including its
+    /// [depth limit](crate::ser::Options::max_depth).
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::ser::Options;
+    /// use serde_bencode::value::Value;
+    ///
+    /// let mut value = Value::Int(1);
+    /// for _ in 0..10_000 {
+    ///     value = Value::List(vec![value]);
+    /// }
+    /// assert!(value.to_bytes().is_err());
+    /// let encoded = value.to_bytes_with_options(Options::new().max_depth(10_000)).unwrap();
+    /// assert_eq!(encoded.len(), 2 * 10_000 + 3);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value is nested deeper than the default limit.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        self.to_bytes_with_options(Options::default())
+    }
+
+    /// Encode this value without recursing like [`Value::to_bytes`], using the given options.
+    ///
+    /// Of the options, only the [key order](crate::ser::Options::sort_keys) and the
+    /// [depth limit](crate::ser::Options::max_depth) apply to a `Value`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value is nested deeper than `options` allow.
+    pub fn to_bytes_with_options(&self, options: Options) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut stack: Vec<Frame<'_>> = Vec::new();
+        let mut next = Some(self);
+        loop {
+            if let Some(value) = next.take() {
+                match value {
+                    Value::Bytes(bytes) => write_bytes(&mut out, bytes),
+                    Value::Int(i) => {
+                        out.push(b'i');
+                        out.extend_from_slice(i.to_string().as_bytes());
+                        out.push(b'e');
+                    }
+                    #[cfg(feature = "arbitrary_precision")]
+                    Value::BigInt(i) => {
+                        out.push(b'i');
+                        out.extend_from_slice(i.as_str().as_bytes());
+                        out.push(b'e');
+                    }
+                    Value::List(list) => {
+                        check_depth(stack.len(), &options)?;
+                        out.push(b'l');
+                        stack.push(Frame::List(list.iter()));
+                    }
+                    Value::Dict(dict) => {
+                        check_depth(stack.len(), &options)?;
+                        let mut entries: Vec<_> = dict.iter().map(|(k, v)| (&k[..], v)).collect();
+                        if options.sort_keys && cfg!(feature = "preserve_order") {
+                            entries.sort_by(|a, b| a.0.cmp(b.0));
+                        }
+                        out.push(b'd');
+                        stack.push(Frame::Dict(entries.into_iter()));
+                    }
+                }
+            }
+            let frame = match stack.last_mut() {
+                Some(frame) => frame,
+                None => return Ok(out),
+            };
+            next = match frame {
+                Frame::List(items) => items.next(),
+                Frame::Dict(entries) => entries.next().map(|(key, value)| {
+                    write_bytes(&mut out, key);
+                    value
+                }),
+            };
+            if next.is_none() {
+                out.push(b'e');
+                stack.pop();
+            }
+        }
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(bytes.len().to_string().as_bytes());
+    out.push(b':');
+    out.extend_from_slice(bytes);
+}
+
+fn check_depth(depth: usize, options: &Options) -> Result<()> {
+    match options.max_depth {
+        Some(max) if depth >= max => Err(Error::LimitExceeded(format!(
+            "Nesting is deeper than the maximum of {}",
+            max
+        ))),
+        _ => Ok(()),
+    }
+}
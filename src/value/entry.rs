@@ -0,0 +1,69 @@
+use super::{ByteString, Value};
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "preserve_order"))]
+type MapEntry<'a> = alloc::collections::btree_map::Entry<'a, ByteString, Value>;
+#[cfg(feature = "preserve_order")]
+type MapEntry<'a> = indexmap::map::Entry<'a, ByteString, Value>;
+
+/// An entry of a [`Value::Dict`], which may or may not exist. Returned by [`Value::entry`] and
+/// [`Map::entry`](super::Map::entry).
+#[derive(Debug)]
+pub struct Entry<'a> {
+    entry: MapEntry<'a>,
+}
+
+impl<'a> Entry<'a> {
+    pub(super) fn new(entry: MapEntry<'a>) -> Entry<'a> {
+        Entry { entry }
+    }
+
+    /// The key of the entry.
+    pub fn key(&self) -> &[u8] {
+        self.entry.key()
+    }
+
+    /// Insert `default` if the entry does not exist, and return a reference to the value.
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        self.entry.or_insert(default)
+    }
+
+    /// Insert the result of `default` if the entry does not exist, and return a reference to the
+    /// value.
+    pub fn or_insert_with<F: FnOnce() -> Value>(self, default: F) -> &'a mut Value {
+        self.entry.or_insert_with(default)
+    }
+
+    /// Modify the value if the entry exists.
+    pub fn and_modify<F: FnOnce(&mut Value)>(self, f: F) -> Entry<'a> {
+        Entry {
+            entry: self.entry.and_modify(f),
+        }
+    }
+}
+
+impl Value {
+    /// Get the entry of a dictionary for `key`, for in-place manipulation. Returns `None` if the
+    /// value is not a dictionary.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::bencode;
+    ///
+    /// let mut torrent = bencode!({ "announce-list": [["a"]] });
+    /// torrent
+    ///     .entry("announce-list")
+    ///     .unwrap()
+    ///     .and_modify(|list| list[0] = bencode!(["b"]))
+    ///     .or_insert(bencode!([]));
+    /// *torrent.entry("created by").unwrap().or_insert(bencode!("")) = bencode!("me");
+    /// assert_eq!(torrent, bencode!({ "announce-list": [["b"]], "created by": "me" }));
+    /// assert!(bencode!(1).entry("a").is_none());
+    /// ```
+    pub fn entry<K: Into<Vec<u8>>>(&mut self, key: K) -> Option<Entry<'_>> {
+        match self {
+            Value::Dict(map) => Some(map.entry(key.into())),
+            _ => None,
+        }
+    }
+}
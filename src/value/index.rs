@@ -0,0 +1,145 @@
+use super::Value;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A type that can index into a [`Value`]: `usize` for lists, and strings or byte strings for
+/// dictionaries.
+///
+/// This trait is sealed. It is used by [`Value::get`], [`Value::get_mut`] and the `Index` and
+/// `IndexMut` implementations on `Value`.
+pub trait Index: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value>;
+
+    #[doc(hidden)]
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value>;
+}
+
+impl private::Sealed for usize {}
+
+impl Index for usize {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match v {
+            Value::List(list) => list.get(*self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match v {
+            Value::List(list) => list.get_mut(*self),
+            _ => None,
+        }
+    }
+}
+
+impl private::Sealed for [u8] {}
+
+impl Index for [u8] {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match v {
+            Value::Dict(map) => map.get(self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match v {
+            Value::Dict(map) => map.get_mut(self),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_index_as_bytes {
+    ($($ty:ty)*) => {
+        $(
+            impl private::Sealed for $ty {}
+
+            impl Index for $ty {
+                fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+                    AsRef::<[u8]>::as_ref(self).index_into(v)
+                }
+
+                fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+                    AsRef::<[u8]>::as_ref(self).index_into_mut(v)
+                }
+            }
+        )*
+    };
+}
+
+impl_index_as_bytes!(str String Vec<u8>);
+
+impl<const N: usize> private::Sealed for [u8; N] {}
+
+impl<const N: usize> Index for [u8; N] {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self[..].index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self[..].index_into_mut(v)
+    }
+}
+
+impl<T: Index + ?Sized> private::Sealed for &T {}
+
+impl<T: Index + ?Sized> Index for &T {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).index_into_mut(v)
+    }
+}
+
+impl<I: Index> ops::Index<I> for Value {
+    type Output = Value;
+
+    /// Index into a list or dictionary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a container of the right kind, or if the index or key is not
+    /// present. Use [`Value::get`] for a non-panicking alternative.
+    fn index(&self, index: I) -> &Value {
+        match index.index_into(self) {
+            Some(v) => v,
+            None => panic!("no entry found for index in {}", kind(self)),
+        }
+    }
+}
+
+impl<I: Index> ops::IndexMut<I> for Value {
+    /// Mutably index into a list or dictionary. Missing entries are not inserted.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as the `Index` implementation. Use [`Value::get_mut`]
+    /// for a non-panicking alternative.
+    fn index_mut(&mut self, index: I) -> &mut Value {
+        let kind = kind(self);
+        match index.index_into_mut(self) {
+            Some(v) => v,
+            None => panic!("no entry found for index in {}", kind),
+        }
+    }
+}
+
+fn kind(v: &Value) -> &'static str {
+    match v {
+        Value::Bytes(_) => "byte string",
+        Value::Int(_) => "integer",
+        #[cfg(feature = "arbitrary_precision")]
+        Value::BigInt(_) => "integer",
+        Value::List(_) => "list",
+        Value::Dict(_) => "dictionary",
+    }
+}
@@ -0,0 +1,123 @@
+use super::{Map, Value};
+use crate::error::{Error, Result};
+use crate::helpers::base64::Base64;
+use crate::helpers::hex::Hex;
+use crate::helpers::text::Codec;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// How [`Value::to_json`] and [`Value::from_json`] represent byte strings, which JSON does not
+/// have.
+///
+/// Dictionary keys are converted the same way as other byte strings, so that binary keys, such
+/// as the hashes keying the `piece layers` of a v2 torrent, survive the conversion with
+/// [`Hex`](JsonBytes::Hex) and [`Base64`](JsonBytes::Base64).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonBytes {
+    /// Byte strings that are valid UTF-8 become JSON strings as they are. Invalid sequences in
+    /// other byte strings are replaced with U+FFFD, so binary data such as `pieces` does not
+    /// survive the conversion, and keys that only differ in invalid sequences collide, keeping
+    /// the last entry. JSON strings are converted back as UTF-8.
+    Utf8Lossy,
+    /// Byte strings are hex encoded, in lowercase.
+    Hex,
+    /// Byte strings are base64 encoded with the standard alphabet and padding.
+    Base64,
+}
+
+impl JsonBytes {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            JsonBytes::Utf8Lossy => String::from_utf8_lossy(bytes).into_owned(),
+            JsonBytes::Hex => Hex::encode(bytes),
+            JsonBytes::Base64 => Base64::encode(bytes),
+        }
+    }
+
+    fn decode(self, text: &str) -> Result<Vec<u8>> {
+        let bytes = match self {
+            JsonBytes::Utf8Lossy => Some(text.as_bytes().to_vec()),
+            JsonBytes::Hex => Hex::decode(text.as_bytes()),
+            JsonBytes::Base64 => Base64::decode(text.as_bytes()),
+        };
+        bytes.ok_or_else(|| Error::InvalidValue(format!("Invalid {:?} string `{}`", self, text)))
+    }
+}
+
+impl Value {
+    /// Convert to a JSON value, representing byte strings as selected by `bytes`.
+    ///
+    /// Integers that do not fit in an `i64` become JSON strings of their decimal digits.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::bencode;
+    /// use serde_bencode::value::JsonBytes;
+    ///
+    /// let torrent = bencode!({ "info": { "name": "a", "pieces": b"\x01\xff" } });
+    /// assert_eq!(
+    ///     torrent.to_json(JsonBytes::Utf8Lossy),
+    ///     serde_json::json!({ "info": { "name": "a", "pieces": "\u{1}\u{fffd}" } }),
+    /// );
+    /// assert_eq!(
+    ///     torrent.to_json(JsonBytes::Hex),
+    ///     serde_json::json!({ "696e666f": { "6e616d65": "61", "706965636573": "01ff" } }),
+    /// );
+    /// ```
+    pub fn to_json(&self, bytes: JsonBytes) -> serde_json::Value {
+        match self {
+            Value::Bytes(b) => serde_json::Value::String(bytes.encode(b)),
+            Value::Int(i) => serde_json::Value::from(*i),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigInt(i) => serde_json::Value::String(i.as_str().to_string()),
+            Value::List(list) => list.iter().map(|v| v.to_json(bytes)).collect(),
+            Value::Dict(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(k, v)| (bytes.encode(k), v.to_json(bytes)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Convert from a JSON value, decoding strings as selected by `bytes`.
+    ///
+    /// Booleans become the integers 0 and 1. Numbers must be integers; ones that do not fit in
+    /// an `i64` are only accepted with the `arbitrary_precision` feature.
+    ///
+    /// # Errors
+    ///
+    /// Fails on `null`, on numbers with a fraction or out of range, and on strings and object
+    /// keys that are not valid in the selected encoding.
+    pub fn from_json(json: &serde_json::Value, bytes: JsonBytes) -> Result<Value> {
+        Ok(match json {
+            serde_json::Value::Null => {
+                return Err(Error::InvalidType("Cannot convert JSON null".to_string()))
+            }
+            serde_json::Value::Bool(b) => Value::Int(i64::from(*b)),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Value::Int(i),
+                #[cfg(feature = "arbitrary_precision")]
+                None if n.is_u64() => Value::BigInt(n.as_u64().unwrap().into()),
+                None => {
+                    return Err(Error::InvalidValue(format!(
+                        "Cannot convert JSON number `{}` to an integer",
+                        n
+                    )))
+                }
+            },
+            serde_json::Value::String(s) => Value::Bytes(bytes.decode(s)?.into()),
+            serde_json::Value::Array(list) => Value::List(
+                list.iter()
+                    .map(|v| Value::from_json(v, bytes))
+                    .collect::<Result<_>>()?,
+            ),
+            serde_json::Value::Object(object) => Value::Dict(
+                object
+                    .iter()
+                    .map(|(k, v)| Ok((bytes.decode(k)?, Value::from_json(v, bytes)?)))
+                    .collect::<Result<Map>>()?,
+            ),
+        })
+    }
+}
@@ -0,0 +1,291 @@
+use super::Value;
+use crate::error::{Error, Result};
+use crate::token::{Token, Tokenizer};
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::str;
+use serde::de;
+
+/// A bencode value that is only parsed as far as it is accessed.
+///
+/// [`LazyValue::from_slice`] checks the whole input, but only the outermost list or dictionary
+/// is split into its items. Nested containers are kept as spans of the input and are split when
+/// they are reached through [`LazyList::get`], [`LazyDict::get`] or one of the iterators, and
+/// byte strings borrow from the input. Extracting a few fields from a large document therefore
+/// allocates nothing for the parts that are not looked at, such as the `pieces` of a metainfo
+/// file or a long file list.
+///
+/// Integers must fit in an `i64`. A value can be decoded completely with [`LazyValue::decode`]
+/// or [`LazyValue::to_value`].
+///
+/// # Examples
+/// ```
+/// use serde_bencode::value::LazyValue;
+///
+/// let input = b"d8:announce3:url4:infod5:filesld6:lengthi1eee4:name4:spam6:pieces0:ee";
+/// let torrent = LazyValue::from_slice(input).unwrap();
+/// let torrent = torrent.as_dict().unwrap();
+/// assert_eq!(torrent.get(b"announce").unwrap().as_str(), Some("url"));
+///
+/// let info = torrent.get(b"info").unwrap();
+/// let name = info.as_dict().and_then(|info| info.get(b"name"));
+/// assert_eq!(name.unwrap().as_str(), Some("spam"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LazyValue<'a> {
+    /// A byte string, borrowed from the input.
+    Bytes(&'a [u8]),
+
+    /// An integer.
+    Int(i64),
+
+    /// A list whose items have not been parsed yet.
+    List(LazyList<'a>),
+
+    /// A dictionary whose values have not been parsed yet.
+    Dict(LazyDict<'a>),
+}
+
+/// The items of a [`LazyValue::List`], each kept as the span of its encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazyList<'a> {
+    raw: &'a [u8],
+    items: Vec<&'a [u8]>,
+}
+
+/// The entries of a [`LazyValue::Dict`] in input order, each value kept as the span of its
+/// encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazyDict<'a> {
+    raw: &'a [u8],
+    entries: Vec<(&'a [u8], &'a [u8])>,
+}
+
+impl<'a> LazyValue<'a> {
+    /// Check that `input` is exactly one well-formed bencode value, and split its outermost
+    /// container.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the input is malformed or incomplete, if a dictionary key is not a byte string,
+    /// if an integer does not fit in an `i64`, or if there is input after the value.
+    pub fn from_slice(input: &'a [u8]) -> Result<LazyValue<'a>> {
+        check(input)?;
+        Ok(LazyValue::split(input))
+    }
+
+    /// Parse the outermost level of a span that has already been checked.
+    fn split(raw: &'a [u8]) -> LazyValue<'a> {
+        let mut tokens = Tokenizer::new(raw);
+        match tokens.next() {
+            Some(Ok((_, Token::Int(i)))) => LazyValue::Int(i),
+            Some(Ok((_, Token::ListStart))) => {
+                let mut items = Vec::new();
+                while let Ok(Some(item)) = next_item(&mut tokens) {
+                    items.push(item);
+                }
+                LazyValue::List(LazyList { raw, items })
+            }
+            Some(Ok((_, Token::DictStart))) => {
+                let mut entries = Vec::new();
+                while let Some(Ok((_, Token::Bytes(key)))) = tokens.next() {
+                    match next_item(&mut tokens) {
+                        Ok(Some(value)) => entries.push((key, value)),
+                        _ => break,
+                    }
+                }
+                LazyValue::Dict(LazyDict { raw, entries })
+            }
+            Some(Ok((_, Token::Bytes(b)))) => LazyValue::Bytes(b),
+            _ => LazyValue::Bytes(&[]),
+        }
+    }
+
+    /// The byte string, if this value is one.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match *self {
+            LazyValue::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// The byte string as UTF-8 text, if this value is a byte string holding valid UTF-8.
+    pub fn as_str(&self) -> Option<&'a str> {
+        self.as_bytes().and_then(|b| str::from_utf8(b).ok())
+    }
+
+    /// The integer, if this value is one.
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            LazyValue::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// The list, if this value is one.
+    pub fn as_list(&self) -> Option<&LazyList<'a>> {
+        match self {
+            LazyValue::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// The dictionary, if this value is one.
+    pub fn as_dict(&self) -> Option<&LazyDict<'a>> {
+        match self {
+            LazyValue::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Deserialize the whole value as `T`.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`from_bytes`](crate::from_bytes).
+    pub fn decode<T: de::DeserializeOwned>(&self) -> Result<T> {
+        match self {
+            LazyValue::List(list) => crate::from_bytes(list.raw),
+            LazyValue::Dict(dict) => crate::from_bytes(dict.raw),
+            LazyValue::Bytes(_) | LazyValue::Int(_) => T::deserialize(self.to_value()?),
+        }
+    }
+
+    /// Parse the whole value into a [`Value`].
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`from_bytes`](crate::from_bytes).
+    pub fn to_value(&self) -> Result<Value> {
+        match *self {
+            LazyValue::Bytes(b) => Ok(Value::Bytes(b.into())),
+            LazyValue::Int(i) => Ok(Value::Int(i)),
+            LazyValue::List(_) | LazyValue::Dict(_) => self.decode(),
+        }
+    }
+}
+
+impl<'a> LazyList<'a> {
+    /// The encoding of the list, borrowed from the input.
+    pub fn as_raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// The number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Parse the item at `index`, or return `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<LazyValue<'a>> {
+        self.items.get(index).map(|&raw| LazyValue::split(raw))
+    }
+
+    /// Iterate over the items, parsing each one as it is reached.
+    pub fn iter(&self) -> impl Iterator<Item = LazyValue<'a>> + '_ {
+        self.items.iter().map(|&raw| LazyValue::split(raw))
+    }
+}
+
+impl<'a> LazyDict<'a> {
+    /// The encoding of the dictionary, borrowed from the input.
+    pub fn as_raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the dictionary is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Parse the value of `key`, or return `None` if there is no such key. If the key appears
+    /// more than once, the last entry wins, like when decoding into a map.
+    pub fn get(&self, key: &[u8]) -> Option<LazyValue<'a>> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|&(_, raw)| LazyValue::split(raw))
+    }
+
+    /// The keys in input order.
+    pub fn keys(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.entries.iter().map(|&(key, _)| key)
+    }
+
+    /// Iterate over the entries in input order, parsing each value as it is reached.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a [u8], LazyValue<'a>)> + '_ {
+        self.entries
+            .iter()
+            .map(|&(key, raw)| (key, LazyValue::split(raw)))
+    }
+}
+
+/// The encoding of the next item of the current container, or `None` at its end.
+fn next_item<'a>(tokens: &mut Tokenizer<'a>) -> Result<Option<&'a [u8]>> {
+    if tokens.peek() == Some(b'e') {
+        return Ok(None);
+    }
+    tokens.skip_value()
+}
+
+#[derive(PartialEq)]
+enum Frame {
+    List,
+    Key,
+    Value,
+}
+
+/// Check that `input` is exactly one value, with byte strings for all dictionary keys.
+fn check(input: &[u8]) -> Result<()> {
+    let mut tokens = Tokenizer::new(input);
+    let mut stack = Vec::new();
+    loop {
+        let token = match tokens.next() {
+            Some(token) => token?.1,
+            None => return Err(Error::EndOfStream),
+        };
+        if token == Token::End {
+            if stack.pop() == Some(Frame::Value) {
+                return Err(Error::Syntax("Dictionary key without a value".to_string()));
+            }
+        } else {
+            match stack.last_mut() {
+                Some(frame @ Frame::Key) => {
+                    if !matches!(token, Token::Bytes(_)) {
+                        return Err(Error::Syntax(format!(
+                            "Dictionary key must be a byte string; got `{:?}`",
+                            token
+                        )));
+                    }
+                    *frame = Frame::Value;
+                }
+                Some(frame @ Frame::Value) => *frame = Frame::Key,
+                _ => {}
+            }
+            match token {
+                Token::ListStart => stack.push(Frame::List),
+                Token::DictStart => stack.push(Frame::Key),
+                _ => {}
+            }
+        }
+        if stack.is_empty() {
+            break;
+        }
+    }
+    if tokens.offset() < input.len() {
+        return Err(Error::TrailingData);
+    }
+    Ok(())
+}
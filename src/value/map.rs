@@ -0,0 +1,362 @@
+//! The map type of [`Value::Dict`] and its iterators.
+
+use super::{ByteString, Entry, Value};
+use core::fmt;
+use core::iter::{FromIterator, FusedIterator};
+use core::ops;
+use serde::de;
+use serde::ser::{self, SerializeMap};
+use serde_bytes::Bytes;
+
+#[cfg(not(feature = "preserve_order"))]
+type MapImpl = alloc::collections::BTreeMap<ByteString, Value>;
+#[cfg(feature = "preserve_order")]
+type MapImpl = indexmap::IndexMap<ByteString, Value>;
+
+#[cfg(not(feature = "preserve_order"))]
+use alloc::collections::btree_map as map_impl;
+#[cfg(feature = "preserve_order")]
+use indexmap::map as map_impl;
+
+/// The map of a [`Value::Dict`], from byte string keys to values.
+///
+/// By default it is backed by a `BTreeMap` and iterates in key order. With the `preserve_order`
+/// feature it is backed by an [`IndexMap`](indexmap::IndexMap) and keeps entries in the order
+/// they were inserted or decoded; serializing such a map with
+/// [`sort_keys(false)`](crate::ser::Options::sort_keys) reproduces unsorted input byte for byte.
+/// The API is the same either way.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::value::{Map, Value};
+///
+/// let mut map = Map::new();
+/// map.insert("name".into(), Value::from("a.iso"));
+/// map.insert(b"length".to_vec().into(), Value::from(1024));
+/// assert_eq!(map.get("name"), Some(&Value::from("a.iso")));
+/// assert_eq!(map[b"length"], Value::from(1024));
+/// assert_eq!(map.remove("name"), Some(Value::from("a.iso")));
+/// assert_eq!(map.len(), 1);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Map {
+    map: MapImpl,
+}
+
+impl Map {
+    /// Create an empty map.
+    pub fn new() -> Map {
+        Map {
+            map: MapImpl::new(),
+        }
+    }
+
+    /// Create an empty map with room for `capacity` entries. Without the `preserve_order`
+    /// feature the capacity is ignored.
+    pub fn with_capacity(capacity: usize) -> Map {
+        #[cfg(feature = "preserve_order")]
+        return Map {
+            map: MapImpl::with_capacity(capacity),
+        };
+        #[cfg(not(feature = "preserve_order"))]
+        {
+            let _ = capacity;
+            Map::new()
+        }
+    }
+
+    /// Remove all entries.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// A reference to the value for `key`.
+    pub fn get<K: ?Sized + AsRef<[u8]>>(&self, key: &K) -> Option<&Value> {
+        self.map.get(key.as_ref())
+    }
+
+    /// A mutable reference to the value for `key`.
+    pub fn get_mut<K: ?Sized + AsRef<[u8]>>(&mut self, key: &K) -> Option<&mut Value> {
+        self.map.get_mut(key.as_ref())
+    }
+
+    /// The stored key and the value for `key`.
+    pub fn get_key_value<K: ?Sized + AsRef<[u8]>>(&self, key: &K) -> Option<(&ByteString, &Value)> {
+        self.map.get_key_value(key.as_ref())
+    }
+
+    /// Whether the map has an entry for `key`.
+    pub fn contains_key<K: ?Sized + AsRef<[u8]>>(&self, key: &K) -> bool {
+        self.map.contains_key(key.as_ref())
+    }
+
+    /// Insert a value, returning the one previously stored for `key`. An existing entry keeps
+    /// its position with the `preserve_order` feature.
+    pub fn insert(&mut self, key: ByteString, value: Value) -> Option<Value> {
+        self.map.insert(key, value)
+    }
+
+    /// Remove the entry for `key` and return its value. With the `preserve_order` feature the
+    /// entries after it are shifted down to keep their order, which takes linear time.
+    pub fn remove<K: ?Sized + AsRef<[u8]>>(&mut self, key: &K) -> Option<Value> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Remove the entry for `key` and return it, like [`remove`](Map::remove).
+    pub fn remove_entry<K: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        key: &K,
+    ) -> Option<(ByteString, Value)> {
+        #[cfg(feature = "preserve_order")]
+        return self.map.shift_remove_entry(key.as_ref());
+        #[cfg(not(feature = "preserve_order"))]
+        return self.map.remove_entry(key.as_ref());
+    }
+
+    /// Keep only the entries for which `f` returns `true`.
+    pub fn retain<F: FnMut(&ByteString, &mut Value) -> bool>(&mut self, f: F) {
+        self.map.retain(f);
+    }
+
+    /// Get the entry for `key`, for in-place manipulation.
+    pub fn entry<K: Into<ByteString>>(&mut self, key: K) -> Entry<'_> {
+        Entry::new(self.map.entry(key.into()))
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// An iterator over the entries.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            iter: self.map.iter(),
+        }
+    }
+
+    /// An iterator over the entries, with mutable references to the values.
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            iter: self.map.iter_mut(),
+        }
+    }
+
+    /// An iterator over the keys.
+    pub fn keys(&self) -> Keys<'_> {
+        Keys {
+            iter: self.map.keys(),
+        }
+    }
+
+    /// An iterator over the values.
+    pub fn values(&self) -> Values<'_> {
+        Values {
+            iter: self.map.values(),
+        }
+    }
+
+    /// An iterator over mutable references to the values.
+    pub fn values_mut(&mut self) -> ValuesMut<'_> {
+        ValuesMut {
+            iter: self.map.values_mut(),
+        }
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<K: ?Sized + AsRef<[u8]>> ops::Index<&K> for Map {
+    type Output = Value;
+
+    /// The value for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map has no entry for `key`.
+    fn index(&self, key: &K) -> &Value {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<K: ?Sized + AsRef<[u8]>> ops::IndexMut<&K> for Map {
+    /// A mutable reference to the value for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map has no entry for `key`.
+    fn index_mut(&mut self, key: &K) -> &mut Value {
+        self.get_mut(key).expect("no entry found for key")
+    }
+}
+
+impl<K: Into<ByteString>> FromIterator<(K, Value)> for Map {
+    fn from_iter<T: IntoIterator<Item = (K, Value)>>(iter: T) -> Map {
+        Map {
+            map: iter.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+        }
+    }
+}
+
+impl<K: Into<ByteString>> Extend<(K, Value)> for Map {
+    fn extend<T: IntoIterator<Item = (K, Value)>>(&mut self, iter: T) {
+        self.map
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v)));
+    }
+}
+
+impl<K: Into<ByteString>, const N: usize> From<[(K, Value); N]> for Map {
+    fn from(entries: [(K, Value); N]) -> Map {
+        IntoIterator::into_iter(entries).collect()
+    }
+}
+
+impl ser::Serialize for Map {
+    fn serialize<S: ser::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut map = s.serialize_map(Some(self.len()))?;
+        for (k, v) in self {
+            map.serialize_entry(&Bytes::new(k), v)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Map {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Map, D::Error> {
+        deserializer.deserialize_map(MapVisitor)
+    }
+}
+
+struct MapVisitor;
+
+impl<'de> de::Visitor<'de> for MapVisitor {
+    type Value = Map;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a dictionary")
+    }
+
+    fn visit_map<V: de::MapAccess<'de>>(self, mut access: V) -> Result<Map, V::Error> {
+        let mut map = Map::new();
+        while let Some(k) = access.next_key::<ByteString>()? {
+            map.insert(k, access.next_value()?);
+        }
+        Ok(map)
+    }
+}
+
+macro_rules! delegate_iterator {
+    ($name:ident $(<$lt:lifetime>)?, $item:ty) => {
+        impl$(<$lt>)? Iterator for $name$(<$lt>)? {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<$item> {
+                self.iter.next()
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.iter.size_hint()
+            }
+        }
+
+        impl$(<$lt>)? DoubleEndedIterator for $name$(<$lt>)? {
+            fn next_back(&mut self) -> Option<$item> {
+                self.iter.next_back()
+            }
+        }
+
+        impl$(<$lt>)? ExactSizeIterator for $name$(<$lt>)? {
+            fn len(&self) -> usize {
+                self.iter.len()
+            }
+        }
+
+        impl$(<$lt>)? FusedIterator for $name$(<$lt>)? {}
+    };
+}
+
+/// An iterator over the entries of a [`Map`], returned by [`Map::iter`].
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    iter: map_impl::Iter<'a, ByteString, Value>,
+}
+
+delegate_iterator!(Iter<'a>, (&'a ByteString, &'a Value));
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a ByteString, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// A mutable iterator over the entries of a [`Map`], returned by [`Map::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'a> {
+    iter: map_impl::IterMut<'a, ByteString, Value>,
+}
+
+delegate_iterator!(IterMut<'a>, (&'a ByteString, &'a mut Value));
+
+impl<'a> IntoIterator for &'a mut Map {
+    type Item = (&'a ByteString, &'a mut Value);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+/// An owning iterator over the entries of a [`Map`].
+#[derive(Debug)]
+pub struct IntoIter {
+    iter: map_impl::IntoIter<ByteString, Value>,
+}
+
+delegate_iterator!(IntoIter, (ByteString, Value));
+
+impl IntoIterator for Map {
+    type Item = (ByteString, Value);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            iter: self.map.into_iter(),
+        }
+    }
+}
+
+/// An iterator over the keys of a [`Map`], returned by [`Map::keys`].
+#[derive(Clone, Debug)]
+pub struct Keys<'a> {
+    iter: map_impl::Keys<'a, ByteString, Value>,
+}
+
+delegate_iterator!(Keys<'a>, &'a ByteString);
+
+/// An iterator over the values of a [`Map`], returned by [`Map::values`].
+#[derive(Clone, Debug)]
+pub struct Values<'a> {
+    iter: map_impl::Values<'a, ByteString, Value>,
+}
+
+delegate_iterator!(Values<'a>, &'a Value);
+
+/// A mutable iterator over the values of a [`Map`], returned by [`Map::values_mut`].
+#[derive(Debug)]
+pub struct ValuesMut<'a> {
+    iter: map_impl::ValuesMut<'a, ByteString, Value>,
+}
+
+delegate_iterator!(ValuesMut<'a>, &'a mut Value);
@@ -0,0 +1,59 @@
+use super::Value;
+
+/// How [`Value::merge_with`] combines two dictionaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Merge dictionaries recursively. Entries of the other value replace existing entries,
+    /// except that dictionaries on both sides are merged in turn. This is the default of
+    /// [`Value::merge`].
+    Deep,
+    /// Entries of the other value replace existing entries as a whole, including dictionaries.
+    Shallow,
+    /// Merge dictionaries recursively, but keep existing entries and only add the missing ones,
+    /// which fills in defaults.
+    KeepExisting,
+}
+
+impl Value {
+    /// Merge `other` into `self`, recursively merging dictionaries present on both sides. See
+    /// [`MergePolicy::Deep`].
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::bencode;
+    ///
+    /// let mut torrent = bencode!({ "announce": "a", "info": { "name": "x", "private": 0 } });
+    /// torrent.merge(bencode!({ "info": { "private": 1 }, "comment": "c" }));
+    /// assert_eq!(
+    ///     torrent,
+    ///     bencode!({ "announce": "a", "comment": "c", "info": { "name": "x", "private": 1 } }),
+    /// );
+    /// ```
+    pub fn merge(&mut self, other: Value) {
+        self.merge_with(other, MergePolicy::Deep)
+    }
+
+    /// Merge `other` into `self` according to `policy`.
+    ///
+    /// Dictionaries are combined entry by entry. For any other combination of values, `other`
+    /// replaces `self`, unless the policy is [`MergePolicy::KeepExisting`]. Lists are not merged
+    /// element by element.
+    pub fn merge_with(&mut self, other: Value, policy: MergePolicy) {
+        match (self, other) {
+            (Value::Dict(map), Value::Dict(other)) => {
+                for (key, value) in other {
+                    match map.get_mut(&key) {
+                        Some(existing) if policy != MergePolicy::Shallow => {
+                            existing.merge_with(value, policy)
+                        }
+                        _ => {
+                            map.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (_, _) if policy == MergePolicy::KeepExisting => {}
+            (this, other) => *this = other,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use super::Value;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::de;
+use serde::ser::{self, SerializeMap};
+use serde_bytes::ByteBuf;
+
+/// A dictionary decoded entry by entry, keeping the input order and any duplicate keys.
+///
+/// [`Value::Dict`] silently keeps only the last of several entries with the same key, which is
+/// what a consumer wants but hides malformed input from an auditing tool. Decoding into a
+/// `RawDict` reports exactly what was on the wire. Nested dictionaries are decoded as ordinary
+/// [`Value`]s; use `RawDict` as the field type wherever duplicates matter.
+///
+/// Serializing a `RawDict` with duplicate keys fails unless key sorting is disabled with
+/// [`sort_keys(false)`](crate::ser::Options::sort_keys), in which case the entries are written
+/// back in their original order.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::value::{RawDict, Value};
+///
+/// let dict: RawDict = serde_bencode::from_bytes(b"d1:bi1e1:ai2e1:bi3ee").unwrap();
+/// assert_eq!(dict.0.len(), 3);
+/// assert_eq!(dict.get_all(b"b").collect::<Vec<_>>(), [&Value::Int(1), &Value::Int(3)]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawDict(pub Vec<(ByteBuf, Value)>);
+
+impl RawDict {
+    /// Iterate over the values of all entries with the given key, in input order.
+    pub fn get_all<'a, K: AsRef<[u8]> + ?Sized>(
+        &'a self,
+        key: &'a K,
+    ) -> impl Iterator<Item = &'a Value> + 'a {
+        self.0
+            .iter()
+            .filter(move |(k, _)| k.as_slice() == key.as_ref())
+            .map(|(_, v)| v)
+    }
+}
+
+impl ser::Serialize for RawDict {
+    fn serialize<S: ser::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut map = s.serialize_map(Some(self.0.len()))?;
+        for (k, v) in &self.0 {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+struct RawDictVisitor;
+
+impl<'de> de::Visitor<'de> for RawDictVisitor {
+    type Value = RawDict;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a bencode dictionary")
+    }
+
+    fn visit_map<V: de::MapAccess<'de>>(self, mut access: V) -> Result<RawDict, V::Error> {
+        let mut entries = Vec::with_capacity(access.size_hint().unwrap_or(0));
+        while let Some(entry) = access.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(RawDict(entries))
+    }
+}
+
+impl<'de> de::Deserialize<'de> for RawDict {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<RawDict, D::Error> {
+        deserializer.deserialize_map(RawDictVisitor)
+    }
+}
@@ -0,0 +1,134 @@
+use super::Value;
+use crate::error::Error;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use serde::{de, ser};
+use serde_bytes::Bytes;
+
+pub(crate) const TOKEN: &str = "$serde_bencode::private::RawValue";
+
+/// The encoding of a single bencode value, kept exactly as it appeared in the input.
+///
+/// Decoding a field as a `RawValue` validates it but keeps its bytes instead of interpreting
+/// them, and serializing a `RawValue` writes those bytes back unchanged. This is what a hash over
+/// part of a document needs: the `info` dictionary of a metainfo file must be hashed as it was
+/// received, even if it is not canonical or contains keys a typed structure would drop.
+///
+/// The bytes can be decoded further with [`RawValue::decode`]. Formats other than bencode see a
+/// `RawValue` as a byte string holding the encoding, which formats without byte strings, such as
+/// JSON, write as an array of numbers. Reading it back from them, bytes or an array of numbers
+/// that hold a single well-formed encoding are taken as they are, while anything else is taken
+/// as a decoded value and encoded again.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::value::RawValue;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Torrent {
+///     info: RawValue,
+/// }
+///
+/// let torrent: Torrent = serde_bencode::from_bytes(b"d4:infod4:name1:a6:lengthi1eee").unwrap();
+/// assert_eq!(torrent.info.as_bytes(), b"d4:name1:a6:lengthi1ee");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawValue(Vec<u8>);
+
+impl RawValue {
+    /// Wrap the encoding of a single value, checking that it is well-formed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `bytes` is not exactly one well-formed bencode value.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<RawValue, Error> {
+        crate::from_bytes::<de::IgnoredAny>(&bytes)?;
+        Ok(RawValue(bytes))
+    }
+
+    /// Serialize a value and keep its encoding.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`to_bytes`](crate::to_bytes).
+    pub fn from_value<T: ser::Serialize>(value: &T) -> Result<RawValue, Error> {
+        crate::to_bytes(value).map(RawValue)
+    }
+
+    /// The encoded value.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Take the encoded value.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    /// Deserialize the encoded value.
+    ///
+    /// # Errors
+    ///
+    /// Fails for the same reasons as [`from_bytes`](crate::from_bytes).
+    pub fn decode<'a, T: de::Deserialize<'a>>(&'a self) -> Result<T, Error> {
+        crate::from_bytes(&self.0)
+    }
+}
+
+impl AsRef<[u8]> for RawValue {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl ser::Serialize for RawValue {
+    fn serialize<S: ser::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_newtype_struct(TOKEN, Bytes::new(&self.0))
+    }
+}
+
+struct RawValueVisitor;
+
+impl<'de> de::Visitor<'de> for RawValueVisitor {
+    type Value = RawValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any bencode value")
+    }
+
+    /// Called by this crate's deserializers with the raw encoding.
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<RawValue, E> {
+        Ok(RawValue(v))
+    }
+
+    /// Called by any other deserializer.
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<RawValue, D::Error> {
+        let value = <Value as de::Deserialize>::deserialize(deserializer)?;
+        let encoding = match &value {
+            Value::Bytes(bytes) => Some(bytes.to_vec()),
+            Value::List(list) => list
+                .iter()
+                .map(|v| match v {
+                    Value::Int(i) => u8::try_from(*i).ok(),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        };
+        match encoding.and_then(|bytes| RawValue::from_bytes(bytes).ok()) {
+            Some(raw) => Ok(raw),
+            None => RawValue::from_value(&value).map_err(de::Error::custom),
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for RawValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<RawValue, D::Error> {
+        deserializer.deserialize_newtype_struct(TOKEN, RawValueVisitor)
+    }
+}
@@ -0,0 +1,88 @@
+use super::Value;
+use crate::error::{Error, Result};
+use crate::ser;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::str::FromStr;
+
+/// Writes the bencode encoding of the value as text.
+///
+/// Bytes that are not part of a valid UTF-8 sequence are written as `\xNN` with two lowercase
+/// hex digits, and backslashes as `\\`. Length prefixes count the encoded bytes, not the escaped
+/// text, so the output is exactly the bencode encoding whenever it is valid UTF-8 without
+/// backslashes. [`FromStr`] parses the text back.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::bencode;
+/// use serde_bencode::value::Value;
+///
+/// let value = bencode!({ "id": b"\xff\xfe", "path": "a\\b" });
+/// let text = value.to_string();
+/// assert_eq!(text, r"d2:id2:\xff\xfe4:path3:a\\be");
+/// assert_eq!(text.parse::<Value>().unwrap(), value);
+/// ```
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self
+            .to_bytes_with_options(ser::Options::new().max_depth(usize::MAX))
+            .map_err(|_| fmt::Error)?;
+        for chunk in bytes.utf8_chunks() {
+            for part in chunk.valid().split_inclusive('\\') {
+                f.write_str(part)?;
+                if part.ends_with('\\') {
+                    f.write_char('\\')?;
+                }
+            }
+            for b in chunk.invalid() {
+                write!(f, "\\x{:02x}", b)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the text written by the [`Display`](fmt::Display) implementation.
+///
+/// Any bencode that is valid UTF-8 and has no backslashes is accepted as it is. `\xNN` escapes
+/// may use upper or lowercase hex digits.
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Value> {
+        crate::from_bytes(&unescape(s)?)
+    }
+}
+
+fn unescape(s: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b != b'\\' {
+            bytes.push(b);
+            rest = tail;
+            continue;
+        }
+        match tail {
+            [b'\\', tail @ ..] => {
+                bytes.push(b'\\');
+                rest = tail;
+            }
+            [b'x', hi, lo, tail @ ..] if hi.is_ascii_hexdigit() && lo.is_ascii_hexdigit() => {
+                let digits = [*hi, *lo];
+                // Both digits are ASCII.
+                let digits = core::str::from_utf8(&digits).unwrap();
+                bytes.push(u8::from_str_radix(digits, 16).unwrap());
+                rest = tail;
+            }
+            _ => {
+                return Err(Error::InvalidValue(format!(
+                    "Invalid escape sequence at byte {}",
+                    s.len() - rest.len()
+                )))
+            }
+        }
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,146 @@
+//! Building a [`Value`] from a flat stream of tokens, so that deeply nested input does not
+//! recurse.
+//!
+//! [`Value`]'s `Deserialize` impl asks for a newtype struct named [`TOKEN`]. This crate's
+//! deserializer answers with a single-entry map keyed by the token, whose value is a sequence of
+//! [`Token`]s: every scalar, the start of every list and dictionary, and every end. The containers
+//! that are still open are kept on the heap while the sequence is read. Other deserializers call
+//! `visit_newtype_struct` and are read the usual way.
+
+use super::{ByteString, Map, Value, ValueVisitor};
+use alloc::vec::Vec;
+use core::fmt;
+use serde::de;
+
+/// Name used to ask this crate's deserializer for the token stream.
+pub(crate) const TOKEN: &str = "$serde_bencode::private::Value";
+
+/// One token of the stream. The start of a list is visited as an empty sequence, the start of a
+/// dictionary as an empty map and the end of either as a unit.
+enum Token {
+    Scalar(Value),
+    List,
+    Dict,
+    End,
+}
+
+struct TokenVisitor;
+
+impl<'de> de::Visitor<'de> for TokenVisitor {
+    type Value = Token;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a bencode token")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Token, E> {
+        Ok(Token::Scalar(Value::Int(value)))
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<Token, E> {
+        ValueVisitor.visit_i128(value).map(Token::Scalar)
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<Token, E> {
+        ValueVisitor.visit_u128(value).map(Token::Scalar)
+    }
+
+    /// The digits of an integer outside of the 128-bit range. Byte strings never come as text.
+    #[cfg(feature = "arbitrary_precision")]
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Token, E> {
+        value
+            .parse()
+            .map(|n| Token::Scalar(Value::BigInt(n)))
+            .map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Token, E> {
+        Ok(Token::Scalar(Value::Bytes(value.into())))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Token, E> {
+        Ok(Token::Scalar(Value::Bytes(value.into())))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, _: A) -> Result<Token, A::Error> {
+        Ok(Token::List)
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, _: A) -> Result<Token, A::Error> {
+        Ok(Token::Dict)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Token, E> {
+        Ok(Token::End)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Token {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Token, D::Error> {
+        deserializer.deserialize_any(TokenVisitor)
+    }
+}
+
+/// A container that has been started but not finished.
+enum Frame {
+    List(Vec<Value>),
+    /// A dictionary, and the key of the entry whose value is being read.
+    Dict(Map, Option<ByteString>),
+}
+
+/// Reads the token stream into a [`Value`].
+pub(super) struct TreeSeed;
+
+impl<'de> de::DeserializeSeed<'de> for TreeSeed {
+    type Value = Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_seq(TreeVisitor)
+    }
+}
+
+struct TreeVisitor;
+
+impl<'de> de::Visitor<'de> for TreeVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a stream of bencode tokens")
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut tokens: A) -> Result<Value, A::Error> {
+        let mut stack = Vec::new();
+        while let Some(token) = tokens.next_element()? {
+            let value = match token {
+                Token::Scalar(value) => value,
+                Token::List => {
+                    stack.push(Frame::List(Vec::new()));
+                    continue;
+                }
+                Token::Dict => {
+                    stack.push(Frame::Dict(Map::new(), None));
+                    continue;
+                }
+                Token::End => match stack.pop() {
+                    Some(Frame::List(list)) => Value::List(list),
+                    Some(Frame::Dict(map, None)) => Value::Dict(map),
+                    Some(Frame::Dict(_, Some(_))) | None => {
+                        return Err(de::Error::custom("unbalanced token stream"))
+                    }
+                },
+            };
+            match stack.last_mut() {
+                None => return Ok(value),
+                Some(Frame::List(list)) => list.push(value),
+                Some(Frame::Dict(map, key)) => match (key.take(), value) {
+                    (Some(key), value) => {
+                        map.insert(key, value);
+                    }
+                    (None, Value::Bytes(bytes)) => *key = Some(bytes),
+                    (None, _) => return Err(de::Error::custom("dictionary key is not bytes")),
+                },
+            }
+        }
+        Err(de::Error::custom("unbalanced token stream"))
+    }
+}
@@ -1,14 +1,28 @@
 extern crate serde_bencode;
 
-use serde::de::DeserializeOwned;
-use serde::Serialize;
-use serde_bencode::de::{from_bytes, from_str};
-use serde_bencode::error::Result;
-use serde_bencode::ser::{to_bytes, to_string, Serializer};
-use serde_bencode::value::Value;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use serde_bencode::bencode;
+use serde_bencode::de::{
+    canonicalize, canonicalize_with_options, from_bytes, from_bytes_partial,
+    from_bytes_partial_with_options, from_bytes_with_options, from_str, parse_events,
+    parse_events_with_options, validate, validate_read, validate_with_options, Decoder,
+    Deserializer, DuplicateKeyPolicy, EventHandler, IoRead, Kind, Options, Position, PushDecoder,
+    SliceRead, Status, DEFAULT_MAX_DEPTH,
+};
+use serde_bencode::error::{Error, Result};
+use serde_bencode::ser::{
+    serialized_len, serialized_len_with_options, to_bytes, to_bytes_in, to_bytes_in_with_options,
+    to_bytes_with_digest, to_bytes_with_options, to_string, BoolPolicy, Encoder, FloatPolicy,
+    KeyPolicy, NonePolicy, Options as SerOptions, Serializer, UnitPolicy,
+};
+use serde_bencode::token::{Token, Tokenizer};
+use serde_bencode::value::{LazyValue, Map, MergePolicy, RawDict, RawValue, Value};
+use serde_bytes::ByteBuf;
 use serde_derive::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
+use std::io;
 
 fn test_value_ser_de<T: Into<Value>>(a: T) {
     let a = a.into();
@@ -125,6 +139,41 @@ fn deserialize_to_string() {
     assert_eq!(r, "yes");
 }
 
+#[test]
+fn deserialize_string_hands_over_buffer() {
+    /// A string, and whether its buffer was handed over rather than copied.
+    struct Owned(String, bool);
+
+    impl<'de> Deserialize<'de> for Owned {
+        fn deserialize<D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Owned, D::Error> {
+            struct OwnedVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for OwnedVisitor {
+                type Value = Owned;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("a string")
+                }
+
+                fn visit_str<E>(self, s: &str) -> std::result::Result<Owned, E> {
+                    Ok(Owned(s.to_string(), false))
+                }
+
+                fn visit_string<E>(self, s: String) -> std::result::Result<Owned, E> {
+                    Ok(Owned(s, true))
+                }
+            }
+
+            d.deserialize_string(OwnedVisitor)
+        }
+    }
+
+    let r: Owned = from_str("24:longer than inline bytes").unwrap();
+    assert_eq!(r.0, "longer than inline bytes");
+    assert!(r.1);
+    assert!(from_bytes::<String>(b"3:\xff\xfe\xfd").is_err());
+}
+
 #[test]
 fn deserialize_to_i64() {
     let r: i64 = from_str("i666e").unwrap();
@@ -144,12 +193,27 @@ fn deserialize_to_freestyle() {
 }
 
 #[test]
-#[ignore]
-#[should_panic(expected = "assertion failed")]
 fn trailing_chars() {
     let s = "i666ed";
     let r: Result<Value> = from_str(s);
-    assert!(r.is_err());
+    assert!(matches!(r, Err(Error::TrailingData)));
+}
+
+#[test]
+fn partial_returns_remainder() {
+    let (r, rest): (Value, _) = from_bytes_partial(b"li1e1:aed1:xi2ee\xff").unwrap();
+    assert_eq!(r, Value::List(vec![1.into(), "a".into()]));
+    assert_eq!(rest, b"d1:xi2ee\xff");
+    let (r, rest): (i64, _) = from_bytes_partial(b"i5e").unwrap();
+    assert_eq!((r, rest), (5, &b""[..]));
+    assert!(from_bytes_partial::<Value>(b"li1e").is_err());
+
+    let options = Options::new().max_depth(1);
+    let (r, rest): (Vec<i64>, _) =
+        from_bytes_partial_with_options(b"li1eeli2ee", options.clone()).unwrap();
+    assert_eq!((r, rest), (vec![1], &b"li2ee"[..]));
+    let r = from_bytes_partial_with_options::<Value>(b"llee", options);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
 }
 
 #[test]
@@ -227,11 +291,43 @@ fn deserialize_to_struct_with_option() {
     );
 }
 
+#[test]
+fn deserialize_option_semantics() {
+    #[derive(PartialEq, Debug, Deserialize)]
+    struct Fake {
+        a: Option<i64>,
+        b: Option<Option<String>>,
+        c: i64,
+        d: Option<Vec<Option<i64>>>,
+    }
+    let r: Fake = from_str("d1:b1:x1:ci2e1:dli1ei2eee").unwrap();
+    assert_eq!(
+        r,
+        Fake {
+            a: None,
+            b: Some(Some("x".to_string())),
+            c: 2,
+            d: Some(vec![Some(1), Some(2)]),
+        }
+    );
+    let r: Fake = from_str("d1:ai0e1:ci3ee").unwrap();
+    assert_eq!((r.a, r.b, r.d), (Some(0), None, None));
+    // Malformed values fail instead of turning into `None`.
+    assert!(from_str::<Fake>("d1:bi1e1:ci2ee").is_err());
+    assert!(from_str::<Fake>("d1:a1:x1:ci3ee").is_err());
+    assert!(from_str::<Fake>("d1:a1:xe").is_err());
+    assert!(from_str::<Fake>("d1:ci3e1:dli1e1:xee").is_err());
+    assert_eq!(from_str::<Option<i64>>("i5e").unwrap(), Some(5));
+    assert!(from_str::<Option<i64>>("").is_err());
+    let r = Vec::<Option<String>>::deserialize(bencode!(["a", "b"])).unwrap();
+    assert_eq!(r, [Some("a".to_string()), Some("b".to_string())]);
+}
+
 #[test]
 fn deserialize_to_value() {
     let b = "d1:xi1111e1:y3:doge";
     let r: Value = from_str(b).unwrap();
-    let mut d = HashMap::new();
+    let mut d = Map::new();
     d.insert("x".into(), 1111.into());
     d.insert("y".into(), "dog".into());
     assert_eq!(r, Value::Dict(d));
@@ -343,6 +439,30 @@ fn ser_de_variant_unit() {
     test_ser_de_eq(("pre".to_string(), Mock::B, "post".to_string()));
 }
 
+#[test]
+fn ser_de_unit_variant_keys() {
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, PartialOrd, Ord)]
+    #[serde(rename_all = "lowercase")]
+    enum Event {
+        Started,
+        Stopped,
+    }
+    let map = BTreeMap::from([(Event::Stopped, 2), (Event::Started, 1)]);
+    let bytes = to_bytes(&map).unwrap();
+    assert_eq!(bytes, b"d7:startedi1e7:stoppedi2ee");
+    assert_eq!(from_bytes::<BTreeMap<Event, i64>>(&bytes).unwrap(), map);
+    let value: Value = from_bytes(&bytes).unwrap();
+    assert_eq!(BTreeMap::<Event, i64>::deserialize(value).unwrap(), map);
+    assert!(matches!(
+        from_bytes::<BTreeMap<Event, i64>>(b"d9:completedi1ee"),
+        Err(Error::UnknownVariant(_))
+    ));
+
+    let options = SerOptions::new().keys(KeyPolicy::Error);
+    let r = to_bytes_with_options(&map, options);
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+}
+
 #[test]
 fn ser_de_variant_newtype() {
     #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
@@ -444,3 +564,2967 @@ fn ser_de_field_vec_tuple() {
 
     test_ser_de_eq(foo);
 }
+
+#[test]
+fn strict_rejects_non_canonical_integers() {
+    let strict = Options::new().strict(true);
+    for s in ["i03e", "i-0e", "ie", "i-e", "i+3e", "i-03e"] {
+        let r: Result<Value> = from_bytes_with_options(s.as_bytes(), strict.clone());
+        assert!(matches!(r, Err(Error::NonCanonical(_))), "{}: {:?}", s, r);
+    }
+    for (s, i) in [("i0e", 0), ("i-3e", -3), ("i30e", 30)] {
+        let r: i64 = from_bytes_with_options(s.as_bytes(), strict.clone()).unwrap();
+        assert_eq!(r, i);
+    }
+    // The default mode stays lenient.
+    assert_eq!(from_str::<i64>("i03e").unwrap(), 3);
+}
+
+#[test]
+fn strict_rejects_non_canonical_string_lengths() {
+    let strict = Options::new().strict(true);
+    let r: Result<String> = from_bytes_with_options(b"03:abc", strict.clone());
+    assert!(matches!(r, Err(Error::NonCanonical(_))));
+    let r: String = from_bytes_with_options(b"0:", strict).unwrap();
+    assert_eq!(r, "");
+}
+
+#[test]
+fn strict_rejects_unsorted_or_duplicate_keys() {
+    let strict = Options::new().strict(true);
+    for s in ["d1:bi1e1:ai2ee", "d1:ai1e1:ai2ee", "di1e1:ae"] {
+        let r: Result<Value> = from_bytes_with_options(s.as_bytes(), strict.clone());
+        assert!(matches!(r, Err(Error::NonCanonical(_))), "{}: {:?}", s, r);
+    }
+    let r: Result<Value> = from_bytes_with_options(b"d1:ad1:di1e1:ci2eee", strict.clone());
+    assert!(matches!(r, Err(Error::NonCanonical(_))));
+
+    let r: Value = from_bytes_with_options(b"d1:ai1e2:aai2e1:bi3ee", strict).unwrap();
+    test_value_de_ser(&to_string(&r).unwrap());
+    assert!(from_str::<Value>("d1:bi1e1:ai2ee").is_ok());
+}
+
+#[test]
+fn limit_max_depth() {
+    let options = Options::new().max_depth(2);
+    let r: Value = from_bytes_with_options(b"lli1eee", options.clone()).unwrap();
+    assert_eq!(r, Value::List(vec![Value::List(vec![Value::Int(1)])]));
+    let r: Result<Value> = from_bytes_with_options(b"llleee", options.clone());
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    let r: Result<Value> = from_bytes_with_options(b"ld1:ald1:ai1eeeee", options);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+}
+
+#[test]
+fn limit_max_bytes_len() {
+    let options = Options::new().max_bytes_len(3);
+    let r: String = from_bytes_with_options(b"3:abc", options.clone()).unwrap();
+    assert_eq!(r, "abc");
+    let r: Result<String> = from_bytes_with_options(b"4:abcd", options.clone());
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    let r: Result<Value> = from_bytes_with_options(b"99999999999:", options);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+}
+
+#[test]
+fn limit_max_container_len() {
+    let options = Options::new().max_container_len(2);
+    let r: Vec<i64> = from_bytes_with_options(b"li1ei2ee", options.clone()).unwrap();
+    assert_eq!(r, [1, 2]);
+    let r: Result<Vec<i64>> = from_bytes_with_options(b"li1ei2ei3ee", options.clone());
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    let r: Result<Value> = from_bytes_with_options(b"d1:ai1e1:bi2e1:ci3ee", options);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+}
+
+#[test]
+fn limit_max_input_len() {
+    let options = Options::new().max_input_len(8);
+    let r: Vec<i64> = from_bytes_with_options(b"li1ei2ee", options.clone()).unwrap();
+    assert_eq!(r, [1, 2]);
+    let r: Result<Vec<i64>> = from_bytes_with_options(b"li1ei2ei3ee", options.clone());
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    let r: Result<Value> = from_bytes_with_options(b"100:", options);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+}
+
+#[test]
+fn default_max_depth_guards_the_stack() {
+    let deep = |n: usize| "l".repeat(n) + &"e".repeat(n);
+    let r: Result<Value> = from_str(&deep(DEFAULT_MAX_DEPTH));
+    assert!(r.is_ok());
+    let r: Result<Value> = from_str(&deep(100_000));
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+}
+
+#[test]
+fn values_are_built_iteratively() {
+    let n = 100_000;
+    let s = format!("{}i1e{}", "ld1:a".repeat(n), "ee".repeat(n));
+    let options = Options::new().max_depth(2 * n);
+    let mut v: Value = from_bytes_with_options(s.as_bytes(), options.clone()).unwrap();
+    // Take the value apart by hand, since dropping it would recurse.
+    let mut depth = 0;
+    loop {
+        v = match v {
+            Value::List(mut list) => list.pop().unwrap(),
+            Value::Dict(mut dict) => dict.remove("a").unwrap(),
+            v => break assert_eq!(v, Value::Int(1)),
+        };
+        depth += 1;
+    }
+    assert_eq!(depth, 2 * n);
+
+    let r: Result<Value> = from_bytes_with_options(s.as_bytes(), options.max_depth(2 * n - 1));
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+}
+
+#[test]
+fn encoder_writes_values_back_to_back() {
+    let mut encoder = Encoder::new(io::BufWriter::new(Vec::new()));
+    encoder.encode(&bencode!({ "t": "aa", "y": "q" })).unwrap();
+    encoder.encode("pong").unwrap();
+    assert!(encoder.encode(&1.5).is_err());
+    encoder.encode(&vec![1, 2]).unwrap();
+    assert!(encoder.get_ref().get_ref().is_empty());
+    encoder.flush().unwrap();
+    let out = encoder.into_inner().into_inner().unwrap();
+    assert_eq!(out, b"d1:t2:aa1:y1:qe4:pongli1ei2ee");
+
+    let de = Deserializer::from_slice(&out);
+    let r: Vec<Value> = de.into_iter().collect::<Result<_>>().unwrap();
+    assert_eq!(r.len(), 3);
+
+    let options = SerOptions::new().float(FloatPolicy::String);
+    let mut encoder = Encoder::with_options(Vec::new(), options);
+    encoder.encode(&1.5).unwrap();
+    assert_eq!(encoder.into_inner(), b"3:1.5");
+}
+
+#[test]
+fn serialize_max_depth() {
+    let deep = |n: usize| (0..n).fold(Value::Int(1), |v, _| Value::List(vec![v]));
+    let options = SerOptions::new().max_depth(2);
+    let r = to_bytes_with_options(&deep(2), options.clone()).unwrap();
+    assert_eq!(r, b"lli1eee");
+    let r = to_bytes_with_options(&deep(3), options.clone());
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    let r = to_bytes_with_options(&bencode!({ "a": [{ "b": 1 }] }), options.clone());
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    assert_eq!(
+        deep(2).to_bytes_with_options(options.clone()).unwrap(),
+        b"lli1eee"
+    );
+    let r = deep(3).to_bytes_with_options(options);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+
+    let value = bencode!({ "b": [1, "x", { "c": [] }], "a": {} });
+    assert_eq!(value.to_bytes().unwrap(), to_bytes(&value).unwrap());
+    let value = deep(DEFAULT_MAX_DEPTH + 1);
+    assert!(matches!(to_bytes(&value), Err(Error::LimitExceeded(_))));
+    assert!(matches!(value.to_bytes(), Err(Error::LimitExceeded(_))));
+    let r = value.to_bytes_with_options(SerOptions::new().max_depth(usize::MAX));
+    assert_eq!(r.unwrap().len(), 2 * (DEFAULT_MAX_DEPTH + 1) + 3);
+}
+
+#[test]
+fn ignored_values_are_skipped_iteratively() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Fake {
+        a: i64,
+    }
+    let n = 100_000;
+    let s = format!("d1:ai1e1:b{}{}e", "l".repeat(n), "e".repeat(n));
+    let r: Fake = from_bytes_with_options(s.as_bytes(), Options::new().max_depth(n + 1)).unwrap();
+    assert_eq!(r, Fake { a: 1 });
+
+    // Limits still apply to skipped values.
+    let r: Result<Fake> = from_str(&s);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    let strict = Options::new().strict(true);
+    let r: Result<Fake> = from_bytes_with_options(b"d1:ai1e1:bd1:yi1e1:xi1eee", strict);
+    assert!(matches!(r, Err(Error::NonCanonical(_))));
+}
+
+/// A reader that hands out one byte per call and is interrupted before every byte.
+struct TrickleReader<'a> {
+    data: &'a [u8],
+    interrupt: bool,
+}
+
+impl<'a> io::Read for TrickleReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        if self.data.is_empty() || buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.data[0];
+        self.data = &self.data[1..];
+        Ok(1)
+    }
+}
+
+#[test]
+fn short_and_interrupted_reads() {
+    let reader = TrickleReader {
+        data: b"d3:foo10:0123456789e",
+        interrupt: false,
+    };
+    let r = Value::deserialize(&mut Deserializer::new(reader)).unwrap();
+    let mut d = Map::new();
+    d.insert("foo".into(), "0123456789".into());
+    assert_eq!(r, Value::Dict(d));
+
+    let reader = TrickleReader {
+        data: b"5:abc",
+        interrupt: false,
+    };
+    let r = Value::deserialize(&mut Deserializer::new(reader));
+    assert!(matches!(r, Err(Error::EndOfStream)));
+}
+
+#[test]
+fn io_errors_are_propagated() {
+    struct FailingReader;
+    impl io::Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::ConnectionReset.into())
+        }
+    }
+    let r = Value::deserialize(&mut Deserializer::new(io::Read::chain(
+        &b"l3:"[..],
+        FailingReader,
+    )));
+    match r {
+        Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::ConnectionReset),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn declared_length_beyond_input() {
+    let input = format!("l{}:abce", 1u64 << 60);
+    assert!(matches!(
+        from_bytes::<Value>(input.as_bytes()),
+        Err(Error::EndOfStream)
+    ));
+    assert!(matches!(
+        from_bytes::<IgnoredAny>(input.as_bytes()),
+        Err(Error::EndOfStream)
+    ));
+    // Readers are checked as the bytes arrive instead of allocating the declared length.
+    let r = Value::deserialize(&mut Deserializer::new(input.as_bytes()));
+    assert!(matches!(r, Err(Error::EndOfStream)));
+
+    // Byte strings longer than the first chunk still decode from readers.
+    let long = vec![b'x'; 300_000];
+    let input = to_bytes(&ByteBuf::from(long.clone())).unwrap();
+    let r = ByteBuf::deserialize(&mut Deserializer::new(&input[..])).unwrap();
+    assert_eq!(r.into_vec(), long);
+}
+
+#[test]
+fn reusable_decoder() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Msg {
+        t: String,
+        a: Vec<i64>,
+    }
+
+    let mut decoder = Decoder::with_options(Options::new().max_depth(2));
+    let messages: [&[u8]; 5] = [
+        b"d1:ali1ei2ee1:t2:aae",
+        b"d1:ali1ee1:t",
+        b"d1:alli1eee1:t2:bbe",
+        b"d1:ale1:t2:cce ",
+        b"d1:ale1:t2:dde",
+    ];
+    let decoded: Vec<_> = messages.iter().map(|m| decoder.decode::<Msg>(m)).collect();
+    assert_eq!(
+        decoded[0].as_ref().unwrap(),
+        &Msg {
+            t: "aa".to_string(),
+            a: vec![1, 2]
+        }
+    );
+    assert!(matches!(decoded[1], Err(Error::EndOfStream)));
+    assert!(matches!(decoded[2], Err(Error::LimitExceeded(_))));
+    assert!(matches!(decoded[3], Err(Error::TrailingData)));
+    assert_eq!(decoded[4].as_ref().unwrap().t, "dd");
+
+    let scratch = decoder.into_scratch();
+    assert!(scratch.capacity() > 0);
+    let mut decoder = Decoder::new().with_scratch(scratch);
+    assert_eq!(decoder.decode::<String>(b"3:abc").unwrap(), "abc");
+}
+
+#[test]
+fn push_decoder() {
+    let input: &[u8] = b"d1:ad1:bi12ee1:t4:spame4:next";
+    let mut decoder = PushDecoder::new();
+    let mut taken = 0;
+    for chunk in input.chunks(1) {
+        match decoder.feed(chunk).unwrap() {
+            Status::NeedMore => taken += 1,
+            Status::Complete(n) => {
+                taken += n;
+                break;
+            }
+        }
+    }
+    assert_eq!(taken, 23);
+    assert!(decoder.is_complete());
+    assert_eq!(decoder.feed(b"x").unwrap(), Status::Complete(0));
+    assert_eq!(decoder.buffered(), &input[..23]);
+    assert_eq!(
+        decoder.decode::<Value>().unwrap(),
+        bencode!({ "a": { "b": 12 }, "t": "spam" })
+    );
+    assert!(!decoder.is_complete());
+    assert!(matches!(decoder.decode::<Value>(), Err(Error::EndOfStream)));
+
+    // A byte string split inside its length prefix and its contents.
+    assert_eq!(decoder.feed(b"1").unwrap(), Status::NeedMore);
+    assert_eq!(decoder.feed(b"0:01234").unwrap(), Status::NeedMore);
+    assert_eq!(decoder.feed(b"56789i1e").unwrap(), Status::Complete(5));
+    assert_eq!(decoder.decode::<String>().unwrap(), "0123456789");
+
+    // Type errors are only found by `decode`, which still resets the decoder.
+    assert_eq!(decoder.feed(b"i1e").unwrap(), Status::Complete(3));
+    assert!(decoder.decode::<String>().is_err());
+    assert_eq!(decoder.feed(b"le").unwrap(), Status::Complete(2));
+    assert_eq!(decoder.decode::<Vec<i64>>().unwrap(), Vec::<i64>::new());
+
+    assert!(matches!(decoder.feed(b"x"), Err(Error::Syntax(_))));
+    decoder.reset();
+    let mut decoder = PushDecoder::with_options(Options::new().max_depth(1).max_input_len(8));
+    assert!(matches!(decoder.feed(b"ll"), Err(Error::LimitExceeded(_))));
+    decoder.reset();
+    assert_eq!(decoder.feed(b"l1:a").unwrap(), Status::NeedMore);
+    assert!(matches!(
+        decoder.feed(b"1:b1:c"),
+        Err(Error::LimitExceeded(_))
+    ));
+}
+
+#[test]
+fn stream_of_values() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Msg {
+        t: String,
+    }
+    let input = b"d1:t2:aaed1:t2:bbe";
+    let mut stream = Deserializer::new(&input[..]).into_iter::<Msg>();
+    assert_eq!(stream.next().unwrap().unwrap().t, "aa");
+    assert_eq!(stream.byte_offset(), 9);
+    assert_eq!(stream.next().unwrap().unwrap().t, "bb");
+    assert_eq!(stream.byte_offset(), input.len());
+    assert!(stream.next().is_none());
+
+    let reader = TrickleReader {
+        data: b"i1ei2ei3",
+        interrupt: false,
+    };
+    let r: Vec<Result<i64>> = Deserializer::new(reader).into_iter().collect();
+    assert_eq!(r.len(), 3);
+    assert_eq!(*r[0].as_ref().unwrap(), 1);
+    assert_eq!(*r[1].as_ref().unwrap(), 2);
+    assert!(matches!(r[2], Err(Error::EndOfStream)));
+}
+
+#[test]
+fn bencode_macro() {
+    assert_eq!(bencode!(-3), Value::Int(-3));
+    assert_eq!(bencode!("spam"), Value::Bytes(b"spam".to_vec().into()));
+    assert_eq!(bencode!(b"\xff"), Value::Bytes(vec![0xff].into()));
+    assert_eq!(bencode!([]), Value::List(vec![]));
+    assert_eq!(bencode!({}), Value::Dict(Map::new()));
+
+    let peers = vec![bencode!({ "ip": "10.0.0.1", "port": 6881 })];
+    let key = String::from("interval");
+    let response = bencode!({
+        (key): 60 * 30,
+        "peers": (Value::List(peers)),
+        b"nested": [[1, 2], { "a": [] }, "x".repeat(2),],
+    });
+    assert_eq!(
+        to_string(&response).unwrap(),
+        "d8:intervali1800e6:nestedlli1ei2eed1:alee2:xxe5:peersld2:ip8:10.0.0.14:porti6881eeee"
+    );
+}
+
+#[test]
+fn value_pointer() {
+    let mut v = bencode!({
+        "info": { "files": [{ "length": 42, "path": ["a", "b"] }] },
+        "a/b": { "~": 1 },
+        b"\xff": 2,
+    });
+    assert_eq!(v.pointer(""), Some(&v));
+    assert_eq!(v.pointer("/info/files/0/length"), Some(&Value::Int(42)));
+    assert_eq!(v.pointer("/info/files/0/path/1"), Some(&bencode!("b")));
+    assert_eq!(v.pointer("/a~1b/~0"), Some(&Value::Int(1)));
+    assert_eq!(v.pointer(b"/\xff"), Some(&Value::Int(2)));
+    assert_eq!(v.pointer("/info/files/1"), None);
+    assert_eq!(v.pointer("/info/files/00"), None);
+    assert_eq!(v.pointer("/info/files/0/length/0"), None);
+    assert_eq!(v.pointer("/a~2b"), None);
+    assert_eq!(v.pointer("info"), None);
+
+    *v.pointer_mut("/info/files/0/length").unwrap() = 43.into();
+    assert_eq!(v.pointer("/info/files/0/length"), Some(&Value::Int(43)));
+}
+
+#[test]
+fn value_index() {
+    let mut v = bencode!({ "info": { "name": "a.iso", "files": [1, 2] }, b"\xff": 3 });
+    assert_eq!(v["info"]["name"], bencode!("a.iso"));
+    assert_eq!(v["info"][&b"files"[..]][1], Value::Int(2));
+    assert_eq!(v[b"\xff"], Value::Int(3));
+    assert_eq!(
+        v[String::from("info")].get("name"),
+        Some(&bencode!("a.iso"))
+    );
+    assert_eq!(v.get("info").and_then(|i| i.get(7)), None);
+    assert_eq!(v.get("missing"), None);
+    assert_eq!(v["info"]["name"].get(0), None);
+
+    v["info"]["files"][0] = 5.into();
+    *v.get_mut("info").unwrap().get_mut("name").unwrap() = "b.iso".into();
+    assert_eq!(v["info"], bencode!({ "name": "b.iso", "files": [5, 2] }));
+}
+
+#[test]
+fn value_merge_and_entry() {
+    let template = bencode!({
+        "announce": "a",
+        "info": { "name": "x", "private": 0, "files": [1, 2] },
+    });
+    let overrides = bencode!({ "info": { "private": 1, "files": [3] }, "comment": "c" });
+
+    let mut deep = template.clone();
+    deep.merge(overrides.clone());
+    assert_eq!(
+        deep,
+        bencode!({
+            "announce": "a",
+            "comment": "c",
+            "info": { "name": "x", "private": 1, "files": [3] },
+        })
+    );
+    let mut shallow = template.clone();
+    shallow.merge_with(overrides.clone(), MergePolicy::Shallow);
+    assert_eq!(
+        shallow,
+        bencode!({ "announce": "a", "comment": "c", "info": { "private": 1, "files": [3] } })
+    );
+    let mut keep = template.clone();
+    keep.merge_with(overrides, MergePolicy::KeepExisting);
+    assert_eq!(
+        keep,
+        bencode!({
+            "announce": "a",
+            "comment": "c",
+            "info": { "name": "x", "private": 0, "files": [1, 2] },
+        })
+    );
+    let mut nested = bencode!({ "info": { "name": "x", "tracker": { "url": "a", "tier": 0 } } });
+    let before = nested.clone();
+    nested.merge_with(
+        bencode!({ "info": { "name": "y", "tracker": { "url": "b", "tier": 1 } } }),
+        MergePolicy::KeepExisting,
+    );
+    assert_eq!(nested, before);
+    nested.merge_with(
+        bencode!({ "info": { "tracker": 5 } }),
+        MergePolicy::KeepExisting,
+    );
+    assert_eq!(nested, before);
+    let mut int = bencode!(1);
+    int.merge(bencode!({ "a": 1 }));
+    assert_eq!(int, bencode!({ "a": 1 }));
+    int.merge_with(bencode!(2), MergePolicy::KeepExisting);
+    assert_eq!(int, bencode!({ "a": 1 }));
+
+    let mut dict = bencode!({ "count": 1 });
+    for _ in 0..2 {
+        dict.entry("count")
+            .unwrap()
+            .and_modify(|count| {
+                if let Value::Int(n) = count {
+                    *n += 1;
+                }
+            })
+            .or_insert(Value::Int(0));
+    }
+    let entry = dict.entry(b"new".to_vec()).unwrap();
+    assert_eq!(entry.key(), b"new");
+    entry.or_insert_with(|| bencode!([]));
+    assert_eq!(dict, bencode!({ "count": 3, "new": [] }));
+    assert!(bencode!([]).entry("a").is_none());
+}
+
+#[test]
+fn value_text_round_trip() {
+    let value = bencode!({
+        "info": { "pieces": b"\x00\xc3\xff", "name": "é\\" },
+        "list": [-1, ""],
+    });
+    let text = value.to_string();
+    assert_eq!(
+        text,
+        "d4:infod4:name3:é\\\\6:pieces3:\u{0}\\xc3\\xffe4:listli-1e0:ee"
+    );
+    assert_eq!(text.parse::<Value>().unwrap(), value);
+    assert_eq!(
+        r"2:\xFF\\".parse::<Value>().unwrap(),
+        Value::Bytes(b"\xff\\".to_vec().into())
+    );
+    assert_eq!("li1ee".parse::<Value>().unwrap(), bencode!([1]));
+
+    assert!(matches!(
+        r"1:\n".parse::<Value>(),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(r"1:\x".parse::<Value>().is_err());
+    assert!(r"1:\".parse::<Value>().is_err());
+    assert!("li1e".parse::<Value>().is_err());
+}
+
+#[test]
+fn value_ord_and_hash() {
+    use std::collections::{BTreeSet, HashSet};
+
+    let sorted = [
+        bencode!(-5),
+        bencode!(3),
+        bencode!(""),
+        bencode!("a"),
+        bencode!(b"\xff"),
+        bencode!([]),
+        bencode!([1]),
+        bencode!([1, 2]),
+        bencode!([2]),
+        bencode!({}),
+        bencode!({ "a": 2 }),
+        bencode!({ "a": 2, "b": 1 }),
+        bencode!({ "b": 1 }),
+    ];
+    for (i, a) in sorted.iter().enumerate() {
+        for (j, b) in sorted.iter().enumerate() {
+            assert_eq!(a.cmp(b), i.cmp(&j), "{} <=> {}", a, b);
+        }
+    }
+
+    let announce_list = vec![bencode!(["a", "b"]), bencode!(["c"]), bencode!(["a", "b"])];
+    let unique: HashSet<_> = announce_list.iter().cloned().collect();
+    assert_eq!(unique.len(), 2);
+    let unique: BTreeSet<_> = announce_list.into_iter().collect();
+    assert_eq!(unique.len(), 2);
+    let mut dicts = HashSet::new();
+    dicts.insert(bencode!({ "a": 1, "b": [2] }));
+    assert!(dicts.contains(&bencode!({ "b": [2], "a": 1 })));
+    assert!(!dicts.contains(&bencode!({ "a": 1 })));
+
+    #[cfg(feature = "arbitrary_precision")]
+    {
+        use serde_bencode::value::BigInt;
+
+        let big = |s: &str| Value::BigInt(s.parse::<BigInt>().unwrap());
+        let sorted = [
+            big("-100000000000000000000"),
+            big("-99999999999999999999"),
+            bencode!(0),
+            big("0"),
+            big("1"),
+            bencode!(2),
+            big("99999999999999999999"),
+            bencode!(""),
+        ];
+        for (i, a) in sorted.iter().enumerate() {
+            for (j, b) in sorted.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{} <=> {}", a, b);
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "no entry found for index in dictionary")]
+fn value_index_missing_key_panics() {
+    let v = bencode!({ "a": 1 });
+    let _ = &v["b"];
+}
+
+#[test]
+#[should_panic(expected = "no entry found for index in integer")]
+fn value_index_mut_wrong_kind_panics() {
+    let mut v = bencode!(1);
+    v[0] = 2.into();
+}
+
+#[test]
+fn deserialize_from_value() {
+    use serde::de::IntoDeserializer;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Kind {
+        Unit,
+        Newtype(i64),
+        Struct { a: String },
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Info<'a> {
+        name: &'a str,
+        #[serde(with = "serde_bytes")]
+        pieces: &'a [u8],
+        kinds: Vec<Kind>,
+        private: Option<u8>,
+    }
+
+    let v = bencode!({
+        "name": "a.iso",
+        "pieces": b"\x00\xff",
+        "kinds": ["Unit", { "Newtype": 3 }, { "Struct": { "a": "x" } }],
+        "private": 1,
+        "extra": [1, 2],
+    });
+    let info = Info::deserialize(&v).unwrap();
+    assert_eq!(info.name, "a.iso");
+    assert_eq!(info.pieces, b"\x00\xff");
+    assert_eq!(
+        info.kinds,
+        vec![
+            Kind::Unit,
+            Kind::Newtype(3),
+            Kind::Struct { a: "x".to_string() }
+        ]
+    );
+    assert_eq!(info.private, Some(1));
+
+    let r: (String, i64) =
+        Deserialize::deserialize(bencode!(["a", 1]).into_deserializer()).unwrap();
+    assert_eq!(r, ("a".to_string(), 1));
+    assert!(Kind::deserialize(bencode!({ "Unit": 1 })).is_err());
+    assert!(Kind::deserialize(&bencode!("Newtype")).is_err());
+    assert!(<(i64,)>::deserialize(bencode!([1, 2])).is_err());
+    assert!(String::deserialize(bencode!(b"\xff")).is_err());
+
+    // The policies are those of the default options: no floats, and bools from 0 and 1 only.
+    assert!(f64::deserialize(&bencode!(1)).is_err());
+    assert!(f32::deserialize(bencode!(1)).is_err());
+    assert!(from_bytes::<f64>(b"i1e").is_err());
+    assert!(bool::deserialize(&bencode!(1)).unwrap());
+    assert!(bool::deserialize(bencode!(2)).is_err());
+    assert!(from_bytes::<bool>(b"i2e").is_err());
+}
+
+#[test]
+fn deserialize_with_value_helper() {
+    fn length_of<'de, D: serde::Deserializer<'de>>(d: D) -> std::result::Result<usize, D::Error> {
+        let v = Value::deserialize(d)?;
+        match v {
+            Value::List(list) => Ok(list.len()),
+            v => ByteBuf::deserialize(v)
+                .map(|b| b.len())
+                .map_err(serde::de::Error::custom),
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Fake {
+        #[serde(deserialize_with = "length_of")]
+        a: usize,
+        #[serde(deserialize_with = "length_of")]
+        b: usize,
+    }
+    let r: Fake = from_str("d1:ali1ei2ee1:b3:xyze").unwrap();
+    assert_eq!((r.a, r.b), (2, 3));
+}
+
+#[test]
+fn value_map_api() {
+    let mut map: Map = vec![("b", bencode!(1)), ("a", bencode!(2))]
+        .into_iter()
+        .collect();
+    map.insert("c".into(), bencode!(3));
+    assert_eq!(map.len(), 3);
+    assert_eq!(map["a"], bencode!(2));
+    assert!(map.contains_key(b"b"));
+    map[b"a".as_ref()] = bencode!(4);
+    *map.entry("d").or_insert(bencode!(0)) = bencode!(5);
+    assert_eq!(map.remove("b"), Some(bencode!(1)));
+    assert_eq!(map.get("b"), None);
+    map.retain(|k, _| k != b"c");
+    for v in map.values_mut() {
+        *v = bencode!([v.clone()]);
+    }
+    assert_eq!(Value::Dict(map.clone()), bencode!({ "a": [4], "d": [5] }));
+    let entries: Vec<_> = map.into_iter().map(|(k, v)| (k.into_vec(), v)).collect();
+    assert_eq!(entries.len(), 2);
+    let decoded: Map = from_bytes(b"d1:ai1ee").unwrap();
+    assert_eq!(to_bytes(&decoded).unwrap(), b"d1:ai1ee");
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn value_dict_preserves_order() {
+    let input = b"d1:bi1e1:ad1:zi0e1:yi0eee";
+    let v: Value = from_bytes(input).unwrap();
+    match &v {
+        Value::Dict(map) => assert_eq!(map.keys().collect::<Vec<_>>(), [b"b", b"a"]),
+        v => panic!("unexpected value: {:?}", v),
+    }
+    let unsorted = SerOptions::new().sort_keys(false);
+    assert_eq!(to_bytes_with_options(&v, unsorted).unwrap(), &input[..]);
+    assert_eq!(to_bytes(&v).unwrap(), b"d1:ad1:yi0e1:zi0ee1:bi1ee");
+    assert_eq!(
+        to_bytes_with_options(
+            &bencode!({ "z": 1, "a": 2 }),
+            SerOptions::new().sort_keys(false)
+        )
+        .unwrap(),
+        b"d1:zi1e1:ai2ee"
+    );
+}
+
+#[test]
+fn raw_dict_keeps_duplicate_keys() {
+    #[derive(Deserialize, Serialize)]
+    struct Torrent {
+        info: RawDict,
+    }
+
+    let input = b"d4:infod4:name1:a6:lengthi1e4:name1:bee";
+    let torrent: Torrent = from_bytes(input).unwrap();
+    assert_eq!(
+        torrent.info.0,
+        vec![
+            (ByteBuf::from("name"), bencode!("a")),
+            (ByteBuf::from("length"), bencode!(1)),
+            (ByteBuf::from("name"), bencode!("b")),
+        ]
+    );
+    assert_eq!(torrent.info.get_all("name").count(), 2);
+    assert!(matches!(to_bytes(&torrent), Err(Error::InvalidValue(_))));
+    let unsorted = SerOptions::new().sort_keys(false);
+    assert_eq!(
+        to_bytes_with_options(&torrent, unsorted).unwrap(),
+        &input[..]
+    );
+
+    // Ordinary values keep the last entry.
+    let v: Value = from_bytes(input).unwrap();
+    assert_eq!(v["info"]["name"], bencode!("b"));
+    assert!(from_bytes::<RawDict>(b"li1ee").is_err());
+}
+
+#[test]
+fn lazy_value() {
+    let input = b"d8:announce3:url4:infod5:filesld6:lengthi1e4:pathl1:aeee4:name4:spamee";
+    let lazy = LazyValue::from_slice(input).unwrap();
+    let torrent = lazy.as_dict().unwrap();
+    assert_eq!(torrent.len(), 2);
+    assert_eq!(
+        torrent.keys().collect::<Vec<_>>(),
+        vec![&b"announce"[..], b"info"]
+    );
+    assert_eq!(torrent.get(b"announce").unwrap().as_str(), Some("url"));
+    assert_eq!(torrent.get(b"missing"), None);
+
+    let info = torrent.get(b"info").unwrap();
+    let info = info.as_dict().unwrap();
+    assert_eq!(info.as_raw(), &input[22..input.len() - 1]);
+    let files = info.get(b"files").unwrap();
+    let file = files.as_list().unwrap().get(0).unwrap();
+    assert_eq!(
+        file.as_dict().unwrap().get(b"length").unwrap().as_int(),
+        Some(1)
+    );
+    assert_eq!(
+        file.decode::<HashMap<String, Value>>().unwrap()["path"],
+        bencode!(["a"])
+    );
+    assert_eq!(
+        lazy.to_value().unwrap(),
+        from_bytes::<Value>(input).unwrap()
+    );
+    assert_eq!(
+        LazyValue::from_slice(b"3:abc").unwrap(),
+        LazyValue::Bytes(b"abc")
+    );
+    assert_eq!(
+        LazyValue::from_slice(b"i7e")
+            .unwrap()
+            .decode::<u8>()
+            .unwrap(),
+        7
+    );
+
+    // The whole input is checked up front, so parsing on access cannot fail.
+    assert!(matches!(
+        LazyValue::from_slice(b"d1:ad1:bi1eee"),
+        Ok(LazyValue::Dict(_))
+    ));
+    assert!(matches!(
+        LazyValue::from_slice(b"d1:ad1:bi1ee"),
+        Err(Error::EndOfStream)
+    ));
+    assert!(matches!(
+        LazyValue::from_slice(b"d1:adi1ei1eee"),
+        Err(Error::Syntax(_))
+    ));
+    assert!(matches!(
+        LazyValue::from_slice(b"d1:ali1ee1:be"),
+        Err(Error::Syntax(_))
+    ));
+    assert!(matches!(
+        LazyValue::from_slice(b"i1ei2e"),
+        Err(Error::TrailingData)
+    ));
+}
+
+#[test]
+fn tokenizer() {
+    let tokens: Result<Vec<_>> = Tokenizer::new(b"d1:ali-3e0:ee4:spami1e").collect();
+    assert_eq!(
+        tokens.unwrap(),
+        vec![
+            (0, Token::DictStart),
+            (1, Token::Bytes(b"a")),
+            (4, Token::ListStart),
+            (5, Token::Int(-3)),
+            (9, Token::Bytes(b"")),
+            (11, Token::End),
+            (12, Token::End),
+            (13, Token::Bytes(b"spam")),
+            (19, Token::Int(1)),
+        ]
+    );
+
+    let mut tokens = Tokenizer::new(b"d1:ald1:bi1eee1:ci2ee");
+    assert_eq!(tokens.next().unwrap().unwrap(), (0, Token::DictStart));
+    assert_eq!(tokens.next().unwrap().unwrap(), (1, Token::Bytes(b"a")));
+    assert_eq!(tokens.skip_value().unwrap(), Some(&b"ld1:bi1eee"[..]));
+    assert_eq!(tokens.depth(), 1);
+    assert_eq!(tokens.peek(), Some(b'1'));
+    assert_eq!(tokens.next().unwrap().unwrap(), (14, Token::Bytes(b"c")));
+    assert_eq!(tokens.skip_value().unwrap(), Some(&b"i2e"[..]));
+    assert!(tokens.skip_value().is_err());
+    assert!(tokens.next().is_none());
+
+    for input in [&b"l4:sp"[..], b"li1e", b"e", b"i1.5e", b"x"] {
+        let tokens: Vec<_> = Tokenizer::new(input).collect();
+        assert!(tokens.last().unwrap().is_err(), "{:?}", input);
+    }
+    assert_eq!(Tokenizer::new(b"").skip_value().unwrap(), None);
+}
+
+#[test]
+fn parse_events_reports_tokens() {
+    use std::ops::ControlFlow;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        stop_at: Option<usize>,
+    }
+
+    impl Recorder {
+        fn record(&mut self, event: String, pos: Position) -> ControlFlow<()> {
+            self.events
+                .push(format!("{}@{}/{}", event, pos.offset, pos.depth));
+            match self.stop_at {
+                Some(n) if self.events.len() == n => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            }
+        }
+    }
+
+    impl EventHandler for Recorder {
+        fn on_int(&mut self, value: i64, pos: Position) -> ControlFlow<()> {
+            self.record(format!("int {}", value), pos)
+        }
+        fn on_big_int(&mut self, digits: &str, pos: Position) -> ControlFlow<()> {
+            self.record(format!("big {}", digits), pos)
+        }
+        fn on_bytes(&mut self, value: &[u8], pos: Position) -> ControlFlow<()> {
+            self.record(format!("bytes {}", String::from_utf8_lossy(value)), pos)
+        }
+        fn begin_list(&mut self, pos: Position) -> ControlFlow<()> {
+            self.record("list".to_string(), pos)
+        }
+        fn begin_dict(&mut self, pos: Position) -> ControlFlow<()> {
+            self.record("dict".to_string(), pos)
+        }
+        fn end(&mut self, pos: Position) -> ControlFlow<()> {
+            self.record("end".to_string(), pos)
+        }
+    }
+
+    let input = b"d1:ali7ei18446744073709551615eee";
+    let mut recorder = Recorder::default();
+    let reader = TrickleReader {
+        data: input,
+        interrupt: false,
+    };
+    let flow = parse_events(IoRead::new(reader), &mut recorder).unwrap();
+    assert!(flow.is_continue());
+    assert_eq!(
+        recorder.events,
+        [
+            "dict@0/0",
+            "bytes a@1/1",
+            "list@4/1",
+            "int 7@5/2",
+            "big 18446744073709551615@8/2",
+            "end@30/1",
+            "end@31/0",
+        ]
+    );
+
+    let mut recorder = Recorder {
+        stop_at: Some(2),
+        ..Recorder::default()
+    };
+    let flow = parse_events(SliceRead::new(b"l1:a1:b"), &mut recorder).unwrap();
+    assert!(flow.is_break());
+    assert_eq!(recorder.events.len(), 2);
+
+    let mut recorder = Recorder::default();
+    assert!(parse_events(SliceRead::new(b"l1:a"), &mut recorder).is_err());
+    assert!(parse_events(SliceRead::new(b"i1ei2e"), &mut recorder).is_err());
+    assert!(parse_events(SliceRead::new(b"e"), &mut recorder).is_err());
+    let options = Options::new().max_depth(1);
+    assert!(matches!(
+        parse_events_with_options(SliceRead::new(b"lle"), options, &mut recorder),
+        Err(Error::LimitExceeded(_))
+    ));
+}
+
+#[test]
+fn reader_hand_off() {
+    use std::io::Read;
+
+    let input = b"d1:ai1ee4:spam\xff\x00rest";
+    let mut de = Deserializer::new(&input[..]);
+    let _: Value = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(de.bytes_consumed(), 8);
+    let r: String = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(r, "spam");
+    assert_eq!(de.bytes_consumed(), 14);
+    assert!(de.peek_kind().is_err());
+    let (mut reader, peeked) = de.into_inner();
+    assert_eq!(peeked, Some(0xff));
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"\x00rest");
+
+    let mut de = Deserializer::new(&input[..]);
+    let _: Value = Deserialize::deserialize(&mut de).unwrap();
+    let (reader, peeked) = de.into_inner();
+    assert_eq!(peeked, None);
+    assert_eq!(reader, &input[8..]);
+}
+
+#[test]
+fn slice_deserializer() {
+    let mut de = Deserializer::from_slice(b"l4:spami7ee");
+    let r: (String, i64) = Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!(r, ("spam".to_string(), 7));
+    de.end().unwrap();
+
+    let mut de = Deserializer::from_slice(b"5:abc");
+    assert!(matches!(
+        Value::deserialize(&mut de),
+        Err(Error::EndOfStream)
+    ));
+
+    // Slices are scanned for delimiters in bulk, readers byte by byte, with the same outcome.
+    let inputs: [&[u8]; 6] = [
+        b"li-12e3:abce",
+        b"i12",
+        b"12",
+        b"i1:2e",
+        b"l3:a",
+        b"li123456789e",
+    ];
+    for input in inputs {
+        let options = Options::new().max_input_len(10);
+        let from_slice = Value::deserialize(&mut Deserializer::from_slice_with_options(
+            input,
+            options.clone(),
+        ));
+        let from_reader = Value::deserialize(&mut Deserializer::with_options(input, options));
+        assert_eq!(format!("{:?}", from_slice), format!("{:?}", from_reader));
+    }
+}
+
+#[test]
+fn ser_de_untagged_enum() {
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    struct FullNode {
+        id: ByteBuf,
+        port: u16,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    #[serde(untagged)]
+    enum Node {
+        Full(FullNode),
+        Compact(ByteBuf),
+    }
+
+    test_ser_de_eq(vec![
+        Node::Full(FullNode {
+            id: ByteBuf::from(vec![0xff, 0x00]),
+            port: 6881,
+        }),
+        Node::Compact(ByteBuf::from(vec![0xff; 6])),
+        Node::Compact(ByteBuf::from("text")),
+    ]);
+}
+
+#[test]
+fn ser_de_untagged_enum_with_unit_variants() {
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    enum Event {
+        Started,
+        Stopped(i64),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    #[serde(untagged)]
+    enum Mock {
+        Event(Event),
+        Int(i64),
+        Text(String),
+        List(Vec<Mock>),
+    }
+
+    test_ser_de_eq(vec![
+        Mock::Event(Event::Started),
+        Mock::Event(Event::Stopped(3)),
+        Mock::Int(5),
+        Mock::Text("Finished".to_string()),
+        Mock::List(vec![Mock::Int(1), Mock::Text("a".to_string())]),
+    ]);
+}
+
+#[test]
+fn deserialize_struct_ignoring_non_utf8_keys() {
+    #[derive(Deserialize, Debug, Eq, PartialEq)]
+    struct Fake {
+        a: i64,
+    }
+    let r: Fake = from_bytes(b"d1:ai1e2:\xff\xfei2ee").unwrap();
+    assert_eq!(r, Fake { a: 1 });
+}
+
+#[test]
+fn ser_de_non_utf8_field_names() {
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    struct Node {
+        #[serde(rename = r"\xff\xfe")]
+        binary: i64,
+        #[serde(rename = r"id\xc3")]
+        truncated: String,
+        text: i64,
+    }
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    enum Kind {
+        #[serde(rename = r"\x80")]
+        Binary(i64),
+    }
+
+    let node = Node {
+        binary: 1,
+        truncated: "a".to_string(),
+        text: 2,
+    };
+    let canonical = &b"d3:id\xc31:a4:texti2e2:\xff\xfei1ee"[..];
+    let r: Node = from_bytes(canonical).unwrap();
+    assert_eq!(r, node);
+    assert_eq!(to_bytes(&node).unwrap(), canonical);
+    let value: Value = from_bytes(canonical).unwrap();
+    assert_eq!(Node::deserialize(&value).unwrap(), node);
+    assert_eq!(Node::deserialize(value).unwrap(), node);
+
+    // Only escapes of bytes that cannot start a UTF-8 character are replaced when serializing,
+    // so other names with a backslash keep it.
+    #[derive(Serialize)]
+    struct Escapes {
+        #[serde(rename = r"\x7f\xFF\x")]
+        text: i64,
+        #[serde(rename = r"a\xffb")]
+        binary: i64,
+    }
+    let escapes = Escapes { text: 1, binary: 2 };
+    assert_eq!(
+        to_bytes(&escapes).unwrap(),
+        &b"d10:\\x7f\\xFF\\xi1e3:a\xffbi2ee"[..]
+    );
+
+    let kind = Kind::Binary(3);
+    let bytes = to_bytes(&kind).unwrap();
+    assert_eq!(bytes, b"d1:\x80i3ee");
+    assert_eq!(from_bytes::<Kind>(&bytes).unwrap(), kind);
+
+    // Variant names of enums used as keys are matched the same way.
+    #[derive(Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    enum Flag {
+        #[serde(rename = r"\xfe")]
+        Binary,
+        Text,
+    }
+    let r: BTreeMap<Flag, i64> = from_bytes(b"d4:Texti1e1:\xfei2ee").unwrap();
+    assert_eq!(r, BTreeMap::from([(Flag::Binary, 2), (Flag::Text, 1)]));
+
+    // Other binary keys are still ignored or rejected as unknown, and captured unchanged by
+    // flattened maps.
+    #[derive(Deserialize, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Strict {}
+    let r = from_bytes::<Strict>(b"d2:a\xffi1ee");
+    assert!(matches!(r, Err(Error::UnknownField(_))));
+    #[derive(Deserialize, Debug)]
+    struct Rest {
+        text: i64,
+        #[serde(flatten)]
+        rest: HashMap<ByteBuf, i64>,
+    }
+    let r: Rest = from_bytes(b"d4:texti1e2:\xfe\xffi2ee").unwrap();
+    assert_eq!(r.text, 1);
+    assert_eq!(r.rest[&ByteBuf::from(vec![0xfe, 0xff])], 2);
+}
+
+#[test]
+fn deserialize_integer_map_keys() {
+    let b = b"d1:0i5e2:10i6e1:7i7ee";
+    let r: HashMap<u32, i64> = from_bytes(b).unwrap();
+    assert_eq!(r, HashMap::from([(0, 5), (10, 6), (7, 7)]));
+    let r: BTreeMap<i8, Value> = from_bytes(b"d2:-1i1e1:2i2ee").unwrap();
+    assert_eq!(r.keys().copied().collect::<Vec<_>>(), vec![-1, 2]);
+    let r: BTreeMap<char, i64> = from_bytes("d1:ai1e2:éi2ee".as_bytes()).unwrap();
+    assert_eq!(r, BTreeMap::from([('a', 1), ('é', 2)]));
+
+    #[derive(Deserialize, Debug, PartialEq, Eq, Hash)]
+    struct Index(u16);
+    let r: HashMap<Index, i64> = from_bytes(b"d3:300i1ee").unwrap();
+    assert_eq!(r[&Index(300)], 1);
+    let r: HashMap<Option<u16>, i64> = from_bytes(b"d3:300i1ee").unwrap();
+    assert_eq!(r[&Some(300)], 1);
+
+    let value: Value = from_bytes(b).unwrap();
+    assert_eq!(HashMap::<u32, i64>::deserialize(&value).unwrap()[&10], 6);
+    assert_eq!(HashMap::<u32, i64>::deserialize(value).unwrap()[&7], 7);
+
+    let r = from_bytes::<HashMap<u32, i64>>(b"d1:ai1ee");
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+    assert!(from_bytes::<HashMap<u8, i64>>(b"d3:256i1ee").is_err());
+    assert!(from_bytes::<HashMap<u8, i64>>(b"d1:\xffi1ee").is_err());
+    assert!(from_bytes::<BTreeMap<char, i64>>(b"d2:abi1ee").is_err());
+    // Integers outside of keys are still decoded from integers only.
+    assert!(from_bytes::<Vec<u32>>(b"l1:1e").is_err());
+}
+
+#[test]
+fn serialize_integer_map_keys() {
+    let map = BTreeMap::from([(9u64, 1), (10, 2), (u64::MAX, 3)]);
+    let bytes = to_bytes(&map).unwrap();
+    assert_eq!(bytes, b"d2:10i2e20:18446744073709551615i3e1:9i1ee");
+    assert_eq!(from_bytes::<BTreeMap<u64, i64>>(&bytes).unwrap(), map);
+    let map = BTreeMap::from([(-1i128, 1), ('é' as i128, 2)]);
+    assert_eq!(to_bytes(&map).unwrap(), b"d2:-1i1e3:233i2ee");
+    let map = BTreeMap::from([('é', 1), ('a', 2)]);
+    assert_eq!(to_bytes(&map).unwrap(), "d1:ai2e2:éi1ee".as_bytes());
+
+    #[derive(Serialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Index(u16);
+    let map = BTreeMap::from([(Index(300), 1)]);
+    assert_eq!(to_bytes(&map).unwrap(), b"d3:300i1ee");
+
+    let options = SerOptions::new().keys(KeyPolicy::Error);
+    let r = to_bytes_with_options(&BTreeMap::from([(1, 1)]), options.clone());
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+    let r = to_bytes_with_options(&BTreeMap::from([('a', 1)]), options.clone());
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+    let r = to_bytes_with_options(&BTreeMap::from([("a", 1)]), options);
+    assert_eq!(r.unwrap(), b"d1:ai1ee");
+}
+
+#[test]
+fn ser_de_internally_tagged_enum() {
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    struct Inner {
+        x: i64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    #[serde(tag = "y")]
+    enum Mock {
+        A,
+        B { a: i64, b: String },
+        C(Inner),
+    }
+
+    assert_eq!(to_string(&Mock::A).unwrap(), "d1:y1:Ae");
+    assert_eq!(
+        to_string(&Mock::B {
+            a: 1,
+            b: "b".to_string()
+        })
+        .unwrap(),
+        "d1:ai1e1:b1:b1:y1:Be"
+    );
+    test_ser_de_eq(vec![
+        Mock::A,
+        Mock::B {
+            a: 1,
+            b: "b".to_string(),
+        },
+        Mock::C(Inner { x: 2 }),
+    ]);
+}
+
+#[test]
+fn ser_de_adjacently_tagged_enum_with_content() {
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    #[serde(tag = "t", content = "c")]
+    enum Mock {
+        A,
+        B(i64),
+        C(i64, String),
+        D { a: i64 },
+    }
+
+    assert_eq!(
+        to_string(&Mock::C(1, "x".to_string())).unwrap(),
+        "d1:cli1e1:xe1:t1:Ce"
+    );
+    test_ser_de_eq(vec![
+        Mock::A,
+        Mock::B(1),
+        Mock::C(2, "x".to_string()),
+        Mock::D { a: 3 },
+    ]);
+}
+
+#[test]
+fn deserialize_variant_with_wrong_shape() {
+    #[derive(Deserialize, Debug, Eq, PartialEq)]
+    enum Mock {
+        A,
+        B(i64),
+        C { a: i64 },
+    }
+
+    // A non-unit variant given as a bare name must not swallow the next value.
+    let r: Result<Vec<Mock>> = from_str("l1:Bi1ee");
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+    let r: Result<Mock> = from_str("1:C");
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+    let r: Result<Mock> = from_str("d1:Ai1ee");
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+    let r: Result<Mock> = from_str("d1:Bi1e1:Ci2ee");
+    assert!(matches!(r, Err(Error::InvalidLength(_))));
+}
+
+#[test]
+fn ser_de_flattened_struct() {
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    struct Common {
+        #[serde(rename = "piece length")]
+        piece_length: u32,
+        #[serde(default)]
+        private: Option<u8>,
+        pieces: ByteBuf,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    struct Info {
+        name: String,
+        #[serde(flatten)]
+        common: Common,
+    }
+
+    let info = Info {
+        name: "file".to_string(),
+        common: Common {
+            piece_length: 16384,
+            private: None,
+            pieces: ByteBuf::from(vec![0xff, 0x00]),
+        },
+    };
+    assert_eq!(
+        to_bytes(&info).unwrap(),
+        &b"d4:name4:file12:piece lengthi16384e6:pieces2:\xff\x00e"[..]
+    );
+    test_ser_de_eq(info);
+}
+
+#[test]
+fn ser_de_flattened_extra_keys() {
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    struct Torrent {
+        announce: String,
+        #[serde(flatten)]
+        extra: HashMap<String, Value>,
+    }
+
+    let b = "d8:announce3:url9:httpseedsl1:ae8:url-listl1:b1:cee";
+    let r: Torrent = from_str(b).unwrap();
+    assert_eq!(r.announce, "url");
+    assert_eq!(r.extra.len(), 2);
+    assert_eq!(
+        r.extra["url-list"],
+        Value::List(vec!["b".into(), "c".into()])
+    );
+    assert_eq!(to_string(&r).unwrap(), b);
+}
+
+#[test]
+fn capture_unknown_keys_byte_for_byte() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Info {
+        length: i64,
+        name: String,
+        #[serde(flatten)]
+        rest: BTreeMap<ByteBuf, Value>,
+    }
+
+    let b = &b"d6:lengthi3e6:md5sum2:\xaa\xbb4:name1:n5:x-subd1:ai1ee2:\xff\xffl1:xee"[..];
+    let r: Info = from_bytes(b).unwrap();
+    assert_eq!(r.rest.len(), 3);
+    assert_eq!(
+        r.rest[&ByteBuf::from(vec![0xff, 0xff])],
+        Value::List(vec!["x".into()])
+    );
+    assert_eq!(to_bytes(&r).unwrap(), b);
+}
+
+#[test]
+fn serialize_hash_map_in_canonical_order() {
+    let mut m = HashMap::new();
+    for k in ["zz", "a", "b\u{e9}", "b", "ab", "B"] {
+        m.insert(k.to_string(), 1);
+    }
+    let expected = "d1:Bi1e1:ai1e2:abi1e1:bi1e3:b\u{e9}i1e2:zzi1ee";
+    for _ in 0..8 {
+        assert_eq!(to_string(&m).unwrap(), expected);
+    }
+}
+
+#[test]
+fn serialize_rejects_duplicate_keys() {
+    #[derive(Serialize)]
+    struct Fake {
+        a: i64,
+        #[serde(flatten)]
+        rest: HashMap<String, i64>,
+    }
+
+    let mut rest = HashMap::new();
+    rest.insert("a".to_string(), 2);
+    let r = to_bytes(&Fake { a: 1, rest });
+    assert!(matches!(r, Err(Error::InvalidValue(_))));
+}
+
+#[test]
+fn serialize_unsorted_keys() {
+    let v: Value = from_str("d1:bi1e1:ai2ee").unwrap();
+    assert_eq!(to_string(&v).unwrap(), "d1:ai2e1:bi1ee");
+
+    #[derive(Serialize)]
+    struct Fake {
+        z: i64,
+        a: Vec<HashMap<String, i64>>,
+    }
+    let mut m = HashMap::new();
+    m.insert("y".to_string(), 1);
+    let f = Fake { z: 1, a: vec![m] };
+    let unsorted = SerOptions::new().sort_keys(false);
+    assert_eq!(
+        to_bytes_with_options(&f, unsorted).unwrap(),
+        b"d1:zi1e1:ald1:yi1eeee"
+    );
+}
+
+#[test]
+fn serialize_none_policy() {
+    #[derive(Serialize)]
+    struct Fake {
+        a: Option<i32>,
+        b: Option<i32>,
+    }
+    let f = Fake {
+        a: None,
+        b: Some(1),
+    };
+    let error = SerOptions::new().none(NonePolicy::Error);
+    assert!(matches!(
+        to_bytes_with_options(&f, error.clone()),
+        Err(Error::InvalidValue(_))
+    ));
+    let f = Fake {
+        a: Some(2),
+        b: Some(1),
+    };
+    assert_eq!(to_bytes_with_options(&f, error).unwrap(), b"d1:ai2e1:bi1ee");
+}
+
+#[test]
+fn serialize_unit_policy() {
+    #[derive(Serialize)]
+    struct Unit;
+
+    #[derive(Serialize)]
+    struct Fake {
+        a: (),
+        b: Unit,
+    }
+    let f = Fake { a: (), b: Unit };
+    assert_eq!(to_string(&f).unwrap(), "de");
+    let cases: [(UnitPolicy, &[u8]); 3] = [
+        (UnitPolicy::EmptyBytes, b"d1:a0:1:b0:e"),
+        (UnitPolicy::EmptyList, b"d1:ale1:blee"),
+        (UnitPolicy::EmptyDict, b"d1:ade1:bdee"),
+    ];
+    for (policy, expected) in cases {
+        let options = SerOptions::new().unit(policy);
+        assert_eq!(to_bytes_with_options(&f, options).unwrap(), expected);
+    }
+    let options = SerOptions::new().unit(UnitPolicy::Error);
+    assert!(to_bytes_with_options(&f, options).is_err());
+}
+
+#[test]
+fn serialize_bool_policy() {
+    let options = SerOptions::new().bool(BoolPolicy::Error);
+    assert!(matches!(
+        to_bytes_with_options(&true, options),
+        Err(Error::InvalidValue(_))
+    ));
+    let options = SerOptions::new().bool(BoolPolicy::Integer);
+    assert_eq!(to_bytes_with_options(&true, options).unwrap(), b"i1e");
+}
+
+#[test]
+fn ser_de_byte_arrays() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Node {
+        id: [u8; 20],
+        pair: (u8, u8),
+        mixed: (u8, String),
+    }
+
+    let node = Node {
+        id: [0xab; 20],
+        pair: (1, 2),
+        mixed: (3, "a".to_string()),
+    };
+    let encoded = to_bytes(&node).unwrap();
+    let mut expected = b"d2:id20:".to_vec();
+    expected.extend_from_slice(&[0xab; 20]);
+    expected.extend_from_slice(b"5:mixedli3e1:ae4:pair2:\x01\x02e");
+    assert_eq!(encoded, expected);
+    assert_eq!(from_bytes::<Node>(&encoded).unwrap(), node);
+    let value: Value = from_bytes(&encoded).unwrap();
+    assert_eq!(Node::deserialize(&value).unwrap(), node);
+    assert_eq!(Node::deserialize(value).unwrap(), node);
+
+    assert_eq!(to_bytes(&[0u8; 0]).unwrap(), b"le");
+    let options = SerOptions::new().byte_tuples(false);
+    assert_eq!(
+        to_bytes_with_options(&([1u8, 2], (3u8,)), options).unwrap(),
+        b"lli1ei2eeli3eee"
+    );
+
+    // Lists of integers are still accepted, but byte strings must have the exact length.
+    assert_eq!(from_bytes::<[u8; 2]>(b"li1ei2ee").unwrap(), [1, 2]);
+    assert_eq!(from_bytes::<[u8; 0]>(b"0:").unwrap(), [0u8; 0]);
+    assert_eq!(from_bytes::<[u8; 0]>(b"le").unwrap(), [0u8; 0]);
+    assert!(matches!(
+        from_bytes::<[String; 0]>(b"l1:ae"),
+        Err(Error::InvalidLength(_))
+    ));
+    assert!(matches!(
+        from_bytes::<[u8; 20]>(b"3:abc"),
+        Err(Error::InvalidLength(_))
+    ));
+    assert!(matches!(
+        from_bytes::<[u8; 2]>(b"3:abc"),
+        Err(Error::InvalidLength(_))
+    ));
+    assert!(from_bytes::<[u16; 2]>(b"2:ab").is_ok());
+    assert!(matches!(
+        from_bytes::<[String; 1]>(b"1:a"),
+        Err(Error::InvalidType(_))
+    ));
+}
+
+#[test]
+fn deserializer_peek() {
+    let input = b"d1:t2:aa1:y1:e1:eli201e5:oops!ee";
+    let mut de = Deserializer::new(&input[..]);
+    assert_eq!(de.peek_kind().unwrap(), Kind::DictStart);
+    assert_eq!(de.peek_bytes_len().unwrap(), None);
+    assert_eq!(
+        Value::deserialize(&mut de).unwrap()["e"][1],
+        bencode!("oops!")
+    );
+    de.end().unwrap();
+    assert!(matches!(de.peek_kind(), Err(Error::EndOfStream)));
+
+    // Peeking never consumes the token, whatever reads it afterwards.
+    let mut de = Deserializer::new(&b"5:hello3:abci1ee"[..]);
+    assert_eq!(de.peek_bytes_len().unwrap(), Some(5));
+    assert_eq!(de.peek_kind().unwrap(), Kind::Bytes);
+    assert_eq!(de.peek_bytes_len().unwrap(), Some(5));
+    assert_eq!(String::deserialize(&mut de).unwrap(), "hello");
+    assert_eq!(de.peek_bytes_len().unwrap(), Some(3));
+    IgnoredAny::deserialize(&mut de).unwrap();
+    assert_eq!(de.peek_kind().unwrap(), Kind::Integer);
+    assert_eq!(de.peek_bytes_len().unwrap(), None);
+    assert_eq!(i64::deserialize(&mut de).unwrap(), 1);
+    assert_eq!(de.peek_kind().unwrap(), Kind::End);
+    assert!(matches!(
+        Deserializer::from_slice(b"x").peek_kind(),
+        Err(Error::Syntax(_))
+    ));
+
+    let mut de = Deserializer::from_slice(b"04:spam");
+    de.peek_bytes_len().unwrap();
+    assert_eq!(
+        RawValue::deserialize(&mut de).unwrap().as_bytes(),
+        b"04:spam"
+    );
+    let mut de = Deserializer::from_slice(b"4:spam");
+    de.peek_bytes_len().unwrap();
+    assert!(matches!(de.end(), Err(Error::TrailingData)));
+    let options = Options::new().max_bytes_len(3);
+    let mut de = Deserializer::from_slice_with_options(b"4:spam", options);
+    assert!(matches!(de.peek_bytes_len(), Err(Error::LimitExceeded(_))));
+}
+
+#[test]
+fn deserialize_bool_policy() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Info {
+        private: bool,
+        seed: Option<bool>,
+    }
+    let info: Info = from_bytes(b"d7:privatei1e4:seedi0ee").unwrap();
+    assert_eq!(
+        info,
+        Info {
+            private: true,
+            seed: Some(false)
+        }
+    );
+    assert_eq!(to_bytes(&info).unwrap(), b"d7:privatei1e4:seedi0ee");
+    let value: Value = from_bytes(b"li0ei1ee").unwrap();
+    assert_eq!(Vec::<bool>::deserialize(&value).unwrap(), [false, true]);
+    assert_eq!(Vec::<bool>::deserialize(value).unwrap(), [false, true]);
+
+    assert!(matches!(
+        from_bytes::<bool>(b"i2e"),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        from_bytes::<bool>(b"4:true"),
+        Err(Error::InvalidType(_))
+    ));
+    assert!(bool::deserialize(Value::Int(-1)).is_err());
+    let non_zero = Options::new().bool(BoolPolicy::NonZero);
+    assert!(from_bytes_with_options::<bool>(b"i-7e", non_zero.clone()).unwrap());
+    assert!(!from_bytes_with_options::<bool>(b"i0e", non_zero).unwrap());
+    let error = Options::new().bool(BoolPolicy::Error);
+    assert!(matches!(
+        from_bytes_with_options::<bool>(b"i1e", error),
+        Err(Error::InvalidType(_))
+    ));
+}
+
+#[test]
+fn serialize_into_existing_buffer() {
+    let mut out = b"prefix".to_vec();
+    to_bytes_in(&bencode!({ "b": 1, "a": [2] }), &mut out).unwrap();
+    assert_eq!(out, b"prefixd1:ali2ee1:bi1ee");
+    let capacity = out.capacity();
+    out.clear();
+    to_bytes_in(&"spam", &mut out).unwrap();
+    assert_eq!(out, b"4:spam");
+    assert_eq!(out.capacity(), capacity);
+
+    let r = to_bytes_in(&("a", 1.5), &mut out);
+    assert!(r.is_err());
+    assert_eq!(out, b"4:spam");
+    let options = SerOptions::new().float(FloatPolicy::String);
+    to_bytes_in_with_options(&("a", 1.5), &mut out, options).unwrap();
+    assert_eq!(out, b"4:spaml1:a3:1.5e");
+}
+
+#[test]
+fn serialized_len_matches_to_bytes() {
+    #[derive(Serialize)]
+    struct Fake {
+        b: Option<i64>,
+        a: Vec<String>,
+        c: (),
+        d: HashMap<String, Option<i64>>,
+    }
+
+    let mut d = HashMap::new();
+    d.insert("x".to_string(), Some(-12));
+    d.insert("y".to_string(), None);
+    let fake = Fake {
+        b: None,
+        a: vec!["spam".to_string(), String::new()],
+        c: (),
+        d,
+    };
+    let options = SerOptions::new()
+        .sort_keys(false)
+        .unit(UnitPolicy::EmptyList)
+        .float(FloatPolicy::String);
+    assert_eq!(
+        serialized_len(&fake).unwrap(),
+        to_bytes(&fake).unwrap().len()
+    );
+    assert_eq!(
+        serialized_len_with_options(&fake, options.clone()).unwrap(),
+        to_bytes_with_options(&fake, options.clone()).unwrap().len()
+    );
+    assert_eq!(serialized_len_with_options(&1.5f64, options).unwrap(), 5);
+    assert!(serialized_len(&1.5f64).is_err());
+
+    struct Dup(Vec<(&'static str, i64)>);
+    impl Serialize for Dup {
+        fn serialize<S: serde::Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+            s.collect_map(self.0.iter().cloned())
+        }
+    }
+    assert!(serialized_len(&Dup(vec![("a", 1), ("a", 2)])).is_err());
+}
+
+#[test]
+fn digest_selected_subtree() {
+    let input: &[u8] = b"d1:ai1e4:infod5:filesld6:lengthi4eee4:name1:xe1:zi2ee";
+    let value: Value = from_bytes(input).unwrap();
+    let digest = |path: &[&str]| {
+        let mut ser = Vec::new();
+        let bytes = to_bytes_with_digest(&value, SerOptions::default(), path, |b| {
+            ser.push(b.to_vec())
+        })
+        .unwrap();
+        assert_eq!(bytes, input);
+
+        let mut de = Vec::new();
+        let reader = IoRead::new(TrickleReader {
+            data: input,
+            interrupt: false,
+        });
+        let mut deserializer =
+            Deserializer::with_digest(reader, Options::default(), path, |b: &[u8]| {
+                de.extend_from_slice(b)
+            });
+        assert_eq!(Value::deserialize(&mut deserializer).unwrap(), value);
+        drop(deserializer);
+        assert!(ser.len() <= 1);
+        assert_eq!(ser.concat(), de);
+        de
+    };
+    assert_eq!(digest(&["info"]), b"d5:filesld6:lengthi4eee4:name1:xe");
+    assert_eq!(digest(&["info", "name"]), b"1:x");
+    assert_eq!(digest(&["info", "files"]), b"ld6:lengthi4eee");
+    assert_eq!(digest(&["z"]), b"i2e");
+    assert_eq!(digest(&[]), input);
+    assert_eq!(digest(&["name"]), b"");
+    assert_eq!(digest(&["info", "files", "length"]), b"");
+}
+
+#[test]
+fn compact_peers() {
+    use std::net::{SocketAddrV4, SocketAddrV6};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Response {
+        #[serde(with = "serde_bencode::helpers::compact_peers")]
+        peers: Vec<SocketAddrV4>,
+        #[serde(with = "serde_bencode::helpers::compact_peers")]
+        peers6: Vec<SocketAddrV6>,
+    }
+
+    let response = Response {
+        peers: vec![
+            "1.2.3.4:80".parse().unwrap(),
+            "255.255.255.255:65535".parse().unwrap(),
+        ],
+        peers6: vec!["[::1]:6881".parse().unwrap()],
+    };
+    let encoded = to_bytes(&response).unwrap();
+    assert_eq!(
+        encoded,
+        &b"d5:peers12:\x01\x02\x03\x04\x00\x50\xff\xff\xff\xff\xff\xff6:peers618:\
+           \0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01\x1a\xe1e"[..]
+    );
+    assert_eq!(from_bytes::<Response>(&encoded).unwrap(), response);
+    let value: Value = from_bytes(&encoded).unwrap();
+    assert_eq!(Response::deserialize(value).unwrap(), response);
+
+    assert!(from_bytes::<Response>(b"d5:peers5:\x01\x02\x03\x04\x006:peers60:e").is_err());
+    assert!(from_bytes::<Response>(b"d5:peersle6:peers60:e").is_err());
+}
+
+#[test]
+fn pieces_helper() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Pieces {
+        #[serde(with = "serde_bencode::helpers::pieces")]
+        v1: Vec<[u8; 20]>,
+        #[serde(with = "serde_bencode::helpers::pieces")]
+        v2: Vec<[u8; 32]>,
+    }
+
+    let pieces = Pieces {
+        v1: vec![[1; 20], [2; 20]],
+        v2: vec![],
+    };
+    let encoded = to_bytes(&pieces).unwrap();
+    let mut expected = b"d2:v140:".to_vec();
+    expected.extend_from_slice(&[1; 20]);
+    expected.extend_from_slice(&[2; 20]);
+    expected.extend_from_slice(b"2:v20:e");
+    assert_eq!(encoded, expected);
+    assert_eq!(from_bytes::<Pieces>(&encoded).unwrap(), pieces);
+    let value: Value = from_bytes(&encoded).unwrap();
+    assert_eq!(Pieces::deserialize(&value).unwrap(), pieces);
+
+    let r = from_bytes::<Pieces>(b"d2:v13:abc2:v20:e");
+    assert!(matches!(r, Err(Error::InvalidLength(_))));
+    let r = from_bytes::<Pieces>(b"d2:v10:2:v2i1ee");
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+}
+
+#[test]
+fn hex_and_base32_helpers() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Hashes {
+        #[serde(with = "serde_bencode::helpers::hex")]
+        hex: String,
+        #[serde(with = "serde_bencode::helpers::hex")]
+        hex_raw: [u8; 2],
+        #[serde(with = "serde_bencode::helpers::base32")]
+        base32: String,
+        #[serde(with = "serde_bencode::helpers::base32")]
+        base32_raw: [u8; 3],
+    }
+
+    let hashes = Hashes {
+        hex: "00ff7f".to_string(),
+        hex_raw: [0xab, 0xcd],
+        base32: "MZXW6YTBOI".to_string(),
+        base32_raw: *b"foo",
+    };
+    let encoded = to_bytes(&hashes).unwrap();
+    assert_eq!(
+        encoded,
+        &b"d6:base326:foobar10:base32_raw3:foo3:hex3:\x00\xff\x7f7:hex_raw2:\xab\xcde"[..]
+    );
+    assert_eq!(from_bytes::<Hashes>(&encoded).unwrap(), hashes);
+
+    // Array fields also accept the encoded text, in any case and with padding.
+    let text = b"d6:base323:foo10:base32_raw8:mzxw6===3:hex0:7:hex_raw4:ABcde";
+    let decoded: Hashes = from_bytes(text).unwrap();
+    assert_eq!(decoded.base32, "MZXW6");
+    assert_eq!(decoded.base32_raw, *b"foo");
+    assert_eq!(decoded.hex, "");
+    assert_eq!(decoded.hex_raw, [0xab, 0xcd]);
+
+    let invalid = |hex: &str, base32: &str| Hashes {
+        hex: hex.to_string(),
+        hex_raw: [0; 2],
+        base32: base32.to_string(),
+        base32_raw: [0; 3],
+    };
+    assert!(to_bytes(&invalid("abc", "")).is_err());
+    assert!(to_bytes(&invalid("zz", "")).is_err());
+    assert!(to_bytes(&invalid("", "MZXW6YTBO")).is_err());
+    assert!(to_bytes(&invalid("", "MZ1")).is_err());
+    assert!(from_bytes::<Hashes>(b"d6:base320:10:base32_raw1:x3:hex0:7:hex_raw2:abe").is_err());
+
+    // The field types can be named in the bounds of generic wrappers.
+    fn hex_bytes<T: serde_bencode::helpers::Field>(value: &T) -> Vec<u8> {
+        let mut ser = Serializer::new();
+        serde_bencode::helpers::hex::serialize(value, &mut ser).unwrap();
+        ser.into_vec()
+    }
+    assert_eq!(hex_bytes(&"00ff".to_string()), b"2:\x00\xff");
+    assert_eq!(hex_bytes(&[1u8]), b"1:\x01");
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Token {
+        #[serde(with = "serde_bencode::helpers::base64")]
+        text: String,
+        #[serde(with = "serde_bencode::helpers::base64")]
+        raw: [u8; 4],
+    }
+    let token: Token = from_bytes(b"d3:raw8:AAECAw==4:text5:\xfb\xff\x00\x01\x02e").unwrap();
+    assert_eq!(token.raw, [0, 1, 2, 3]);
+    assert_eq!(token.text, "+/8AAQI=");
+    assert_eq!(
+        to_bytes(&token).unwrap(),
+        b"d3:raw4:\x00\x01\x02\x034:text5:\xfb\xff\x00\x01\x02e"
+    );
+    let unpadded: Token = from_bytes(b"d3:raw6:AAECAw4:text0:e").unwrap();
+    assert_eq!(unpadded.raw, [0, 1, 2, 3]);
+    for invalid in ["A", "AB=C", "AAECAw===", "A*=="] {
+        let token = Token {
+            text: invalid.to_string(),
+            raw: [0; 4],
+        };
+        assert!(to_bytes(&token).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn unix_seconds_helper() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Metainfo {
+        #[serde(
+            rename = "creation date",
+            with = "serde_bencode::helpers::unix_seconds"
+        )]
+        creation_date: SystemTime,
+        #[serde(default, with = "serde_bencode::helpers::unix_seconds::option")]
+        updated: Option<SystemTime>,
+    }
+
+    let metainfo = Metainfo {
+        creation_date: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        updated: Some(UNIX_EPOCH - Duration::from_secs(10)),
+    };
+    let encoded = to_bytes(&metainfo).unwrap();
+    assert_eq!(encoded, b"d13:creation datei1700000000e7:updatedi-10ee");
+    assert_eq!(from_bytes::<Metainfo>(&encoded).unwrap(), metainfo);
+
+    let metainfo: Metainfo = from_bytes(b"d13:creation datei0ee").unwrap();
+    assert_eq!(metainfo.creation_date, UNIX_EPOCH);
+    assert_eq!(metainfo.updated, None);
+    assert_eq!(to_bytes(&metainfo).unwrap(), b"d13:creation datei0ee");
+
+    // Fractions of a second are rounded down.
+    let metainfo = Metainfo {
+        creation_date: UNIX_EPOCH + Duration::from_millis(1500),
+        updated: Some(UNIX_EPOCH - Duration::from_millis(1500)),
+    };
+    assert_eq!(
+        to_bytes(&metainfo).unwrap(),
+        b"d13:creation datei1e7:updatedi-2ee"
+    );
+
+    assert!(from_bytes::<Metainfo>(b"d13:creation date3:nowe").is_err());
+}
+
+#[test]
+fn raw_value_keeps_encoding() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Outer {
+        a: RawValue,
+        b: Vec<RawValue>,
+    }
+
+    // Not canonical: the keys are unsorted and the integer has a leading zero.
+    let input: &[u8] = b"d1:ad1:zi01e1:ylee1:bld1:ai1eeli2eeee";
+    let outer: Outer = from_bytes(input).unwrap();
+    assert_eq!(outer.a.as_bytes(), b"d1:zi01e1:ylee");
+    assert_eq!(outer.b.len(), 2);
+    assert_eq!(outer.b[0].as_bytes(), b"d1:ai1ee");
+    assert_eq!(outer.b[1].as_bytes(), b"li2ee");
+    assert_eq!(
+        to_bytes(&outer).unwrap(),
+        &b"d1:ad1:zi01e1:ylee1:bld1:ai1eeli2eeee"[..]
+    );
+    assert_eq!(serialized_len(&outer).unwrap(), input.len());
+
+    let reader = TrickleReader {
+        data: input,
+        interrupt: false,
+    };
+    let streamed = Outer::deserialize(&mut Deserializer::new(reader)).unwrap();
+    assert_eq!(streamed, outer);
+
+    // Other deserializers produce the canonical encoding.
+    let value: Value = from_bytes(input).unwrap();
+    let from_value = Outer::deserialize(value).unwrap();
+    assert_eq!(from_value.a.as_bytes(), b"d1:yle1:zi1ee");
+    assert_eq!(from_value.b, outer.b);
+
+    assert_eq!(
+        from_value.a.decode::<Value>().unwrap(),
+        outer.a.decode().unwrap()
+    );
+    // Tokens that were peeked before the `RawValue` was read keep their bytes too.
+    let peeked: Vec<RawValue> = from_bytes(b"li01e03:abclee").unwrap();
+    let peeked: Vec<&[u8]> = peeked.iter().map(RawValue::as_bytes).collect();
+    assert_eq!(peeked, [&b"i01e"[..], b"03:abc", b"le"]);
+
+    // A byte string is encoded as itself, not taken as an encoding.
+    let bytes = Value::from("4:spam");
+    assert_eq!(
+        RawValue::deserialize(&bytes).unwrap().as_bytes(),
+        b"6:4:spam"
+    );
+
+    // JSON writes the encoding as an array of numbers and reads the same bytes back.
+    let json = serde_json::to_string(&outer).unwrap();
+    assert!(json.starts_with("{\"a\":[100,49,58,122,105,48,49,101,"));
+    assert_eq!(serde_json::from_str::<Outer>(&json).unwrap(), outer);
+    let decoded: RawValue = serde_json::from_str("[1,2]").unwrap();
+    assert_eq!(decoded.as_bytes(), b"li1ei2ee");
+
+    assert!(RawValue::from_bytes(b"i1ei2e".to_vec()).is_err());
+    assert!(RawValue::from_bytes(b"l".to_vec()).is_err());
+    assert_eq!(
+        RawValue::from_value(&vec![1, 2]).unwrap(),
+        RawValue::from_bytes(b"li1ei2ee".to_vec()).unwrap()
+    );
+}
+
+#[test]
+fn ignored_list_elements() {
+    let ignored: Vec<IgnoredAny> = from_bytes(b"lli1eed1:ai1ee3:abce").unwrap();
+    assert_eq!(ignored.len(), 3);
+}
+
+#[cfg(feature = "torrent")]
+#[test]
+fn torrent_metainfo() {
+    use serde_bencode::torrent::{FileEntry, Info, Metainfo};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let info: &[u8] =
+        b"d6:lengthi4e4:name5:a.txt12:piece lengthi16384e6:pieces20:AAAAAAAAAAAAAAAAAAAA1:xi01ee";
+    let mut input = b"d8:announce3:url13:creation datei1700000000e4:info".to_vec();
+    input.extend_from_slice(info);
+    input.push(b'e');
+
+    let metainfo: Metainfo = from_bytes(&input).unwrap();
+    assert_eq!(metainfo.announce.as_deref(), Some("url"));
+    assert_eq!(
+        metainfo.creation_date,
+        Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    );
+    assert_eq!(metainfo.info.as_bytes(), info);
+    assert_eq!(
+        metainfo.info_hash(),
+        *b"\x48\x3a\x22\x0a\xb6\xba\x8c\x1c\x02\xa7\x12\x7d\x2e\x1f\x0a\xb6\x29\xd9\xd2\x1b"
+    );
+    assert_eq!(to_bytes(&metainfo).unwrap(), input);
+
+    let decoded = metainfo.info().unwrap();
+    assert_eq!(decoded.name, "a.txt");
+    assert_eq!(decoded.piece_length, 16384);
+    assert_eq!(decoded.pieces, [b'A'; 20]);
+    assert_eq!(decoded.length, Some(4));
+    assert_eq!(decoded.files, None);
+    assert_eq!(decoded.total_length(), 4);
+
+    let multi = Info {
+        name: "dir".to_string(),
+        piece_length: 16384,
+        pieces: vec![0; 20],
+        length: None,
+        files: Some(vec![
+            FileEntry {
+                length: 3,
+                path: vec!["a".to_string(), "b.txt".to_string()],
+                md5sum: None,
+            },
+            FileEntry {
+                length: 5,
+                path: vec!["c.txt".to_string()],
+                md5sum: None,
+            },
+        ]),
+        private: Some(1),
+        md5sum: None,
+        meta_version: None,
+        file_tree: None,
+    };
+    let metainfo = Metainfo::new("url", &multi).unwrap();
+    let encoded = to_bytes(&metainfo).unwrap();
+    assert_eq!(
+        encoded,
+        &b"d8:announce3:url4:infod5:filesld6:lengthi3e4:pathl1:a5:b.txteed6:lengthi5e4:pathl5:\
+           c.txteee4:name3:dir12:piece lengthi16384e6:pieces20:\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\
+           \0\0\x007:privatei1eee"[..]
+    );
+    let decoded: Metainfo = from_bytes(&encoded).unwrap();
+    assert_eq!(decoded.info().unwrap(), multi);
+    assert_eq!(decoded.info().unwrap().total_length(), 8);
+}
+
+#[cfg(feature = "torrent")]
+#[test]
+fn torrent_metainfo_v2() {
+    use serde_bencode::torrent::{File, FileTree, Info, Metainfo};
+
+    let sub = FileTree::Directory(
+        vec![(
+            "b".to_string(),
+            FileTree::File(File {
+                length: 0,
+                pieces_root: None,
+            }),
+        )]
+        .into_iter()
+        .collect(),
+    );
+    let a = FileTree::File(File {
+        length: 3,
+        pieces_root: Some([1; 32]),
+    });
+    let info = Info {
+        name: "dir".to_string(),
+        piece_length: 16384,
+        pieces: Vec::new(),
+        length: None,
+        files: None,
+        private: None,
+        md5sum: None,
+        meta_version: Some(2),
+        file_tree: Some(
+            vec![("a.txt".to_string(), a), ("sub".to_string(), sub)]
+                .into_iter()
+                .collect(),
+        ),
+    };
+    let mut metainfo = Metainfo::new("url", &info).unwrap();
+    metainfo.piece_layers = Some(vec![([1; 32], vec![2; 64])].into_iter().collect());
+
+    let mut encoded =
+        b"d8:announce3:url4:infod9:file treed5:a.txtd0:d6:lengthi3e11:pieces root32:".to_vec();
+    encoded.extend_from_slice(&[1; 32]);
+    encoded.extend_from_slice(
+        b"ee3:subd1:bd0:d6:lengthi0eeeee12:meta versioni2e4:name3:dir12:piece lengthi16384ee\
+          12:piece layersd32:",
+    );
+    encoded.extend_from_slice(&[1; 32]);
+    encoded.extend_from_slice(b"64:");
+    encoded.extend_from_slice(&[2; 64]);
+    encoded.extend_from_slice(b"ee");
+    assert_eq!(to_bytes(&metainfo).unwrap(), encoded);
+
+    let decoded: Metainfo = from_bytes(&encoded).unwrap();
+    assert_eq!(decoded, metainfo);
+    assert_eq!(decoded.info().unwrap(), info);
+    // The hashes are plain arrays, read from a `Value` as well.
+    let value: Value = from_bytes(&encoded).unwrap();
+    assert_eq!(Metainfo::deserialize(&value).unwrap(), metainfo);
+    assert_eq!(
+        decoded.info_hash_v2(),
+        *b"\x36\x13\xc4\xfd\x91\x92\xfb\x0b\x8e\x07\xa6\xfc\xc8\xa0\x63\xc7\
+           \xd9\x6d\x11\x00\x5d\x1d\xfa\xd2\x26\x3f\xb2\x83\x83\x5d\x18\xd2"
+    );
+    assert_eq!(info.total_length(), 3);
+    let tree = FileTree::Directory(info.file_tree.clone().unwrap());
+    let paths: Vec<Vec<&str>> = tree.files().into_iter().map(|(path, _)| path).collect();
+    assert_eq!(paths, [vec!["a.txt"], vec!["sub", "b"]]);
+
+    // A file's attributes cannot be mixed with directory entries, and hashes are 32 bytes.
+    assert!(from_bytes::<FileTree>(b"d0:d6:lengthi1ee1:xd0:d6:lengthi1eeee").is_err());
+    assert!(from_bytes::<FileTree>(b"d1:xd0:d6:lengthi1ee0:d6:lengthi1eeee").is_err());
+    assert!(matches!(
+        from_bytes::<FileTree>(b"d0:d6:lengthi1e11:pieces root3:abcee"),
+        Err(Error::InvalidLength(_))
+    ));
+}
+
+#[cfg(feature = "krpc")]
+#[test]
+fn krpc_messages() {
+    use serde_bencode::krpc::{
+        Error as KrpcError, Message, MessageKind, NodeInfo, Query, Response,
+    };
+
+    let id = *b"abcdefghij0123456789";
+    let info_hash = *b"mnopqrstuvwxyz123456";
+    let round_trip = |message: &Message, encoded: &[u8]| {
+        assert_eq!(to_bytes(message).unwrap(), encoded);
+        assert_eq!(&from_bytes::<Message>(encoded).unwrap(), message);
+    };
+
+    round_trip(
+        &Message::query(
+            b"aa".to_vec(),
+            Query::FindNode {
+                id,
+                target: info_hash,
+            },
+        ),
+        b"d1:ad2:id20:abcdefghij01234567896:target20:mnopqrstuvwxyz123456e1:q9:find_node\
+          1:t2:aa1:y1:qe",
+    );
+    round_trip(
+        &Message::query(
+            b"\x00\xff".to_vec(),
+            Query::AnnouncePeer {
+                id,
+                info_hash,
+                port: 6881,
+                implied_port: true,
+                token: b"aoeusnth".to_vec(),
+            },
+        ),
+        b"d1:ad2:id20:abcdefghij012345678912:implied_porti1e9:info_hash20:mnopqrstuvwxyz123456\
+          4:porti6881e5:token8:aoeusnthe1:q13:announce_peer1:t2:\x00\xff1:y1:qe",
+    );
+
+    let mut response = Response::new(id);
+    response.token = Some(b"tok".to_vec());
+    response.nodes = vec![NodeInfo {
+        id: info_hash,
+        addr: "10.0.0.1:6881".parse().unwrap(),
+    }];
+    response.values = vec!["127.0.0.1:80".parse().unwrap()];
+    let mut message = Message::response(b"bb".to_vec(), response);
+    message.version = Some(b"UT\x01\x02".to_vec());
+    round_trip(
+        &message,
+        b"d1:rd2:id20:abcdefghij01234567895:nodes26:mnopqrstuvwxyz123456\x0a\x00\x00\x01\x1a\xe1\
+          5:token3:tok6:valuesl6:\x7f\x00\x00\x01\x00\x50ee1:t2:bb1:v4:UT\x01\x021:y1:re",
+    );
+
+    round_trip(
+        &Message::error(
+            b"cc".to_vec(),
+            KrpcError::new(KrpcError::METHOD_UNKNOWN, "Method Unknown"),
+        ),
+        b"d1:eli204e14:Method Unknowne1:t2:cc1:y1:ee",
+    );
+
+    // Unknown keys are ignored, but the arguments of the query must be complete.
+    let get_peers: Message = from_bytes(
+        b"d1:ad2:id20:abcdefghij01234567899:info_hash20:mnopqrstuvwxyz1234562:roi1ee\
+          1:q9:get_peers1:t2:aa1:y1:qe",
+    )
+    .unwrap();
+    assert_eq!(
+        get_peers.kind,
+        MessageKind::Query(Query::GetPeers { id, info_hash })
+    );
+    assert!(matches!(
+        from_bytes::<Message>(b"d1:ad2:id20:abcdefghij0123456789e1:q9:get_peers1:t2:aa1:y1:qe"),
+        Err(Error::MissingField(_))
+    ));
+    assert!(matches!(
+        from_bytes::<Message>(b"d1:ad2:id20:abcdefghij0123456789e1:q3:put1:t2:aa1:y1:qe"),
+        Err(Error::UnknownVariant(_))
+    ));
+    assert!(matches!(
+        from_bytes::<Message>(b"d1:rd2:id3:abce1:t2:aa1:y1:re"),
+        Err(Error::InvalidLength(_))
+    ));
+    assert!(matches!(
+        from_bytes::<Message>(b"d1:rd2:id20:abcdefghij01234567895:nodes3:abce1:t2:aa1:y1:re"),
+        Err(Error::InvalidLength(_))
+    ));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_conversion() {
+    use serde_bencode::value::JsonBytes;
+    use serde_json::json;
+
+    let value = bencode!({
+        "announce": "url",
+        "info": { "length": -4, "pieces": b"\x00\xff\xfe" },
+        "list": [1, "a"],
+    });
+    let utf8 = value.to_json(JsonBytes::Utf8Lossy);
+    assert_eq!(
+        utf8,
+        json!({
+            "announce": "url",
+            "info": { "length": -4, "pieces": "\u{0}\u{fffd}\u{fffd}" },
+            "list": [1, "a"],
+        })
+    );
+    assert_ne!(
+        Value::from_json(&utf8, JsonBytes::Utf8Lossy).unwrap(),
+        value
+    );
+
+    let encoded = [
+        (JsonBytes::Hex, "696e666f", "706965636573", "00fffe"),
+        (JsonBytes::Base64, "aW5mbw==", "cGllY2Vz", "AP/+"),
+    ];
+    for (bytes, info, key, pieces) in encoded {
+        let json = value.to_json(bytes);
+        assert_eq!(json[info][key], pieces);
+        assert_eq!(Value::from_json(&json, bytes).unwrap(), value);
+    }
+    assert_eq!(value.to_json(JsonBytes::Base64)["YW5ub3VuY2U="], "dXJs");
+
+    // Binary keys are encoded like any other byte string.
+    let binary_keys: Value = from_bytes(b"d1:\xff1:x1:\xfe1:ye").unwrap();
+    let json = binary_keys.to_json(JsonBytes::Hex);
+    assert_eq!(json, json!({ "fe": "79", "ff": "78" }));
+    assert_eq!(
+        Value::from_json(&json, JsonBytes::Hex).unwrap(),
+        binary_keys
+    );
+    assert!(Value::from_json(&json!({ "xy": 1 }), JsonBytes::Hex).is_err());
+
+    assert_eq!(
+        Value::from_json(&json!([true, false]), JsonBytes::Hex).unwrap(),
+        bencode!([1, 0])
+    );
+    assert!(Value::from_json(&json!(null), JsonBytes::Hex).is_err());
+    assert!(Value::from_json(&json!(1.5), JsonBytes::Hex).is_err());
+    assert!(Value::from_json(&json!("xyz"), JsonBytes::Hex).is_err());
+    assert!(Value::from_json(&json!("A"), JsonBytes::Base64).is_err());
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn shared_bytes() {
+    use bytes::Bytes;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Message {
+        #[serde(with = "serde_bencode::bytes")]
+        id: Bytes,
+        list: Vec<ByteBuf>,
+        #[serde(with = "serde_bencode::bytes")]
+        data: Bytes,
+    }
+
+    let input = Bytes::from_static(b"d4:data3:\xff\x00\xfe2:id2:ab4:listl1:xee");
+    let message: Message = serde_bencode::bytes::from_bytes(&input).unwrap();
+    assert_eq!(message.data, &b"\xff\x00\xfe"[..]);
+    assert_eq!(message.data.as_ptr(), input[9..].as_ptr());
+    assert_eq!(message.id.as_ptr(), input[18..].as_ptr());
+    assert_eq!(to_bytes(&message).unwrap(), input);
+
+    // Other sources copy.
+    assert_eq!(from_bytes::<Message>(&input).unwrap(), message);
+    let value: Value = from_bytes(&input).unwrap();
+    assert_eq!(Message::deserialize(&value).unwrap(), message);
+    assert_eq!(Message::deserialize(value).unwrap(), message);
+
+    let r = serde_bencode::bytes::from_bytes::<Message>(&Bytes::from_static(b"d4:datai1ee"));
+    assert!(matches!(r, Err(Error::InvalidType(_))));
+    let r = serde_bencode::bytes::from_bytes::<Message>(&Bytes::from_static(b"d4:data9:abce"));
+    assert!(matches!(r, Err(Error::EndOfStream)));
+    let options = Options::new().strict(true);
+    let r = serde_bencode::bytes::from_bytes_with_options::<Message>(
+        &Bytes::from_static(b"d4:data03:abce"),
+        options,
+    );
+    assert!(matches!(r, Err(Error::NonCanonical(_))));
+
+    let mut buf = bytes::BytesMut::new();
+    serde_bencode::bytes::to_buf_mut(&message, &mut buf).unwrap();
+    serde_bencode::bytes::to_buf_mut(&message, &mut buf).unwrap();
+    assert_eq!(buf.len(), 2 * input.len());
+    assert!(serde_bencode::bytes::to_buf_mut(&1.5, &mut buf).is_err());
+    let mut array = [0u8; 6];
+    let options = SerOptions::new().float(FloatPolicy::String);
+    serde_bencode::bytes::to_buf_mut_with_options(&1.5, &mut &mut array[..], options).unwrap();
+    assert_eq!(&array, b"3:1.5\0");
+}
+
+#[cfg(feature = "bumpalo")]
+#[test]
+fn arena_value() {
+    use bumpalo::Bump;
+    use serde_bencode::arena::{self, Value as ArenaValue};
+
+    let bump = Bump::new();
+    let input = b"d4:spaml1:ai-2ee3:egg3:new3:egg3:olde";
+    let value = arena::from_bytes_in(input, &bump).unwrap();
+    let dict = value.as_dict().unwrap();
+    assert_eq!(dict.len(), 2);
+    assert_eq!(dict[0].0, b"egg");
+    assert_eq!(value.get(b"egg").unwrap().as_str(), Some("old"));
+    assert_eq!(
+        value.get(b"spam").unwrap().as_list().unwrap(),
+        &[ArenaValue::Bytes(b"a"), ArenaValue::Int(-2)]
+    );
+    assert_eq!(value.get(b"ham"), None);
+    assert_eq!(value.to_value(), from_bytes::<Value>(input).unwrap());
+    assert_eq!(to_bytes(&value).unwrap(), b"d3:egg3:old4:spaml1:ai-2eee");
+
+    let options = Options::new().duplicate_keys(DuplicateKeyPolicy::FirstWins);
+    let value = arena::from_bytes_in_with_options(input, options, &bump).unwrap();
+    assert_eq!(value.get(b"egg").unwrap().as_str(), Some("new"));
+    let options = Options::new().max_depth(1);
+    let r = arena::from_bytes_in_with_options(b"lli1eee", options, &bump);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    assert!(matches!(
+        arena::from_bytes_in(b"di1ei2ee", &bump),
+        Err(Error::Syntax(_))
+    ));
+    assert!(matches!(
+        arena::from_bytes_in(b"l1:a", &bump),
+        Err(Error::EndOfStream)
+    ));
+    assert!(matches!(
+        arena::from_bytes_in(b"i1ei2e", &bump),
+        Err(Error::TrailingData)
+    ));
+}
+
+#[cfg(feature = "smallvec")]
+#[test]
+fn value_inline_byte_strings() {
+    let input = b"d4:name4:spam4:path26:abcdefghijklmnopqrstuvwxyz5:\xff\xfe\xfd\xfc\xfbi1ee";
+    let value: Value = from_bytes(input).unwrap();
+    match &value {
+        Value::Dict(map) => assert!(map.keys().all(|k| k.is_inline())),
+        _ => panic!("expected a dictionary"),
+    }
+    assert!(matches!(&value["name"], Value::Bytes(b) if b.is_inline()));
+    assert!(matches!(&value["path"], Value::Bytes(b) if !b.is_inline()));
+    assert_eq!(to_bytes(&value).unwrap(), &input[..]);
+
+    let r: (String, ByteBuf, String) =
+        from_bytes(b"l4:spam2:\xff\xfe26:abcdefghijklmnopqrstuvwxyze").unwrap();
+    assert_eq!(r.0, "spam");
+    assert_eq!(r.1, [0xff, 0xfe][..]);
+    assert_eq!(r.2.len(), 26);
+}
+
+#[cfg(feature = "memmap2")]
+#[test]
+fn mmap_from_file() {
+    use serde_bencode::mmap::{from_file, from_file_with_options, MappedFile};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct File {
+        length: i64,
+        path: Vec<String>,
+    }
+
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("serde_bencode-mmap-{}.torrent", std::process::id()));
+    let input = b"d6:lengthi5e4:pathl1:a1:bee";
+    std::fs::write(&path, input).unwrap();
+
+    // SAFETY: Only this test writes the file, and it does not while the file is mapped.
+    let file: File = unsafe { from_file(&path) }.unwrap();
+    assert_eq!(
+        file,
+        File {
+            length: 5,
+            path: vec!["a".to_string(), "b".to_string()]
+        }
+    );
+    let r: Result<File> = unsafe { from_file_with_options(&path, Options::new().max_depth(1)) };
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+
+    let mapped = unsafe { MappedFile::open(&path) }.unwrap();
+    assert_eq!(mapped.as_bytes(), &input[..]);
+    assert_eq!(mapped.decode::<File>().unwrap(), file);
+    let lazy = mapped.lazy().unwrap();
+    let length = lazy.as_dict().and_then(|dict| dict.get(b"length"));
+    assert_eq!(length.and_then(|length| length.as_int()), Some(5));
+    let raw = lazy.as_dict().unwrap().as_raw();
+    assert_eq!(raw.as_ptr(), mapped.as_bytes().as_ptr());
+    drop(mapped);
+    std::fs::remove_file(&path).unwrap();
+
+    let r: Result<File> = unsafe { from_file(&path) };
+    assert!(matches!(r, Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::NotFound));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli_round_trip() {
+    use std::io::Write;
+    use std::process::{Command, Output, Stdio};
+
+    fn run(args: &[&str], input: &[u8]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bencode"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(input).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    let input = b"d4:infod6:lengthi7e6:pieces2:\x00\xffe4:name1:ae";
+    let json = run(&["to-json", "--bytes", "hex"], input);
+    assert!(json.status.success());
+    assert_eq!(
+        json.stdout,
+        &b"{\"696e666f\":{\"6c656e677468\":7,\"706965636573\":\"00ff\"},\"6e616d65\":\"61\"}\n"[..]
+    );
+    let bencode = run(&["from-json", "--bytes", "hex"], &json.stdout);
+    assert!(bencode.status.success());
+    assert_eq!(bencode.stdout, input);
+
+    assert!(run(&["validate"], input).status.success());
+    let invalid = run(&["validate"], &input[1..]);
+    assert_eq!(invalid.status.code(), Some(1));
+    assert!(!invalid.stderr.is_empty());
+    assert!(!run(&["validate", "--strict"], b"i01e").status.success());
+    let pretty = run(&["pretty"], b"li1e1:ae");
+    assert_eq!(pretty.stdout, b"[\n  1,\n  \"a\"\n]\n");
+    assert_eq!(run(&["frobnicate"], b"").status.code(), Some(2));
+}
+
+#[test]
+fn pretty_print() {
+    use serde_bencode::pretty;
+
+    let input = b"d4:infod5:filesld6:lengthi1e4:pathl1:aeee4:name5:\"a\"\n\x00\
+        6:pieces2:\xff\x00e3:nil0:4:urlslee";
+    let expected = r#"{
+  "info": {
+    "files": [
+      {
+        "length": 1,
+        "path": [
+          "a"
+        ]
+      }
+    ],
+    "name": "\"a\"\n\0",
+    "pieces": <2 bytes ff00>
+  },
+  "nil": "",
+  "urls": []
+}"#;
+    assert_eq!(pretty::to_string(input).unwrap(), expected);
+    assert_eq!(pretty::to_string(b"i-7e").unwrap(), "-7");
+    assert_eq!(pretty::to_string(b"de").unwrap(), "{}");
+
+    let long = to_bytes(&ByteBuf::from(vec![0xff; 40])).unwrap();
+    let expected = format!("<40 bytes {}...>", "ff".repeat(pretty::MAX_HEX_BYTES));
+    assert_eq!(pretty::to_string(&long).unwrap(), expected);
+    assert!(pretty::to_string(b"li1e").is_err());
+    assert!(pretty::to_string(b"i1ei2e").is_err());
+}
+
+#[test]
+fn transcode_self_describing() {
+    let input: &[u8] = b"d1:ai1e1:bl3:abci-5ee1:cd1:xi18446744073709551615ee1:d2:\xff\xfe1:elee";
+    let json = serde_transcode::transcode(
+        &mut Deserializer::from_slice(input),
+        serde_json::value::Serializer,
+    )
+    .unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "a": 1,
+            "b": ["abc", -5],
+            "c": { "x": u64::MAX },
+            "d": [255, 254],
+            "e": [],
+        })
+    );
+
+    let mut ser = Serializer::new();
+    serde_transcode::transcode(&mut Deserializer::from_slice(input), &mut ser).unwrap();
+    assert_eq!(ser.into_vec(), input);
+
+    for invalid in [&b"e"[..], b"di1ei2ee", b"d1:ae", b"l"] {
+        let r = serde_transcode::transcode(
+            &mut Deserializer::from_slice(invalid),
+            serde_json::value::Serializer,
+        );
+        assert!(r.is_err(), "{:?}", invalid);
+    }
+    assert!(matches!(
+        from_bytes::<Value>(b"di1ei2ee"),
+        Err(Error::Syntax(_))
+    ));
+}
+
+#[test]
+fn skip_ignored_fields() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Torrent {
+        announce: String,
+    }
+
+    let mut input = b"d8:announce3:url4:infod6:lengthi4e6:pieces".to_vec();
+    input.extend_from_slice(b"100000:");
+    input.extend_from_slice(&[7; 100000]);
+    input.extend_from_slice(b"5:filesld4:pathl1:aeeee3:zzzli1eee");
+    let expected = Torrent {
+        announce: "url".to_string(),
+    };
+    assert_eq!(from_bytes::<Torrent>(&input).unwrap(), expected);
+    let reader = io::BufReader::new(&input[..]);
+    let r = Torrent::deserialize(&mut Deserializer::new(reader)).unwrap();
+    assert_eq!(r, expected);
+
+    // Skipped values are still checked against the limits and for completeness.
+    let limited = Options::new().max_bytes_len(1000);
+    let r: Result<Torrent> = from_bytes_with_options(&input, limited);
+    assert!(matches!(r, Err(Error::LimitExceeded(_))));
+    let truncated = &input[..1000];
+    assert!(matches!(
+        from_bytes::<Torrent>(truncated),
+        Err(Error::EndOfStream)
+    ));
+    let r = Torrent::deserialize(&mut Deserializer::new(truncated));
+    assert!(matches!(r, Err(Error::EndOfStream)));
+    let strict = Options::new().strict(true);
+    let r: Result<Torrent> =
+        from_bytes_with_options(b"d8:announce3:url4:infod1:bi1e1:ai2eee", strict);
+    assert!(matches!(r, Err(Error::NonCanonical(_))));
+
+    // The bytes of skipped values still reach a digest.
+    let mut info = Vec::new();
+    let mut de = Deserializer::with_digest(
+        SliceRead::new(&input),
+        Options::default(),
+        &["info"],
+        |b: &[u8]| info.extend_from_slice(b),
+    );
+    assert_eq!(Torrent::deserialize(&mut de).unwrap(), expected);
+    de.end().unwrap();
+    drop(de);
+    assert_eq!(info, &input[22..input.len() - 11]);
+}
+
+#[test]
+fn validate_well_formed() {
+    let valid: &[&[u8]] = &[b"i-1e", b"0:", b"le", b"de", b"d1:ad1:bl4:spamee1:ci1ee"];
+    for input in valid {
+        validate(input).unwrap();
+        validate_read(IoRead::new(*input), Options::default()).unwrap();
+    }
+    let invalid: &[&[u8]] = &[
+        b"",
+        b"e",
+        b"i1",
+        b"ie",
+        b"4:spa",
+        b"l",
+        b"li1e",
+        b"di1ei2ee",
+        b"d1:ae",
+        b"i1ee",
+        b"x",
+    ];
+    for input in invalid {
+        assert!(validate(input).is_err(), "{:?}", input);
+        assert!(validate_read(IoRead::new(*input), Options::default()).is_err());
+    }
+    assert!(matches!(validate(b"li1ei2e"), Err(Error::EndOfStream)));
+    assert!(matches!(validate(b"i1ei2e"), Err(Error::TrailingData)));
+
+    let strict = Options::new().strict(true);
+    assert!(validate(b"d1:bi1e1:ai2ee").is_ok());
+    assert!(matches!(
+        validate_with_options(b"d1:bi1e1:ai2ee", strict),
+        Err(Error::NonCanonical(_))
+    ));
+    let limited = Options::new().max_bytes_len(3);
+    assert!(matches!(
+        validate_with_options(b"l4:spame", limited),
+        Err(Error::LimitExceeded(_))
+    ));
+}
+
+#[test]
+fn canonicalize_raw() {
+    let input = b"d4:spaml01:ai-0ei+7ee3:cowd1:zi1e1:adee0:0:e";
+    let canonical = canonicalize(input).unwrap();
+    assert_eq!(canonical, b"d0:0:3:cowd1:ade1:zi1ee4:spaml1:ai0ei7eee");
+    assert_eq!(canonicalize(&canonical).unwrap(), canonical);
+    let strict = Options::new().strict(true);
+    assert!(from_bytes_with_options::<Value>(&canonical, strict).is_ok());
+    // Integers beyond `i64` are kept as they are.
+    assert_eq!(
+        canonicalize(b"i0340282366920938463463374607431768211455e").unwrap(),
+        b"i340282366920938463463374607431768211455e"
+    );
+
+    assert!(matches!(
+        canonicalize(b"d1:ai1e1:bi2e1:ai3ee"),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(canonicalize(b"li1e").is_err());
+    assert!(canonicalize(b"i1ei2e").is_err());
+    let limited = Options::new().max_depth(1);
+    assert!(matches!(
+        canonicalize_with_options(b"llee", limited),
+        Err(Error::LimitExceeded(_))
+    ));
+}
+
+#[test]
+fn duplicate_key_policy() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Torrent {
+        announce: String,
+    }
+
+    let input = b"d8:announce4:good4:infod1:ai1ee8:announce3:bade";
+    let map = |policy| {
+        let options = Options::new().duplicate_keys(policy);
+        from_bytes_with_options::<HashMap<String, Value>>(input, options)
+    };
+    let torrent = |policy| {
+        let options = Options::new().duplicate_keys(policy);
+        from_bytes_with_options::<Torrent>(input, options)
+    };
+
+    assert_eq!(
+        map(DuplicateKeyPolicy::PassThrough).unwrap()["announce"],
+        Value::from("bad")
+    );
+    assert!(torrent(DuplicateKeyPolicy::PassThrough).is_err());
+    assert_eq!(
+        map(DuplicateKeyPolicy::FirstWins).unwrap()["announce"],
+        Value::from("good")
+    );
+    assert_eq!(
+        torrent(DuplicateKeyPolicy::FirstWins).unwrap(),
+        Torrent {
+            announce: "good".to_string(),
+        }
+    );
+    assert!(matches!(
+        map(DuplicateKeyPolicy::Error),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        torrent(DuplicateKeyPolicy::Error),
+        Err(Error::InvalidValue(_))
+    ));
+
+    // Keys are only compared within the same dictionary.
+    let options = Options::new().duplicate_keys(DuplicateKeyPolicy::Error);
+    let nested = b"d1:ad1:ai1ee1:bd1:ai2eee";
+    assert!(from_bytes_with_options::<Value>(nested, options.clone()).is_ok());
+    let r = from_bytes_with_options::<Value>(b"ld1:ai1eed1:ai2eee", options.clone());
+    assert!(r.is_ok());
+    // Skipped values are checked too.
+    let r = from_bytes_with_options::<Torrent>(b"d8:announce1:a4:infod1:ai1e1:ai2eee", options);
+    assert!(matches!(r, Err(Error::InvalidValue(_))));
+}
+
+#[test]
+fn field_errors() {
+    #[derive(Deserialize, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Info {
+        #[allow(dead_code)]
+        length: u64,
+    }
+    #[derive(Deserialize, Debug)]
+    struct Torrent {
+        #[allow(dead_code)]
+        announce: String,
+        #[allow(dead_code)]
+        info: Info,
+    }
+
+    let message = |input: &[u8]| from_bytes::<Torrent>(input).unwrap_err().to_string();
+    assert_eq!(
+        message(b"d8:announcei1e4:infod6:lengthi1eee"),
+        "Invalid Type: integer `1` (expected: `a string`) in field `announce`"
+    );
+    assert_eq!(
+        message(b"d8:announce1:a4:infod6:length1:xee"),
+        "Invalid Type: string \"x\" (expected: `u64`) in field `info.length`"
+    );
+    assert_eq!(
+        message(b"d8:announce1:a4:infodee"),
+        "Missing Field: `length` in field `info`"
+    );
+    assert_eq!(message(b"d8:announce1:ae"), "Missing Field: `info`");
+    assert!(message(b"d8:announce1:a4:infod1:xi1eee").starts_with("Unknown Field: `x`"));
+
+    #[derive(Deserialize, Debug)]
+    struct File {
+        #[allow(dead_code)]
+        length: u64,
+    }
+    #[derive(Deserialize, Debug)]
+    struct MultiFile {
+        #[allow(dead_code)]
+        files: Vec<File>,
+    }
+    #[derive(Deserialize, Debug)]
+    struct MultiFileTorrent {
+        #[allow(dead_code)]
+        info: MultiFile,
+    }
+    let r = from_bytes::<MultiFileTorrent>(
+        b"d4:infod5:filesld6:lengthi1eed6:lengthi2eed6:lengthi-3eeeee",
+    );
+    assert_eq!(
+        r.unwrap_err().to_string(),
+        "Invalid Value: integer `-3` (expected: `u64`) in field `info.files[2].length`"
+    );
+    let r = from_bytes::<Vec<Vec<u8>>>(b"ll1:aee");
+    assert_eq!(
+        r.unwrap_err().to_string(),
+        "Invalid Type: string \"a\" (expected: `u8`) in field `[0][0]`"
+    );
+    let r = from_bytes::<MultiFileTorrent>(b"d4:infod5:filesldeeee");
+    assert_eq!(
+        r.unwrap_err().to_string(),
+        "Missing Field: `length` in field `info.files[0]`"
+    );
+
+    let r = from_bytes::<(i64, i64)>(b"li1ee");
+    assert!(matches!(r, Err(Error::InvalidLength(_))));
+    let r = from_bytes::<(i64, i64)>(b"li1ei2ei3ee");
+    assert!(matches!(r, Err(Error::InvalidLength(_))));
+}
+
+#[test]
+fn error_kinds() {
+    use serde_bencode::error::ErrorKind;
+    use std::error::Error as _;
+
+    let kind = |input: &[u8]| from_bytes::<Value>(input).unwrap_err().kind();
+    assert_eq!(kind(b"x"), ErrorKind::Syntax);
+    assert_eq!(kind(b"i1x2e"), ErrorKind::Syntax);
+    assert_eq!(kind(b"d1:ae"), ErrorKind::Syntax);
+    assert_eq!(kind(b"i1ei2e"), ErrorKind::Syntax);
+    assert_eq!(kind(b"l1:a"), ErrorKind::Eof);
+    let options = Options::new().strict(true);
+    let error = from_bytes_with_options::<Value>(b"i01e", options).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Syntax);
+    let options = Options::new().max_depth(1);
+    let error = from_bytes_with_options::<Value>(b"llee", options).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Limit);
+
+    let error = from_bytes::<u8>(b"i300e").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Data);
+    assert_eq!(to_bytes(&1.5).unwrap_err().kind(), ErrorKind::Data);
+    let error = from_bytes::<std::net::Ipv4Addr>(b"3:abc").unwrap_err();
+    assert!(matches!(error, Error::Custom(_)));
+    assert_eq!(error.kind(), ErrorKind::Custom);
+
+    // A syntax error keeps the path to where it occurred.
+    let error = from_bytes::<HashMap<String, Vec<i64>>>(b"d1:ali1ei?eee").unwrap_err();
+    assert_eq!(error.to_string(), "Can't parse `?` as integer in field `a`");
+    assert!(error.source().is_none());
+
+    let error = Error::from(io::Error::from(io::ErrorKind::ConnectionReset));
+    assert_eq!(error.kind(), ErrorKind::Io);
+    let source = error.source().unwrap().downcast_ref::<io::Error>().unwrap();
+    assert_eq!(source.kind(), io::ErrorKind::ConnectionReset);
+}
+
+#[test]
+fn ser_de_float_policy() {
+    assert!(to_bytes(&1.0f64).is_err());
+    assert!(from_str::<f64>("i1e").is_err());
+
+    let ser = SerOptions::new().float(FloatPolicy::Integer);
+    let de = Options::new().float(FloatPolicy::Integer);
+    let bytes = to_bytes_with_options(&(2.0f64, -3.0f32), ser.clone()).unwrap();
+    assert_eq!(bytes, b"li2ei-3ee");
+    let r: (f64, f32) = from_bytes_with_options(&bytes, de.clone()).unwrap();
+    assert_eq!(r, (2.0, -3.0));
+    assert!(to_bytes_with_options(&2.5f64, ser.clone()).is_err());
+    assert!(to_bytes_with_options(&f64::NAN, ser.clone()).is_err());
+    assert!(to_bytes_with_options(&1e19f64, ser).is_err());
+    assert!(from_bytes_with_options::<f64>(b"i9007199254740993e", de.clone()).is_err());
+    let r: f32 = from_bytes_with_options(b"i16777216e", de.clone()).unwrap();
+    assert_eq!(r, 16_777_216.0);
+    assert!(from_bytes_with_options::<f32>(b"i16777217e", de.clone()).is_err());
+    let r: f64 = from_bytes_with_options(b"i16777217e", de.clone()).unwrap();
+    assert_eq!(r, 16_777_217.0);
+    assert!(from_bytes_with_options::<f64>(b"3:2.5", de).is_err());
+
+    let ser = SerOptions::new().float(FloatPolicy::String);
+    let de = Options::new().float(FloatPolicy::String);
+    let bytes = to_bytes_with_options(&(2.5f64, 0.1f32), ser.clone()).unwrap();
+    assert_eq!(bytes, b"l3:2.53:0.1e");
+    let r: (f64, f32) = from_bytes_with_options(&bytes, de.clone()).unwrap();
+    assert_eq!(r, (2.5, 0.1));
+    assert!(to_bytes_with_options(&f64::INFINITY, ser).is_err());
+    assert!(from_bytes_with_options::<f64>(b"3:inf", de.clone()).is_err());
+    assert!(from_bytes_with_options::<f64>(b"i2e", de).is_err());
+}
+
+#[test]
+fn ser_de_wide_integers() {
+    test_ser_de_eq(u64::MAX);
+    test_ser_de_eq(i64::MIN);
+    test_ser_de_eq(i128::MIN);
+    test_ser_de_eq(i128::MAX);
+    test_ser_de_eq(u128::MAX);
+    test_ser_de_eq((u64::MAX, -1i128, u128::MAX / 3));
+    assert_eq!(
+        to_string(&u128::MAX).unwrap(),
+        "i340282366920938463463374607431768211455e"
+    );
+    let r: u64 = from_str("i18446744073709551615e").unwrap();
+    assert_eq!(r, u64::MAX);
+    let r: i128 = from_str("i-170141183460469231731687303715884105728e").unwrap();
+    assert_eq!(r, i128::MIN);
+}
+
+#[test]
+fn deserialize_out_of_range_integers() {
+    assert!(from_str::<u64>("i18446744073709551616e").is_err());
+    assert!(matches!(
+        from_str::<u8>("i256e"),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(from_str::<u128>("i340282366920938463463374607431768211456e").is_err());
+    // Without arbitrary precision, `Value` only holds `i64` integers, so larger ones must not
+    // wrap around.
+    #[cfg(not(feature = "arbitrary_precision"))]
+    assert!(from_str::<Value>("i9223372036854775808e").is_err());
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn ser_de_arbitrary_precision_integers() {
+    use serde_bencode::value::BigInt;
+
+    let huge = "i-1000000000000000000000000000000000000000000e";
+    let r: Value = from_str(huge).unwrap();
+    match &r {
+        Value::BigInt(n) => assert_eq!(n.as_str(), &huge[1..huge.len() - 1]),
+        r => panic!("unexpected value: {:?}", r),
+    }
+    test_value_de_ser(huge);
+    test_value_de_ser("li18446744073709551615ei340282366920938463463374607431768211456ee");
+
+    let r: Vec<BigInt> =
+        from_str("li1ei-0000000000000000000000000000000000000000000000000042ee").unwrap();
+    assert_eq!(r, vec![BigInt::from(1), BigInt::from(-42)]);
+    assert!(from_str::<BigInt>("3:abc").is_err());
+    assert!("-0".parse::<BigInt>().is_err());
+    assert!("012".parse::<BigInt>().is_err());
+    assert_eq!("-12".parse::<BigInt>().unwrap().as_i64(), Some(-12));
+}
+
+#[cfg(feature = "tokio")]
+mod tokio_io {
+    use super::*;
+    use serde_bencode::de::{from_async_reader, AsyncBencodeDecoder};
+    use serde_bencode::ser::to_async_writer;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    /// An async reader that returns one byte per poll, and is pending before every byte.
+    struct TrickleAsyncReader<'a> {
+        data: &'a [u8],
+        pending: bool,
+    }
+
+    impl AsyncRead for TrickleAsyncReader<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            self.pending = !self.pending;
+            if self.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            if let Some((&first, rest)) = self.data.split_first() {
+                buf.put_slice(&[first]);
+                self.data = rest;
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn async_decode_stream() {
+        let reader = TrickleAsyncReader {
+            data: b"d1:ai1ee4:spamli1ei2ee",
+            pending: false,
+        };
+        let mut decoder = AsyncBencodeDecoder::new(reader);
+        let r: Value = decoder.decode().await.unwrap().unwrap();
+        assert_eq!(r, Value::Dict(Map::from([(b"a".to_vec(), 1.into())])));
+        // A value of the wrong type is skipped over.
+        assert!(decoder.decode::<i64>().await.is_err());
+        let r: Vec<i64> = decoder.decode().await.unwrap().unwrap();
+        assert_eq!(r, vec![1, 2]);
+        assert!(decoder.decode::<Value>().await.unwrap().is_none());
+
+        let mut decoder = AsyncBencodeDecoder::new(&b"l4:sp"[..]);
+        assert!(matches!(
+            decoder.decode::<Value>().await,
+            Err(Error::EndOfStream)
+        ));
+    }
+
+    #[tokio::test]
+    async fn async_decode_hand_off() {
+        let input = b"d1:ai1ee4:spam\xff\x00rest";
+        let mut decoder = AsyncBencodeDecoder::new(&input[..]);
+        let _: Value = decoder.decode().await.unwrap().unwrap();
+        assert_eq!(decoder.bytes_consumed(), 8);
+        let r: String = decoder.decode().await.unwrap().unwrap();
+        assert_eq!(r, "spam");
+        assert_eq!(decoder.bytes_consumed(), 14);
+        assert_eq!(decoder.buffered(), b"\xff\x00rest");
+
+        let reader = TrickleAsyncReader {
+            data: input,
+            pending: false,
+        };
+        let mut decoder = AsyncBencodeDecoder::new(reader);
+        let _: Value = decoder.decode().await.unwrap().unwrap();
+        let (mut reader, mut rest) = decoder.into_inner();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut rest)
+            .await
+            .unwrap();
+        assert_eq!(rest, &input[8..]);
+    }
+
+    #[tokio::test]
+    async fn async_decode_limits() {
+        let options = Options::new().max_input_len(16);
+        let mut decoder = AsyncBencodeDecoder::with_options(&b"100:"[..], options);
+        assert!(matches!(
+            decoder.decode::<Value>().await,
+            Err(Error::LimitExceeded(_))
+        ));
+        let options = Options::new().max_depth(2);
+        let mut decoder = AsyncBencodeDecoder::with_options(&b"llli1eeee"[..], options);
+        assert!(matches!(
+            decoder.decode::<Value>().await,
+            Err(Error::LimitExceeded(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn async_from_reader_and_to_writer() {
+        let mut out = Vec::new();
+        to_async_writer(&mut out, &vec!["a", "b"]).await.unwrap();
+        assert_eq!(out, b"l1:a1:be");
+        let r: Vec<String> = from_async_reader(&out[..]).await.unwrap();
+        assert_eq!(r, vec!["a", "b"]);
+        assert!(matches!(
+            from_async_reader::<_, Value>(&b"i1ei2e"[..]).await,
+            Err(Error::TrailingData)
+        ));
+    }
+}